@@ -1,8 +1,15 @@
 // src/app_actions.rs
 
-use crate::persistence::{ImageMeta, NadeType};
+use crate::persistence::{
+    ImageMeta, ImageRole, MapConfig, NadeType, Setpos, Side, Strat, TechniqueFilter, ThrowTechnique,
+};
+use crate::services::export_service::{ImportPlan, ImportedLineups};
+use crate::services::image_service::BulkEdit;
+use crate::services::integrity_service::{IntegrityIssue, RepairAction};
+use crate::services::persistence_service::ManifestRecoveryChoice;
 use crate::ui::edit_view::EditFormData; // Added import
 use std::path::PathBuf; // Added import
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub enum AppAction {
@@ -11,9 +18,13 @@ pub enum AppAction {
 
     // --- Upload Actions ---
     SubmitUpload {
-        file_path: PathBuf,
+        image_files: Vec<(PathBuf, ImageRole)>, // Lineup steps in order
         map_name: String,
         nade_type: NadeType,
+        side: Side,
+        technique: ThrowTechnique,
+        tags: Vec<String>,
+        setpos: Option<Setpos>, // Throw position and view angles from `getpos`
         position: String,
         notes: String,
     },
@@ -37,17 +48,54 @@ pub enum AppAction {
     SetGridImageSize(f32),
     ShowUploadModal,
     SetNadeFilter(Option<NadeType>),
-    ImageGridImageClicked(ImageMeta),
+    SetSideFilter(Option<Side>),
+    SetTechniqueFilter(TechniqueFilter),
+    SetTagFilter(Vec<String>),
+    SetSearchQuery(String),
+    // Map list management
+    ShowMapManager,
+    CloseMapManager,
+    UpdateMapConfig(MapConfig),
+    // Radar panel
+    ToggleRadarPanel,
+    SetRadarImage {
+        map_name: String,
+        path: PathBuf,
+    },
+    // Practice config window
+    ShowPracticeConfig(Vec<String>), // Preselected image ids
+    ClosePracticeConfig,
+    ImageGridImageClicked(String), // Image id
     ToggleReorderMode,
+    // Grid selection and the selection bar's bulk actions
+    SelectImages {
+        image_ids: Vec<String>, // The whole new selection, empty to clear it
+        anchor: Option<String>, // Lineup a Shift-click extends the selection from
+    },
+    BulkEditImages {
+        image_ids: Vec<String>,
+        edit: BulkEdit,
+    },
+    DeleteImages(Vec<String>), // Image ids, already confirmed
+    ExportSelection,           // Opens the sharing view on the selected lineups
+    MoveImagesToMap {
+        image_ids: Vec<String>,
+        map: String,
+        copy: bool, // Add copies to `map` and keep the originals
+    },
     ReorderImage {
         from_index: usize,
         to_index: usize,
     },
+    // Manifest history (Ctrl+Z / Ctrl+Shift+Z)
+    Undo,
+    Redo,
 
     // --- Detail Modal Actions ---
     DetailModalClose,
-    DetailModalRequestEdit(ImageMeta),
-    DetailModalRequestDelete(ImageMeta),
+    DetailModalSelectStep(usize),     // Index into `ImageMeta::steps`
+    DetailModalRequestEdit(String),   // Image id
+    DetailModalRequestDelete(String), // Image id
 
     // --- Edit Modal Actions ---
     EditModalSave(EditFormData),
@@ -57,8 +105,56 @@ pub enum AppAction {
     DeleteConfirm,
     DeleteCancel,
 
+    // --- Tag Manager Actions ---
+    ShowTagManager,
+    CloseTagManager,
+    RenameTag {
+        from: String,
+        to: String, // Merges into `to` if that tag already exists
+    },
+
+    // --- Strat Book Actions ---
+    ToggleStratPanel,
+    EditStrat(Option<String>), // Strat id, or `None` for a new strat on the current map
+    SaveStrat(Strat),
+    CloseStratEditor,
+    DeleteStrat(String),      // Strat id
+    PlayStrat(String),        // Strat id
+    StratPlaybackStep(usize), // Index into `Strat::timeline`
+    CloseStratPlayback,
+
     // --- Sharing Actions ---
     ShowSharingView,
+    // Library backups window
+    ShowBackups,
+    CloseBackups,
+    RestoreManifestBackup(usize), // Backup generation, 1 is the most recent
+    // Trash window
+    ShowTrash,
+    CloseTrash,
+    RestoreFromTrash(String),   // Image id
+    PurgeTrash(Option<String>), // Image id, or `None` to empty the trash
+    SetTrashRetention(u32),     // Days, 0 keeps lineups until the trash is emptied
+    // Library check window
+    ShowIntegrityCheck, // Opens the window and starts a check in the background
+    CloseIntegrityCheck,
+    IntegrityCheckCompleted(Result<Vec<IntegrityIssue>, String>),
+    RepairIntegrityIssue(IntegrityIssue, RepairAction),
+    // Library recovery dialog, after manifest.json failed to load
+    ResolveManifestRecovery(ManifestRecoveryChoice),
+    // Sent from the sharing view's export/import threads to the main thread
+    ExportProgress {
+        done: usize, // Image files written
+        total: usize,
+    },
+    ExportCompleted(Result<String, String>), // Status message or error
+    ImportProgress {
+        done: usize, // Package files read, then images moved into the library
+        total: usize,
+    },
+    ImportPreviewReady(Arc<ImportPlan>), // The package was read and awaits the user's review
+    // Lineups whose images are stored, for the main thread to merge, or an error
+    ImportCompleted(Result<Arc<ImportedLineups>, String>),
     // Add other action categories and specific actions as needed
     // Example: Modal Actions, etc.
 }
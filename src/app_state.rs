@@ -1,17 +1,22 @@
 // src/app_state.rs
 
 use crate::app_actions::AppAction; // Added for channel type
-use crate::persistence::{ImageManifest, ImageMeta, NadeType};
+use crate::history::History;
+use crate::persistence::{
+    ImageManifest, ImageMeta, MapConfig, NadeType, Side, Strat, TechniqueFilter, Trash,
+};
 use std::sync::mpsc; // Added for channel
 
 use crate::services::image_service::ImageService;
-use crate::services::persistence_service::PersistenceService;
+use crate::services::integrity_service::IntegrityIssue;
+use crate::services::persistence_service::{ManifestRecovery, PersistenceService};
 use crate::services::thumbnail_service::{
     ConcreteThumbnailService as ThumbnailService, ThumbnailLoadJob, ThumbnailLoadResult,
     spawn_thumbnail_worker_thread,
 };
 use crate::ui::edit_view::EditFormData; // Assuming EditFormData is pub
 use eframe::egui;
+use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex}; // Added for Arc and Mutex
 
@@ -21,18 +26,52 @@ use std::sync::{Arc, Mutex}; // Added for Arc and Mutex
 pub struct AppState {
     // Filtering UI state
     pub selected_nade_type: Option<NadeType>,
+    pub selected_side: Option<Side>,
+    pub technique_filter: TechniqueFilter,
+    pub selected_tags: Vec<String>, // Lineups must carry every selected tag
+    // Search UI state
+    pub search_query: String,
+    pub search_results: Vec<ImageMeta>, // Ranked matches from every map
     // Reordering UI state
     pub reorder_mode: bool,
+    // Lineups selected in the grid for bulk actions, and the one a Shift-click extends from
+    pub selected_images: HashSet<String>,
+    pub selection_anchor: Option<String>,
+    // Undo/redo of manifest edits made this session
+    pub history: History,
     // Upload modal state
     pub show_upload_modal: bool, // This flag will be used by NadexApp to control UploadModal visibility
     // Sharing UI state
     pub show_sharing_view: bool,
+    // Tag manager state
+    pub show_tag_manager: bool,
+    // Map manager state
+    pub show_map_manager: bool,
+    // Library backups window
+    pub show_backups: bool,
+    // Trash window, and the deleted lineups it lists (kept in step with `.trash/trash.json`)
+    pub show_trash: bool,
+    pub trash: Trash,
+    // Library check window
+    pub show_integrity_check: bool,
+    pub integrity_report: Option<Result<Vec<IntegrityIssue>, String>>, // `None` while a check runs
+    // Set when manifest.json failed to load, until the user picks what to continue with
+    pub manifest_recovery: Option<ManifestRecovery>,
+    // Radar panel state
+    pub show_radar_panel: bool,
+    pub radar_texture: Option<(PathBuf, egui::TextureHandle)>, // Cached radar of the current map, keyed by file
+    // Strat book state
+    pub show_strat_panel: bool,
+    pub editing_strat: Option<Strat>, // Draft in the strat editor, saved through `AppAction::SaveStrat`
+    pub strat_playback: Option<(String, usize)>, // Strat id and the `Strat::timeline` group shown
+    // Practice config window state; `Some` while open, holding the chosen lineup ids
+    pub practice_config_selection: Option<Vec<String>>,
     pub is_processing_upload: bool,
     pub current_map: String,
     pub current_map_images: Vec<ImageMeta>,
 
-    // List of available maps
-    pub maps: Vec<&'static str>,
+    // User-configurable map list (order, labels, hidden maps), persisted in maps.json
+    pub map_config: MapConfig,
     // Map of map name -> Vec of image file names (not full paths)
     pub image_manifest: ImageManifest,
     // For displaying error messages
@@ -48,6 +87,7 @@ pub struct AppState {
     pub edit_form_data: Option<EditFormData>,
     pub show_delete_confirmation: Option<ImageMeta>,
     pub detail_view_error: Option<String>,
+    pub detail_step: usize, // Which step of the selected lineup the detail view shows
 
     // Services
     pub persistence_service: Arc<PersistenceService>,
@@ -82,36 +122,78 @@ impl AppState {
         let ts_clone_for_is = Arc::clone(&thumbnail_service);
         let image_service = Arc::new(ImageService::new(ps_clone_for_is, ts_clone_for_is));
 
-        let mut manifest = persistence_service.load_manifest();
+        // A manifest that fails to load is moved aside and the user picks a replacement in
+        // the recovery dialog; until then the library stays empty and nothing is saved
+        let (manifest, manifest_recovery) = match persistence_service.load_manifest() {
+            Ok(manifest) => (manifest, None),
+            Err(e) => {
+                log::error!("Failed to load manifest.json: {}", e);
+                let recovery = persistence_service.recover_manifest(&e);
+                (ImageManifest::default(), Some(recovery))
+            }
+        };
 
-        // Migrate existing images to have proper order values for backward compatibility
-        manifest.migrate_image_order();
+        // Lineups deleted longer ago than the user keeps them are purged; their images go below
+        let mut trash = persistence_service.load_trash();
+        let purged = trash.purge_expired(std::time::SystemTime::now());
+        if purged > 0 {
+            log::info!("Purged {} lineups from the trash", purged);
+            if let Err(e) = persistence_service.save_trash(&trash) {
+                log::error!("Failed to save the trash: {}", e);
+            }
+        }
 
-        // Save the migrated manifest if any changes were made
-        let _ = persistence_service.save_manifest(&manifest);
+        // Nothing is being stored yet, so images no manifest uses can go. Not while the
+        // library is being recovered: the replacement may need them.
+        if manifest_recovery.is_none()
+            && let Err(e) = persistence_service.collect_garbage()
+        {
+            log::error!("Failed to remove unused images: {}", e);
+        }
+
+        // Maps with lineups that aren't in the map list yet (e.g. from older versions) get added
+        let mut map_config = persistence_service.load_map_config();
+        if map_config.discover_maps(&manifest) {
+            let _ = persistence_service.save_map_config(&map_config);
+        }
+        let current_map = map_config
+            .visible_maps()
+            .next()
+            .map_or_else(|| "de_ancient".to_string(), |entry| entry.name.clone());
 
         let (tx, rx) = mpsc::channel::<AppAction>();
 
         Self {
             selected_nade_type: None,
+            selected_side: None,
+            technique_filter: TechniqueFilter::default(),
+            selected_tags: Vec::new(),
+            search_query: String::new(),
+            search_results: Vec::new(),
             reorder_mode: false,
-            current_map: "de_ancient".to_string(),
+            selected_images: HashSet::new(),
+            selection_anchor: None,
+            history: History::default(),
+            current_map,
             current_map_images: Vec::new(),
             show_upload_modal: false, // Managed by NadexApp
             show_sharing_view: false, // Flag to control sharing view visibility
+            show_tag_manager: false,
+            show_map_manager: false,
+            show_backups: false,
+            show_trash: false,
+            trash,
+            show_integrity_check: false,
+            integrity_report: None,
+            manifest_recovery,
+            show_radar_panel: false,
+            show_strat_panel: false,
+            editing_strat: None,
+            strat_playback: None,
+            practice_config_selection: None,
+            radar_texture: None,
             is_processing_upload: false,
-            maps: vec![
-                "de_ancient",
-                "de_anubis",
-                "de_cache",
-                "de_dust2",
-                "de_inferno",
-                "de_mirage",
-                "de_nuke",
-                "de_overpass",
-                "de_train",
-                "de_vertigo",
-            ],
+            map_config,
             image_manifest: manifest,
             error_message: None,
             data_dir,               // Comes from initialization above
@@ -123,6 +205,7 @@ impl AppState {
             edit_form_data: None,
             show_delete_confirmation: None,
             detail_view_error: None,
+            detail_step: 0,
             persistence_service, // Add the initialized service (now Arc-wrapped)
             image_service,       // Add the initialized service (now Arc-wrapped)
             thumbnail_service,   // Use the thumbnail_service initialized earlier
@@ -136,6 +219,49 @@ impl AppState {
         self.current_map_images = self
             .image_service
             .get_images_for_map_sorted(&self.image_manifest, &self.current_map);
+        self.search_results = self
+            .image_service
+            .search_images(&self.image_manifest, &self.search_query);
+    }
+
+    /// Lineups the grid shows: the current map (or the search results while searching)
+    /// narrowed by the nade type, side, throw technique and tag filters.
+    pub fn visible_images(&self) -> Vec<&ImageMeta> {
+        let source_images = if self.search_query.trim().is_empty() {
+            &self.current_map_images
+        } else {
+            &self.search_results
+        };
+        source_images
+            .iter()
+            .filter(|meta| {
+                self.selected_nade_type.is_none() || self.selected_nade_type == Some(meta.nade_type)
+            })
+            .filter(|meta| {
+                self.selected_side
+                    .is_none_or(|side_filter| meta.side.matches(side_filter))
+            })
+            .filter(|meta| self.technique_filter.matches(&meta.technique))
+            .filter(|meta| self.selected_tags.iter().all(|tag| meta.tags.contains(tag)))
+            .collect()
+    }
+
+    /// The selected lineups the grid currently shows, in grid order. Selected lineups hidden
+    /// by a filter are left out of bulk actions.
+    pub fn selected_visible_images(&self) -> Vec<&ImageMeta> {
+        self.visible_images()
+            .into_iter()
+            .filter(|meta| self.selected_images.contains(&meta.id))
+            .collect()
+    }
+
+    /// Add maps that gained lineups (e.g. through an import) to the map list and persist it.
+    pub fn sync_map_config(&mut self) {
+        if self.map_config.discover_maps(&self.image_manifest)
+            && let Err(e) = self.persistence_service.save_map_config(&self.map_config)
+        {
+            log::error!("Failed to save map list: {}", e);
+        }
     }
 }
 
@@ -143,11 +269,21 @@ impl std::fmt::Debug for AppState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AppState")
             .field("selected_nade_type", &self.selected_nade_type)
+            .field("selected_side", &self.selected_side)
+            .field("technique_filter", &self.technique_filter)
+            .field("selected_tags", &self.selected_tags)
+            .field("search_query", &self.search_query)
+            .field("search_results", &self.search_results)
             .field("show_upload_modal", &self.show_upload_modal)
+            .field("show_radar_panel", &self.show_radar_panel)
+            .field("show_strat_panel", &self.show_strat_panel)
+            .field("editing_strat", &self.editing_strat)
+            .field("strat_playback", &self.strat_playback)
+            .field("practice_config_selection", &self.practice_config_selection)
             .field("is_processing_upload", &self.is_processing_upload)
             .field("current_map", &self.current_map)
             .field("current_map_images", &self.current_map_images)
-            .field("maps", &self.maps)
+            .field("map_config", &self.map_config)
             .field("image_manifest", &self.image_manifest)
             .field("error_message", &self.error_message)
             .field("data_dir", &self.data_dir)
@@ -165,6 +301,7 @@ impl std::fmt::Debug for AppState {
             .field("edit_form_data", &self.edit_form_data)
             .field("show_delete_confirmation", &self.show_delete_confirmation)
             .field("detail_view_error", &self.detail_view_error)
+            .field("detail_step", &self.detail_step)
             .field("persistence_service", &self.persistence_service) // Add persistence_service
             // Skipping sender/receiver in Debug
             .finish()
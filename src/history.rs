@@ -0,0 +1,415 @@
+// src/history.rs
+use crate::persistence::{ImageManifest, ImageMeta, MapMeta, Strat};
+use std::collections::{BTreeSet, HashMap};
+
+/// Undo steps kept; older ones are dropped as new edits come in
+pub const HISTORY_LIMIT: usize = 50;
+
+/// One record in the manifest, as it was before and after an operation. `None` means the
+/// record didn't exist on that side.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Image {
+        id: String,
+        before: Option<Box<ImageMeta>>,
+        after: Option<Box<ImageMeta>>,
+    },
+    Strat {
+        id: String,
+        before: Option<Box<Strat>>,
+        after: Option<Box<Strat>>,
+    },
+    Map {
+        name: String,
+        before: Option<MapMeta>,
+        after: Option<MapMeta>,
+    },
+}
+
+impl Change {
+    fn inverse(&self) -> Self {
+        match self.clone() {
+            Change::Image { id, before, after } => Change::Image {
+                id,
+                before: after,
+                after: before,
+            },
+            Change::Strat { id, before, after } => Change::Strat {
+                id,
+                before: after,
+                after: before,
+            },
+            Change::Map {
+                name,
+                before,
+                after,
+            } => Change::Map {
+                name,
+                before: after,
+                after: before,
+            },
+        }
+    }
+
+    /// Put the `after` side into `manifest`, whatever the record looks like there now
+    fn apply(&self, manifest: &mut ImageManifest) {
+        match self {
+            Change::Image { id, after, .. } => {
+                // Replaced in place when the lineup stays on its map, so the manifest keeps its order
+                let current_map = manifest
+                    .images
+                    .iter()
+                    .find(|(_, images)| images.iter().any(|img| img.id == *id))
+                    .map(|(map, _)| map.clone());
+                if let (Some(map), Some(after)) = (&current_map, after)
+                    && *map == after.map
+                    && let Some(slot) = manifest
+                        .images
+                        .get_mut(map)
+                        .and_then(|images| images.iter_mut().find(|img| img.id == *id))
+                {
+                    *slot = (**after).clone();
+                    return;
+                }
+                if let Some(map) = current_map {
+                    let images = manifest.images.entry(map.clone()).or_default();
+                    images.retain(|img| img.id != *id);
+                    if images.is_empty() {
+                        manifest.images.remove(&map);
+                    }
+                }
+                if let Some(after) = after {
+                    // Back in its place by grid order, e.g. when a delete is undone
+                    let images = manifest.images.entry(after.map.clone()).or_default();
+                    let index = images
+                        .iter()
+                        .position(|img| img.order > after.order)
+                        .unwrap_or(images.len());
+                    images.insert(index, (**after).clone());
+                }
+            }
+            Change::Strat { id, after, .. } => {
+                let position = manifest.strats.iter().position(|strat| strat.id == *id);
+                match (position, after) {
+                    (Some(index), Some(after)) => manifest.strats[index] = (**after).clone(),
+                    (Some(index), None) => {
+                        manifest.strats.remove(index);
+                    }
+                    (None, Some(after)) => manifest.strats.push((**after).clone()),
+                    (None, None) => {}
+                }
+            }
+            Change::Map { name, after, .. } => match after {
+                Some(meta) => {
+                    manifest.maps.insert(name.clone(), meta.clone());
+                }
+                None => {
+                    manifest.maps.remove(name);
+                }
+            },
+        }
+    }
+}
+
+/// A recorded edit to the manifest, e.g. a lineup saved from the edit modal. Applying the
+/// inverse takes the manifest back to how it was before.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub description: String, // Shown in the undo/redo tooltips, e.g. "delete lineup"
+    changes: Vec<Change>,
+}
+
+impl Operation {
+    /// The operation that turns `before` into `after`, or `None` if nothing changed
+    pub fn between(
+        description: impl Into<String>,
+        before: &ImageManifest,
+        after: &ImageManifest,
+    ) -> Option<Self> {
+        let mut changes = Vec::new();
+
+        let images_by_id = |manifest: &ImageManifest| -> HashMap<String, ImageMeta> {
+            manifest
+                .images
+                .values()
+                .flatten()
+                .map(|img| (img.id.clone(), img.clone()))
+                .collect()
+        };
+        let (mut old_images, mut new_images) = (images_by_id(before), images_by_id(after));
+        let ids: BTreeSet<String> = old_images
+            .keys()
+            .chain(new_images.keys())
+            .cloned()
+            .collect();
+        for id in ids {
+            let (old, new) = (old_images.remove(&id), new_images.remove(&id));
+            if old != new {
+                changes.push(Change::Image {
+                    id,
+                    before: old.map(Box::new),
+                    after: new.map(Box::new),
+                });
+            }
+        }
+
+        let strats_by_id = |manifest: &ImageManifest| -> HashMap<String, Strat> {
+            manifest
+                .strats
+                .iter()
+                .map(|strat| (strat.id.clone(), strat.clone()))
+                .collect()
+        };
+        let (mut old_strats, mut new_strats) = (strats_by_id(before), strats_by_id(after));
+        let ids: BTreeSet<String> = old_strats
+            .keys()
+            .chain(new_strats.keys())
+            .cloned()
+            .collect();
+        for id in ids {
+            let (old, new) = (old_strats.remove(&id), new_strats.remove(&id));
+            if old != new {
+                changes.push(Change::Strat {
+                    id,
+                    before: old.map(Box::new),
+                    after: new.map(Box::new),
+                });
+            }
+        }
+
+        let names: BTreeSet<&String> = before.maps.keys().chain(after.maps.keys()).collect();
+        for name in names {
+            let (old, new) = (before.maps.get(name), after.maps.get(name));
+            if old != new {
+                changes.push(Change::Map {
+                    name: name.clone(),
+                    before: old.cloned(),
+                    after: new.cloned(),
+                });
+            }
+        }
+
+        if changes.is_empty() {
+            return None;
+        }
+        Some(Self {
+            description: description.into(),
+            changes,
+        })
+    }
+
+    pub fn apply(&self, manifest: &mut ImageManifest) {
+        for change in &self.changes {
+            change.apply(manifest);
+        }
+    }
+
+    /// Lineups applying this takes out of the manifest
+    pub fn removed_images(&self) -> impl Iterator<Item = &ImageMeta> {
+        self.changes.iter().filter_map(|change| match change {
+            Change::Image {
+                before: Some(before),
+                after: None,
+                ..
+            } => Some(&**before),
+            _ => None,
+        })
+    }
+
+    /// Lineups applying this puts into the manifest
+    pub fn added_images(&self) -> impl Iterator<Item = &ImageMeta> {
+        self.changes.iter().filter_map(|change| match change {
+            Change::Image {
+                before: None,
+                after: Some(after),
+                ..
+            } => Some(&**after),
+            _ => None,
+        })
+    }
+
+    /// The operation that undoes this one
+    pub fn inverse(&self) -> Self {
+        Self {
+            description: self.description.clone(),
+            changes: self.changes.iter().rev().map(Change::inverse).collect(),
+        }
+    }
+}
+
+/// Undo and redo stacks of the operations made this session
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Operation>,
+    redo: Vec<Operation>,
+}
+
+impl History {
+    /// Remember a new operation. Anything that was undone can't be redone after it.
+    pub fn record(&mut self, operation: Operation) {
+        self.redo.clear();
+        self.undo.push(operation);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    /// The operation to apply to undo the latest one, which moves to the redo stack
+    pub fn undo(&mut self) -> Option<Operation> {
+        let operation = self.undo.pop()?;
+        let inverse = operation.inverse();
+        self.redo.push(operation);
+        Some(inverse)
+    }
+
+    /// The operation to apply to redo the latest undone one
+    pub fn redo(&mut self) -> Option<Operation> {
+        let operation = self.redo.pop()?;
+        self.undo.push(operation.clone());
+        Some(operation)
+    }
+
+    pub fn next_undo(&self) -> Option<&str> {
+        self.undo.last().map(|op| op.description.as_str())
+    }
+
+    pub fn next_redo(&self) -> Option<&str> {
+        self.redo.last().map(|op| op.description.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::new_image_id;
+    use std::time::SystemTime;
+
+    fn lineup(map: &str, position: &str, order: usize) -> ImageMeta {
+        ImageMeta {
+            id: new_image_id(),
+            filename: format!("{}.webp", position),
+            map: map.to_string(),
+            position: position.to_string(),
+            order,
+            ..Default::default()
+        }
+    }
+
+    fn sample_manifest() -> ImageManifest {
+        let mut manifest = ImageManifest::default();
+        let window = lineup("de_mirage", "window", 0);
+        let jungle = lineup("de_mirage", "jungle", 1);
+        let mut strat = Strat::new("A execute", "de_mirage");
+        strat.add_lineup(&window.id);
+        manifest.strats.push(strat);
+        manifest
+            .images
+            .insert("de_mirage".to_string(), vec![window, jungle]);
+        manifest.maps.insert(
+            "de_mirage".to_string(),
+            MapMeta {
+                last_accessed: SystemTime::UNIX_EPOCH,
+            },
+        );
+        manifest
+    }
+
+    #[test]
+    fn test_unchanged_manifest_records_nothing() {
+        let manifest = sample_manifest();
+        assert_eq!(
+            Operation::between("edit", &manifest, &manifest.clone()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_undo_and_redo_delete() {
+        let before = sample_manifest();
+        let window_id = before.images["de_mirage"][0].id.clone();
+        let mut after = before.clone();
+        after.images.get_mut("de_mirage").unwrap().remove(0);
+        after.remove_image_from_strats(&window_id);
+
+        let mut history = History::default();
+        history.record(Operation::between("delete lineup", &before, &after).unwrap());
+        assert_eq!(history.next_undo(), Some("delete lineup"));
+
+        let mut manifest = after.clone();
+        history.undo().unwrap().apply(&mut manifest);
+        assert_eq!(manifest, before);
+        assert_eq!(history.next_redo(), Some("delete lineup"));
+
+        history.redo().unwrap().apply(&mut manifest);
+        assert_eq!(manifest, after);
+        assert!(history.next_redo().is_none());
+    }
+
+    #[test]
+    fn test_undo_edit_that_moved_lineup_to_another_map() {
+        let before = sample_manifest();
+        let mut after = before.clone();
+        let mut moved = after.images.get_mut("de_mirage").unwrap().remove(1);
+        moved.map = "de_inferno".to_string();
+        after.images.insert("de_inferno".to_string(), vec![moved]);
+
+        let operation = Operation::between("edit lineup", &before, &after).unwrap();
+        let mut manifest = before.clone();
+        operation.apply(&mut manifest);
+        assert_eq!(manifest, after);
+        operation.inverse().apply(&mut manifest);
+        assert_eq!(manifest, before);
+    }
+
+    #[test]
+    fn test_undo_keeps_later_unrelated_changes() {
+        let before = sample_manifest();
+        let mut after = before.clone();
+        for (idx, image) in after
+            .images
+            .get_mut("de_mirage")
+            .unwrap()
+            .iter_mut()
+            .rev()
+            .enumerate()
+        {
+            image.order = idx;
+        }
+        let operation = Operation::between("reorder lineups", &before, &after).unwrap();
+
+        // A lineup added since then survives undoing the reorder
+        let mut manifest = after.clone();
+        let added = lineup("de_mirage", "stairs", 2);
+        manifest
+            .images
+            .get_mut("de_mirage")
+            .unwrap()
+            .push(added.clone());
+        operation.inverse().apply(&mut manifest);
+        assert_eq!(manifest.find_image(&added.id), Some(&added));
+        for image in &before.images["de_mirage"] {
+            assert_eq!(manifest.find_image(&image.id), Some(image));
+        }
+    }
+
+    #[test]
+    fn test_new_operation_clears_redo_and_history_is_bounded() {
+        let mut history = History::default();
+        let mut manifest = sample_manifest();
+        for idx in 0..HISTORY_LIMIT + 5 {
+            let before = manifest.clone();
+            manifest.images.get_mut("de_mirage").unwrap()[0].notes = idx.to_string();
+            history.record(Operation::between("edit lineup", &before, &manifest).unwrap());
+        }
+        let mut undone = 0;
+        while history.undo().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, HISTORY_LIMIT);
+
+        let before = manifest.clone();
+        manifest.images.get_mut("de_mirage").unwrap()[0].notes = "new".to_string();
+        history.record(Operation::between("edit lineup", &before, &manifest).unwrap());
+        assert!(history.redo().is_none());
+    }
+}
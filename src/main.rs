@@ -1,6 +1,7 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use crate::persistence::ImageMeta;
+use crate::history::Operation;
+use crate::persistence::{ImageManifest, ImageMeta, Strat, normalize_tag};
 use eframe::{NativeOptions, egui};
 
 use log::{self, LevelFilter};
@@ -8,6 +9,9 @@ use log::{self, LevelFilter};
 // persistence::copy_image_to_data is called via persistence::copy_image_to_data_threaded or directly in persistence module
 use crate::app_actions::AppAction;
 use crate::app_state::AppState;
+use crate::services::export_service::ExportService;
+use crate::services::image_service::ImageService;
+use crate::services::integrity_service::IntegrityService;
 use crate::ui::sharing_view::SharingView;
 use crate::ui::update_dialog::UpdateDialog;
 use crate::ui::upload_modal_view::UploadModal;
@@ -16,6 +20,9 @@ use std::sync::Arc;
 mod app_actions;
 mod app_state;
 pub mod common;
+mod history;
+mod migrations;
+mod object_store;
 mod persistence;
 mod services;
 mod ui;
@@ -102,32 +109,47 @@ impl NadexApp {
         self.app_state.filter_images_for_current_map();
     }
 
-    fn load_detail_image(&mut self, ctx: &egui::Context, image_meta: &ImageMeta) {
-        // First try to load from the full-size WebP in the thumbnails directory
-        let map_dir = self.app_state.data_dir.join(&self.app_state.current_map);
-        // With WebP-only storage, image files are already in WebP format in the main directory
-        let image_path_to_load = map_dir.join(&image_meta.filename);
+    /// Check the library in the background; the report arrives as
+    /// `AppAction::IntegrityCheckCompleted`
+    fn start_integrity_check(&mut self, ctx: &egui::Context) {
+        self.app_state.integrity_report = None;
+        let service = IntegrityService::new(Arc::clone(&self.app_state.persistence_service));
+        let sender = self.app_state.upload_result_sender.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let report = service.check().map_err(|e| {
+                log::error!("Library check failed: {}", e);
+                e.to_string()
+            });
+            let _ = sender.send(AppAction::IntegrityCheckCompleted(report));
+            ctx.request_repaint();
+        });
+    }
+
+    fn load_detail_image(&mut self, ctx: &egui::Context, image_meta: &ImageMeta, step: usize) {
+        // Search results can come from any map, so resolve against the lineup's own map
+        let filename = image_meta
+            .steps()
+            .get(step)
+            .map_or_else(|| image_meta.filename.clone(), |s| s.filename.clone());
+        self.app_state.detail_step = step;
+        let image_path_to_load =
+            object_store::image_path(&self.app_state.data_dir, &image_meta.map, &filename);
         match image::open(&image_path_to_load) {
             Ok(img) => {
                 let color_image = egui::ColorImage::from_rgba_unmultiplied(
                     [img.width() as usize, img.height() as usize],
                     img.to_rgba8().as_flat_samples().as_slice(),
                 );
-                let texture_name = format!(
-                    "detail_{}_{:?}",
-                    image_meta.filename,
-                    std::time::SystemTime::now()
-                );
+                let texture_name =
+                    format!("detail_{}_{:?}", filename, std::time::SystemTime::now());
                 let handle =
                     ctx.load_texture(texture_name, color_image, egui::TextureOptions::default());
                 self.app_state.detail_view_texture_handle = Some(handle);
                 self.app_state.detail_view_error = None;
             }
             Err(e) => {
-                eprintln!(
-                    "Failed to load detail image '{}': {}",
-                    image_meta.filename, e
-                );
+                eprintln!("Failed to load detail image '{}': {}", filename, e);
                 self.app_state.detail_view_error = Some(format!("Error loading image: {}", e));
                 self.app_state.selected_image_for_detail = None;
                 self.app_state.detail_view_texture_handle = None;
@@ -135,26 +157,33 @@ impl NadexApp {
         } // Closes match
     } // Closes load_detail_image
 
-    fn handle_confirm_image_delete(&mut self, meta_to_delete: ImageMeta, ctx: &egui::Context) {
-        let filename_to_delete = meta_to_delete.filename.clone(); // Keep for logging and cache clearing
+    fn handle_confirm_image_delete(
+        &mut self,
+        metas_to_delete: Vec<ImageMeta>,
+        ctx: &egui::Context,
+    ) {
+        let filenames_to_delete: Vec<&str> = metas_to_delete
+            .iter()
+            .map(|meta| meta.filename.as_str())
+            .collect(); // Keep for logging
 
         // Call ImageService to handle deletion (file removal, manifest update, manifest save)
         match self
             .app_state
             .image_service
-            .delete_image(&meta_to_delete, &mut self.app_state.image_manifest)
+            .delete_images(&metas_to_delete, &mut self.app_state.image_manifest)
         {
             Ok(_) => {
                 log::info!(
-                    "Image '{}' deleted successfully via ImageService.",
-                    filename_to_delete
+                    "Image(s) {:?} deleted successfully via ImageService.",
+                    filenames_to_delete
                 );
                 self.app_state.error_message = None; // Clear previous error on successful deletion
             }
             Err(e) => {
                 log::error!(
-                    "ImageService failed to delete image '{}': {}",
-                    filename_to_delete,
+                    "ImageService failed to delete image(s) {:?}: {}",
+                    filenames_to_delete,
                     e
                 );
                 self.app_state.error_message = Some(format!("Failed to delete image: {}", e));
@@ -168,11 +197,22 @@ impl NadexApp {
         // The detail_view_texture_handle is cleared separately.
         // No explicit, separate UI-wide thumbnail cache object needs manual clearing here beyond what egui manages for displayed textures.
         log::debug!(
-            "Image deletion process completed for: '{}'. UI will refresh.",
-            filename_to_delete
+            "Image deletion process completed for: {:?}. UI will refresh.",
+            filenames_to_delete
         );
 
+        // A strat being edited can't keep the deleted lineups either
+        if let Some(strat) = &mut self.app_state.editing_strat {
+            strat
+                .steps
+                .retain(|step| !metas_to_delete.iter().any(|meta| meta.id == step.image_id));
+        }
+
         // UI state updates
+        self.app_state.trash = self.app_state.persistence_service.load_trash();
+        for meta in &metas_to_delete {
+            self.app_state.selected_images.remove(&meta.id);
+        }
         self.app_state.selected_image_for_detail = None;
         self.app_state.detail_view_texture_handle = None;
         self.app_state.show_delete_confirmation = None;
@@ -180,6 +220,48 @@ impl NadexApp {
         ctx.request_repaint();
     }
 
+    /// Remember what an action changed in the manifest since `before`, so it can be undone
+    fn record_history(&mut self, description: &str, before: &ImageManifest) {
+        if let Some(operation) =
+            Operation::between(description, before, &self.app_state.image_manifest)
+        {
+            self.app_state.history.record(operation);
+        }
+    }
+
+    /// Apply an operation from the undo or redo stack; `verb` is for the error message
+    fn apply_history_step(&mut self, operation: &Operation, verb: &str, ctx: &egui::Context) {
+        match self
+            .app_state
+            .image_service
+            .apply_operation(operation, &mut self.app_state.image_manifest)
+        {
+            Ok(()) => {
+                log::info!("{} {}", verb, operation.description);
+                self.app_state.error_message = None;
+            }
+            Err(e) => {
+                log::error!("Failed to {} {}: {}", verb, operation.description, e);
+                self.app_state.error_message = Some(format!(
+                    "Failed to {} {}: {}",
+                    verb, operation.description, e
+                ));
+            }
+        }
+        // Lineups the step took out or brought back moved in or out of the trash
+        self.app_state.trash = self.app_state.persistence_service.load_trash();
+        // Open views may point at lineups that changed or are gone
+        self.app_state.selected_image_for_detail = None;
+        self.app_state.detail_view_texture_handle = None;
+        self.app_state.editing_image_meta = None;
+        self.app_state.edit_form_data = None;
+        self.app_state.show_delete_confirmation = None;
+        self.filter_images_for_current_map();
+        // An undone delete may bring back a map that was removed from the list
+        self.app_state.sync_map_config();
+        ctx.request_repaint();
+    }
+
     fn handle_save_image_edit(
         &mut self,
         form_data_to_save: ui::edit_view::EditFormData,
@@ -212,6 +294,8 @@ impl NadexApp {
             self.app_state.editing_image_meta = None;
             self.app_state.edit_form_data = None;
             self.filter_images_for_current_map(); // Refresh the view
+            // The lineup may have moved to a map that isn't in the list yet
+            self.app_state.sync_map_config();
             ctx.request_repaint();
         } else {
             log::error!(
@@ -288,12 +372,35 @@ impl eframe::App for NadexApp {
             ctx.request_repaint(); // Request repaint if new thumbnails were loaded this frame
         }
 
+        // Undo/redo shortcuts, unless a text field has focus and takes them itself
+        if ctx.memory(|mem| mem.focused().is_none()) {
+            ctx.input_mut(|input| {
+                if input.consume_key(
+                    egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                    egui::Key::Z,
+                ) {
+                    self.action_queue.push(AppAction::Redo);
+                } else if input.consume_key(egui::Modifiers::COMMAND, egui::Key::Z) {
+                    self.action_queue.push(AppAction::Undo);
+                } else if !self.app_state.selected_images.is_empty()
+                    && input.consume_key(egui::Modifiers::NONE, egui::Key::Escape)
+                {
+                    self.action_queue.push(AppAction::SelectImages {
+                        image_ids: Vec::new(),
+                        anchor: None,
+                    });
+                }
+            });
+        }
+
         let actions_to_process = self.action_queue.drain(..).collect::<Vec<_>>();
         if !actions_to_process.is_empty() {
             for action in actions_to_process {
                 match action {
                     AppAction::SelectMap(map_name) => {
                         self.app_state.current_map = map_name;
+                        self.app_state.selected_images.clear();
+                        self.app_state.selection_anchor = None;
                         self.filter_images_for_current_map();
                         self.app_state.selected_image_for_detail = None;
                         self.app_state.detail_view_texture_handle = None;
@@ -301,32 +408,39 @@ impl eframe::App for NadexApp {
                         ctx.request_repaint();
                     }
                     AppAction::SubmitUpload {
-                        file_path,
+                        image_files,
                         map_name,
                         nade_type,
+                        side,
+                        technique,
+                        tags,
+                        setpos,
                         position,
                         notes,
                     } => {
                         log::info!(
-                            "Offloading SubmitUpload for map: {}, file: {:?}",
+                            "Offloading SubmitUpload for map: {}, files: {:?}",
                             map_name,
-                            file_path
+                            image_files
                         );
 
                         let image_service_clone = Arc::clone(&self.app_state.image_service);
                         let sender_clone = self.app_state.upload_result_sender.clone();
-                        let file_path_clone = file_path.clone();
                         let map_name_clone = map_name.clone();
 
                         std::thread::spawn(move || {
                             log::info!(
-                                "Background thread: Delegating to ImageService.orchestrate_full_upload_process for file: {:?}",
-                                file_path_clone
+                                "Background thread: Delegating to ImageService.orchestrate_full_upload_process for files: {:?}",
+                                image_files
                             );
                             image_service_clone.orchestrate_full_upload_process(
-                                file_path_clone,
+                                image_files,
                                 map_name_clone,
                                 nade_type,
+                                side,
+                                technique,
+                                tags,
+                                setpos,
                                 position,
                                 notes,
                                 sender_clone,
@@ -451,19 +565,51 @@ impl eframe::App for NadexApp {
                         self.app_state.detail_view_texture_handle = None;
                         ctx.request_repaint();
                     }
-                    AppAction::ImageGridImageClicked(meta) => {
+                    AppAction::SetSideFilter(side) => {
+                        self.app_state.selected_side = side;
+                        self.filter_images_for_current_map();
+                        self.app_state.selected_image_for_detail = None;
+                        self.app_state.detail_view_texture_handle = None;
+                        ctx.request_repaint();
+                    }
+                    AppAction::SetTechniqueFilter(filter) => {
+                        self.app_state.technique_filter = filter;
+                        self.filter_images_for_current_map();
+                        self.app_state.selected_image_for_detail = None;
+                        self.app_state.detail_view_texture_handle = None;
+                        ctx.request_repaint();
+                    }
+                    AppAction::SetTagFilter(tags) => {
+                        self.app_state.selected_tags = tags;
+                        self.filter_images_for_current_map();
+                        self.app_state.selected_image_for_detail = None;
+                        self.app_state.detail_view_texture_handle = None;
+                        ctx.request_repaint();
+                    }
+                    AppAction::SetSearchQuery(query) => {
+                        self.app_state.search_query = query;
+                        self.filter_images_for_current_map();
+                        self.app_state.selected_image_for_detail = None;
+                        self.app_state.detail_view_texture_handle = None;
+                        ctx.request_repaint();
+                    }
+                    AppAction::ImageGridImageClicked(image_id) => {
                         if self
                             .app_state
                             .selected_image_for_detail
                             .as_ref()
-                            .is_some_and(|selected| selected.filename == meta.filename)
+                            .is_some_and(|selected| selected.id == image_id)
                         {
                             self.app_state.selected_image_for_detail = None;
                             self.app_state.detail_view_texture_handle = None;
-                        } else {
+                        } else if let Some(meta) =
+                            self.app_state.image_manifest.find_image(&image_id).cloned()
+                        {
                             self.app_state.selected_image_for_detail = Some(meta.clone());
                             self.app_state.detail_view_texture_handle = None; // Texture will be loaded
-                            self.load_detail_image(ctx, &meta);
+                            self.load_detail_image(ctx, &meta, 0);
+                        } else {
+                            log::warn!("Clicked image '{}' not found in manifest.", image_id);
                         }
                         ctx.request_repaint();
                     }
@@ -475,24 +621,37 @@ impl eframe::App for NadexApp {
                         self.app_state.edit_form_data = None;
                         ctx.request_repaint();
                     }
-                    AppAction::DetailModalRequestEdit(meta) => {
-                        self.app_state.editing_image_meta = Some(meta.clone());
+                    AppAction::DetailModalSelectStep(step) => {
+                        if let Some(meta) = self.app_state.selected_image_for_detail.clone() {
+                            self.load_detail_image(ctx, &meta, step);
+                        }
+                        ctx.request_repaint();
+                    }
+                    AppAction::DetailModalRequestEdit(image_id) => {
+                        let meta = self.app_state.image_manifest.find_image(&image_id).cloned();
+                        if meta.is_none() {
+                            log::warn!("Image '{}' requested for edit not found.", image_id);
+                        }
                         self.app_state.edit_form_data =
-                            Some(ui::edit_view::EditFormData::from_meta(&meta));
+                            meta.as_ref().map(ui::edit_view::EditFormData::from_meta);
+                        self.app_state.editing_image_meta = meta;
                         self.app_state.selected_image_for_detail = None;
                         self.app_state.detail_view_texture_handle = None;
                         self.app_state.detail_view_error = None;
                         ctx.request_repaint();
                     }
-                    AppAction::DetailModalRequestDelete(meta) => {
-                        self.app_state.show_delete_confirmation = Some(meta);
+                    AppAction::DetailModalRequestDelete(image_id) => {
+                        self.app_state.show_delete_confirmation =
+                            self.app_state.image_manifest.find_image(&image_id).cloned();
                         self.app_state.selected_image_for_detail = None;
                         self.app_state.detail_view_texture_handle = None;
                         self.app_state.detail_view_error = None;
                         ctx.request_repaint();
                     }
                     AppAction::EditModalSave(form_data) => {
+                        let before = self.app_state.image_manifest.clone();
                         self.handle_save_image_edit(form_data, ctx);
+                        self.record_history("edit lineup", &before);
                         // Note: handle_save_image_edit should handle repainting and clearing editing_image_meta/edit_form_data on success/failure.
                     }
                     AppAction::EditModalCancel => {
@@ -508,7 +667,9 @@ impl eframe::App for NadexApp {
                             // It's important that handle_confirm_image_delete also sets show_delete_confirmation to None.
                             // Or, we do it here explicitly before/after the call if it doesn't.
                             // Based on old comment, handle_confirm_image_delete handles this.
-                            self.handle_confirm_image_delete(meta_to_delete, ctx);
+                            let before = self.app_state.image_manifest.clone();
+                            self.handle_confirm_image_delete(vec![meta_to_delete], ctx);
+                            self.record_history("delete lineup", &before);
                         } else {
                             // This case should ideally not happen if DeleteConfirm is only sent when modal is shown.
                             log::warn!(
@@ -520,10 +681,424 @@ impl eframe::App for NadexApp {
                         self.app_state.show_delete_confirmation = None;
                         ctx.request_repaint();
                     }
+                    AppAction::ShowTagManager => {
+                        self.app_state.show_tag_manager = true;
+                        ctx.request_repaint();
+                    }
+                    AppAction::CloseTagManager => {
+                        self.app_state.show_tag_manager = false;
+                        ctx.request_repaint();
+                    }
+                    AppAction::RenameTag { from, to } => {
+                        match self.app_state.image_service.rename_tag(
+                            &mut self.app_state.image_manifest,
+                            &from,
+                            &to,
+                        ) {
+                            Ok(changed) => {
+                                log::info!(
+                                    "Renamed tag '{}' to '{}' on {} image(s).",
+                                    from,
+                                    to,
+                                    changed
+                                );
+                                // Keep an active filter on the renamed tag pointing at its new name
+                                if let Some(new_name) = normalize_tag(&to) {
+                                    let mut selected_tags = Vec::new();
+                                    for tag in self.app_state.selected_tags.drain(..) {
+                                        let tag = if tag == from { new_name.clone() } else { tag };
+                                        if !selected_tags.contains(&tag) {
+                                            selected_tags.push(tag);
+                                        }
+                                    }
+                                    self.app_state.selected_tags = selected_tags;
+                                }
+                                self.app_state.error_message = None;
+                            }
+                            Err(e) => {
+                                log::error!("Failed to rename tag '{}': {}", from, e);
+                                self.app_state.error_message =
+                                    Some(format!("Failed to rename tag: {}", e));
+                            }
+                        }
+                        self.filter_images_for_current_map();
+                        ctx.request_repaint();
+                    }
+                    AppAction::ShowMapManager => {
+                        self.app_state.show_map_manager = true;
+                        ctx.request_repaint();
+                    }
+                    AppAction::CloseMapManager => {
+                        self.app_state.show_map_manager = false;
+                        ctx.request_repaint();
+                    }
+                    AppAction::UpdateMapConfig(map_config) => {
+                        match self
+                            .app_state
+                            .persistence_service
+                            .save_map_config(&map_config)
+                        {
+                            Ok(()) => {
+                                self.app_state.map_config = map_config;
+                                self.app_state.error_message = None;
+                            }
+                            Err(e) => {
+                                log::error!("Failed to save map list: {}", e);
+                                self.app_state.error_message =
+                                    Some(format!("Failed to save map list: {}", e));
+                            }
+                        }
+                        ctx.request_repaint();
+                    }
+                    AppAction::ToggleRadarPanel => {
+                        self.app_state.show_radar_panel = !self.app_state.show_radar_panel;
+                        ctx.request_repaint();
+                    }
+                    AppAction::SetRadarImage { map_name, path } => {
+                        let mut map_config = self.app_state.map_config.clone();
+                        let result = self
+                            .app_state
+                            .persistence_service
+                            .store_radar_image(&path, &map_name)
+                            .and_then(|filename| {
+                                if let Some(entry) = map_config.find_mut(&map_name) {
+                                    entry.radar.image = Some(filename);
+                                }
+                                self.app_state
+                                    .persistence_service
+                                    .save_map_config(&map_config)
+                            });
+                        match result {
+                            Ok(()) => {
+                                self.app_state.map_config = map_config;
+                                // The new file can reuse the old name, so drop the cached texture
+                                self.app_state.radar_texture = None;
+                                self.app_state.error_message = None;
+                            }
+                            Err(e) => {
+                                log::error!("Failed to set radar image for {}: {}", map_name, e);
+                                self.app_state.error_message =
+                                    Some(format!("Failed to set radar image: {}", e));
+                            }
+                        }
+                        ctx.request_repaint();
+                    }
+                    AppAction::ShowPracticeConfig(image_ids) => {
+                        self.app_state.practice_config_selection = Some(image_ids);
+                        ctx.request_repaint();
+                    }
+                    AppAction::ClosePracticeConfig => {
+                        self.app_state.practice_config_selection = None;
+                        ctx.request_repaint();
+                    }
+                    AppAction::ToggleStratPanel => {
+                        self.app_state.show_strat_panel = !self.app_state.show_strat_panel;
+                        ctx.request_repaint();
+                    }
+                    AppAction::EditStrat(strat_id) => {
+                        match strat_id {
+                            Some(strat_id) => {
+                                match self.app_state.image_manifest.find_strat(&strat_id) {
+                                    Some(strat) => {
+                                        self.app_state.editing_strat = Some(strat.clone());
+                                    }
+                                    None => {
+                                        log::warn!("EditStrat: strat {} not found.", strat_id);
+                                    }
+                                }
+                            }
+                            None => {
+                                self.app_state.editing_strat =
+                                    Some(Strat::new("", &self.app_state.current_map));
+                            }
+                        }
+                        ctx.request_repaint();
+                    }
+                    AppAction::SaveStrat(strat) => {
+                        let strat_name = strat.name.clone();
+                        match self
+                            .app_state
+                            .image_service
+                            .save_strat(&mut self.app_state.image_manifest, strat)
+                        {
+                            Ok(()) => {
+                                log::info!("Saved strat '{}'.", strat_name);
+                                self.app_state.editing_strat = None;
+                                self.app_state.error_message = None;
+                            }
+                            Err(e) => {
+                                log::error!("Failed to save strat '{}': {}", strat_name, e);
+                                self.app_state.error_message =
+                                    Some(format!("Failed to save strat: {}", e));
+                            }
+                        }
+                        ctx.request_repaint();
+                    }
+                    AppAction::CloseStratEditor => {
+                        self.app_state.editing_strat = None;
+                        ctx.request_repaint();
+                    }
+                    AppAction::DeleteStrat(strat_id) => {
+                        match self
+                            .app_state
+                            .image_service
+                            .delete_strat(&mut self.app_state.image_manifest, &strat_id)
+                        {
+                            Ok(()) => {
+                                if self
+                                    .app_state
+                                    .strat_playback
+                                    .as_ref()
+                                    .is_some_and(|(id, _)| *id == strat_id)
+                                {
+                                    self.app_state.strat_playback = None;
+                                }
+                                if self
+                                    .app_state
+                                    .editing_strat
+                                    .as_ref()
+                                    .is_some_and(|strat| strat.id == strat_id)
+                                {
+                                    self.app_state.editing_strat = None;
+                                }
+                                self.app_state.error_message = None;
+                            }
+                            Err(e) => {
+                                log::error!("Failed to delete strat {}: {}", strat_id, e);
+                                self.app_state.error_message =
+                                    Some(format!("Failed to delete strat: {}", e));
+                            }
+                        }
+                        ctx.request_repaint();
+                    }
+                    AppAction::PlayStrat(strat_id) => {
+                        self.app_state.strat_playback = Some((strat_id, 0));
+                        ctx.request_repaint();
+                    }
+                    AppAction::StratPlaybackStep(index) => {
+                        if let Some((_, current_index)) = &mut self.app_state.strat_playback {
+                            *current_index = index;
+                        }
+                        ctx.request_repaint();
+                    }
+                    AppAction::CloseStratPlayback => {
+                        self.app_state.strat_playback = None;
+                        ctx.request_repaint();
+                    }
                     AppAction::ShowSharingView => {
                         self.app_state.show_sharing_view = true;
                         ctx.request_repaint();
                     }
+                    AppAction::ShowBackups => {
+                        self.app_state.show_backups = true;
+                        ctx.request_repaint();
+                    }
+                    AppAction::CloseBackups => {
+                        self.app_state.show_backups = false;
+                        ctx.request_repaint();
+                    }
+                    AppAction::ShowTrash => {
+                        self.app_state.show_trash = true;
+                        ctx.request_repaint();
+                    }
+                    AppAction::CloseTrash => {
+                        self.app_state.show_trash = false;
+                        ctx.request_repaint();
+                    }
+                    AppAction::RestoreFromTrash(image_id) => {
+                        let before = self.app_state.image_manifest.clone();
+                        match self
+                            .app_state
+                            .image_service
+                            .restore_from_trash(&image_id, &mut self.app_state.image_manifest)
+                        {
+                            Ok(()) => {
+                                self.record_history("restore lineup", &before);
+                                self.app_state.error_message = None;
+                            }
+                            Err(e) => {
+                                log::error!("Failed to restore lineup {}: {}", image_id, e);
+                                self.app_state.error_message =
+                                    Some(format!("Failed to restore lineup: {}", e));
+                            }
+                        }
+                        self.app_state.trash = self.app_state.persistence_service.load_trash();
+                        self.filter_images_for_current_map();
+                        // The lineup may be for a map that was removed from the list since
+                        self.app_state.sync_map_config();
+                        ctx.request_repaint();
+                    }
+                    AppAction::PurgeTrash(image_id) => {
+                        match &image_id {
+                            Some(id) => {
+                                self.app_state.trash.take(id);
+                            }
+                            None => self.app_state.trash.lineups.clear(),
+                        }
+                        // Their images are freed by the garbage collection at the next start
+                        if let Err(e) = self
+                            .app_state
+                            .persistence_service
+                            .save_trash(&self.app_state.trash)
+                        {
+                            log::error!("Failed to save the trash: {}", e);
+                            self.app_state.error_message =
+                                Some(format!("Failed to purge the trash: {}", e));
+                        }
+                        ctx.request_repaint();
+                    }
+                    AppAction::SetTrashRetention(days) => {
+                        // Lineups past the new period go at the next start, not on a misclick
+                        self.app_state.trash.retention_days = days;
+                        if let Err(e) = self
+                            .app_state
+                            .persistence_service
+                            .save_trash(&self.app_state.trash)
+                        {
+                            log::error!("Failed to save the trash: {}", e);
+                            self.app_state.error_message =
+                                Some(format!("Failed to save the trash settings: {}", e));
+                        }
+                        ctx.request_repaint();
+                    }
+                    AppAction::ShowIntegrityCheck => {
+                        self.app_state.show_integrity_check = true;
+                        self.start_integrity_check(ctx);
+                    }
+                    AppAction::CloseIntegrityCheck => {
+                        self.app_state.show_integrity_check = false;
+                        self.app_state.integrity_report = None;
+                        ctx.request_repaint();
+                    }
+                    AppAction::IntegrityCheckCompleted(report) => {
+                        // A check finishing after the window was closed is dropped
+                        if self.app_state.show_integrity_check {
+                            self.app_state.integrity_report = Some(report);
+                        }
+                        ctx.request_repaint();
+                    }
+                    AppAction::RepairIntegrityIssue(issue, repair) => {
+                        let service =
+                            IntegrityService::new(Arc::clone(&self.app_state.persistence_service));
+                        match service.repair(&issue, &repair) {
+                            Ok(manifest) => {
+                                log::info!("Repaired {:?} with {:?}", issue, repair);
+                                let before =
+                                    std::mem::replace(&mut self.app_state.image_manifest, manifest);
+                                self.record_history("repair", &before);
+                                // The lineup in the detail modal may have been removed
+                                if let Some(meta) = &self.app_state.selected_image_for_detail
+                                    && self.app_state.image_manifest.find_image(&meta.id).is_none()
+                                {
+                                    self.app_state.selected_image_for_detail = None;
+                                    self.app_state.detail_view_texture_handle = None;
+                                }
+                                self.filter_images_for_current_map();
+                                self.app_state.sync_map_config();
+                            }
+                            Err(e) => {
+                                log::error!("Failed to repair {:?}: {}", issue, e);
+                                self.app_state.error_message =
+                                    Some(format!("Repair failed: {}", e));
+                            }
+                        }
+                        // Whatever happened, show what's left
+                        self.start_integrity_check(ctx);
+                    }
+                    AppAction::RestoreManifestBackup(generation) => {
+                        match self
+                            .app_state
+                            .persistence_service
+                            .restore_manifest_backup(generation)
+                        {
+                            Ok(manifest) => {
+                                log::info!("Restored manifest backup {}.", generation);
+                                let before =
+                                    std::mem::replace(&mut self.app_state.image_manifest, manifest);
+                                self.record_history("restore backup", &before);
+                                // Open views may point at lineups or strats the backup doesn't have
+                                self.app_state.selected_image_for_detail = None;
+                                self.app_state.detail_view_texture_handle = None;
+                                self.app_state.editing_image_meta = None;
+                                self.app_state.edit_form_data = None;
+                                self.app_state.editing_strat = None;
+                                self.app_state.strat_playback = None;
+                                self.app_state.show_backups = false;
+                                self.app_state.error_message = None;
+                                self.filter_images_for_current_map();
+                                self.app_state.sync_map_config();
+                            }
+                            Err(e) => {
+                                log::error!(
+                                    "Failed to restore manifest backup {}: {}",
+                                    generation,
+                                    e
+                                );
+                                self.app_state.error_message =
+                                    Some(format!("Failed to restore backup: {}", e));
+                            }
+                        }
+                        ctx.request_repaint();
+                    }
+                    AppAction::ResolveManifestRecovery(choice) => {
+                        if let Some(recovery) = self.app_state.manifest_recovery.take() {
+                            let mut manifest = recovery.manifest(choice);
+                            migrations::migrate(&mut manifest, &self.app_state.data_dir);
+                            log::info!("Continuing with the {:?} manifest.", choice);
+                            if let Err(e) =
+                                self.app_state.persistence_service.save_manifest(&manifest)
+                            {
+                                log::error!("Failed to save the recovered manifest: {}", e);
+                                self.app_state.error_message =
+                                    Some(format!("Failed to save the recovered library: {}", e));
+                            }
+                            self.app_state.image_manifest = manifest;
+                            self.filter_images_for_current_map();
+                            self.app_state.sync_map_config();
+                        }
+                        ctx.request_repaint();
+                    }
+                    AppAction::ExportProgress { done, total }
+                    | AppAction::ImportProgress { done, total } => {
+                        self.sharing_view.set_progress(done, total);
+                    }
+                    AppAction::ExportCompleted(result) => {
+                        self.sharing_view.finish_export(result);
+                        ctx.request_repaint();
+                    }
+                    AppAction::ImportPreviewReady(plan) => {
+                        self.sharing_view.open_import_preview(plan);
+                        ctx.request_repaint();
+                    }
+                    AppAction::ImportCompleted(result) => {
+                        // The images are in the store already. Merge the lineups and strats
+                        // here, so saves made while the import ran aren't overwritten
+                        let result = match result {
+                            Ok(imported) => {
+                                let before = self.app_state.image_manifest.clone();
+                                match ExportService::new(Arc::clone(
+                                    &self.app_state.persistence_service,
+                                ))
+                                .merge_import(&imported, &mut self.app_state.image_manifest)
+                                {
+                                    Ok(()) => {
+                                        self.record_history("import", &before);
+                                        self.filter_images_for_current_map();
+                                        // Imported lineups may be for maps that aren't in the map list yet
+                                        self.app_state.sync_map_config();
+                                        Ok(())
+                                    }
+                                    Err(e) => {
+                                        log::error!("Failed to merge import: {}", e);
+                                        Err(format!("Import failed: {}", e))
+                                    }
+                                }
+                            }
+                            Err(e) => Err(e),
+                        };
+                        self.sharing_view.finish_import(result);
+                        ctx.request_repaint();
+                    }
                     AppAction::ToggleReorderMode => {
                         self.app_state.reorder_mode = !self.app_state.reorder_mode;
                         ctx.request_repaint();
@@ -532,21 +1107,23 @@ impl eframe::App for NadexApp {
                         from_index,
                         to_index,
                     } => {
-                        // Reorder the images in current_map_images
+                        // The indices are into the grid, which filters may narrow down;
+                        // move by the ids it shows so hidden lineups keep their places
+                        let visible_id = |index: usize| {
+                            self.app_state
+                                .visible_images()
+                                .get(index)
+                                .map(|meta| meta.id.clone())
+                        };
                         if from_index != to_index
-                            && from_index < self.app_state.current_map_images.len()
-                            && to_index < self.app_state.current_map_images.len()
+                            && let (Some(moved_id), Some(target_id)) =
+                                (visible_id(from_index), visible_id(to_index))
+                            && ImageService::move_image_to(
+                                &mut self.app_state.current_map_images,
+                                &moved_id,
+                                &target_id,
+                            )
                         {
-                            let item = self.app_state.current_map_images.remove(from_index);
-                            self.app_state.current_map_images.insert(to_index, item);
-
-                            // Update order field for all images
-                            for (idx, image_meta) in
-                                self.app_state.current_map_images.iter_mut().enumerate()
-                            {
-                                image_meta.order = idx;
-                            }
-
                             // Save the updated order to the manifest
                             let updated_manifest = self.app_state.image_manifest.clone();
                             let map_name = self.app_state.current_map.clone();
@@ -555,7 +1132,11 @@ impl eframe::App for NadexApp {
                                 new_manifest
                                     .images
                                     .insert(map_name, self.app_state.current_map_images.clone());
-                                self.app_state.image_manifest = new_manifest;
+                                let before = std::mem::replace(
+                                    &mut self.app_state.image_manifest,
+                                    new_manifest,
+                                );
+                                self.record_history("reorder lineups", &before);
 
                                 // Persist the changes
                                 let persistence_service =
@@ -579,6 +1160,104 @@ impl eframe::App for NadexApp {
                             ctx.request_repaint();
                         }
                     }
+                    AppAction::SelectImages { image_ids, anchor } => {
+                        self.app_state.selected_images = image_ids.into_iter().collect();
+                        self.app_state.selection_anchor = anchor;
+                        ctx.request_repaint();
+                    }
+                    AppAction::BulkEditImages { image_ids, edit } => {
+                        let before = self.app_state.image_manifest.clone();
+                        match self.app_state.image_service.bulk_edit_images(
+                            &mut self.app_state.image_manifest,
+                            &image_ids,
+                            &edit,
+                        ) {
+                            Ok(changed) => {
+                                log::info!("Applied {:?} to {} lineups.", edit, changed);
+                                self.record_history("edit lineups", &before);
+                                self.app_state.error_message = None;
+                            }
+                            Err(e) => {
+                                log::error!("Failed to apply {:?}: {}", edit, e);
+                                self.app_state.error_message =
+                                    Some(format!("Failed to edit lineups: {}", e));
+                            }
+                        }
+                        self.app_state.selected_image_for_detail = None;
+                        self.app_state.detail_view_texture_handle = None;
+                        self.filter_images_for_current_map();
+                        ctx.request_repaint();
+                    }
+                    AppAction::DeleteImages(image_ids) => {
+                        let metas_to_delete: Vec<ImageMeta> = image_ids
+                            .iter()
+                            .filter_map(|id| self.app_state.image_manifest.find_image(id).cloned())
+                            .collect();
+                        let before = self.app_state.image_manifest.clone();
+                        self.handle_confirm_image_delete(metas_to_delete, ctx);
+                        self.record_history("delete lineups", &before);
+                    }
+                    AppAction::ExportSelection => {
+                        self.sharing_view.export_selection();
+                        self.app_state.show_sharing_view = true;
+                        ctx.request_repaint();
+                    }
+                    AppAction::MoveImagesToMap {
+                        image_ids,
+                        map,
+                        copy,
+                    } => {
+                        let before = self.app_state.image_manifest.clone();
+                        match self.app_state.image_service.move_images_to_map(
+                            &mut self.app_state.image_manifest,
+                            &image_ids,
+                            &map,
+                            copy,
+                        ) {
+                            Ok(placed) => {
+                                log::info!(
+                                    "{} {} lineups to '{}'.",
+                                    if copy { "Copied" } else { "Moved" },
+                                    placed.len(),
+                                    map
+                                );
+                                self.record_history(
+                                    if copy {
+                                        "copy lineups to map"
+                                    } else {
+                                        "move lineups to map"
+                                    },
+                                    &before,
+                                );
+                                if !copy {
+                                    for id in &image_ids {
+                                        self.app_state.selected_images.remove(id);
+                                    }
+                                }
+                                self.app_state.error_message = None;
+                            }
+                            Err(e) => {
+                                log::error!("Failed to move lineups to '{}': {}", map, e);
+                                self.app_state.error_message =
+                                    Some(format!("Failed to move lineups: {}", e));
+                            }
+                        }
+                        self.app_state.selected_image_for_detail = None;
+                        self.app_state.detail_view_texture_handle = None;
+                        self.filter_images_for_current_map();
+                        self.app_state.sync_map_config();
+                        ctx.request_repaint();
+                    }
+                    AppAction::Undo => {
+                        if let Some(operation) = self.app_state.history.undo() {
+                            self.apply_history_step(&operation, "undo", ctx);
+                        }
+                    }
+                    AppAction::Redo => {
+                        if let Some(operation) = self.app_state.history.redo() {
+                            self.apply_history_step(&operation, "redo", ctx);
+                        }
+                    }
                 } // End match action
             } // End for loop
         } // End if !actions_to_process.is_empty()
@@ -629,6 +1308,34 @@ impl eframe::App for NadexApp {
             });
         });
 
+        // Strat Panel
+        if self.app_state.show_strat_panel {
+            egui::SidePanel::left("strat_panel")
+                .resizable(true)
+                .default_width(260.0)
+                .show(ctx, |panel_ui| {
+                    ui::strat_panel_view::show_strat_panel(
+                        &mut self.app_state,
+                        panel_ui,
+                        &mut self.action_queue,
+                    );
+                });
+        }
+
+        // Radar Panel
+        if self.app_state.show_radar_panel {
+            egui::SidePanel::right("radar_panel")
+                .resizable(true)
+                .default_width(420.0)
+                .show(ctx, |panel_ui| {
+                    ui::radar_view::show_radar_panel(
+                        &mut self.app_state,
+                        panel_ui,
+                        &mut self.action_queue,
+                    );
+                });
+        }
+
         // Main Central Panel
         egui::CentralPanel::default()
             .frame(egui::Frame::default().inner_margin(egui::Margin {
@@ -645,6 +1352,13 @@ impl eframe::App for NadexApp {
 
                 panel_ui.add_space(4.0);
 
+                // Bulk actions for the lineups selected in the grid
+                ui::selection_bar_view::show_selection_bar(
+                    &self.app_state,
+                    panel_ui,
+                    &mut self.action_queue,
+                );
+
                 // Call the refactored image grid view function
                 ui::image_grid_view::show_image_grid(
                     &mut self.app_state,
@@ -679,14 +1393,10 @@ impl eframe::App for NadexApp {
                     .app_state
                     .edit_form_data
                     .as_ref()
-                    .is_none_or(|data| data.filename != current_editing_meta.filename)
+                    .is_none_or(|data| data.id != current_editing_meta.id)
             {
-                self.app_state.edit_form_data = Some(ui::edit_view::EditFormData {
-                    filename: current_editing_meta.filename.clone(),
-                    nade_type: current_editing_meta.nade_type,
-                    position: current_editing_meta.position.clone(),
-                    notes: current_editing_meta.notes.clone(),
-                });
+                self.app_state.edit_form_data =
+                    Some(ui::edit_view::EditFormData::from_meta(current_editing_meta));
             }
 
             ui::edit_view::show_edit_modal(&mut self.app_state, ctx, &mut self.action_queue);
@@ -702,6 +1412,79 @@ impl eframe::App for NadexApp {
             );
         }
 
+        // --- Tag Manager ---
+        if self.app_state.show_tag_manager {
+            ui::tag_manager_view::show_tag_manager(
+                &mut self.app_state,
+                ctx,
+                &mut self.action_queue,
+            );
+        }
+
+        // --- Map Manager ---
+        if self.app_state.show_map_manager {
+            ui::map_manager_view::show_map_manager(
+                &mut self.app_state,
+                ctx,
+                &mut self.action_queue,
+            );
+        }
+
+        // --- Library Backups ---
+        if self.app_state.show_backups {
+            ui::backup_view::show_backups(&mut self.app_state, ctx, &mut self.action_queue);
+        }
+
+        // --- Trash ---
+        if self.app_state.show_trash {
+            ui::trash_view::show_trash(&mut self.app_state, ctx, &mut self.action_queue);
+        }
+
+        // --- Library Check ---
+        if self.app_state.show_integrity_check {
+            ui::integrity_view::show_integrity_check(
+                &mut self.app_state,
+                ctx,
+                &mut self.action_queue,
+            );
+        }
+
+        // --- Library Recovery ---
+        if self.app_state.manifest_recovery.is_some() {
+            ui::manifest_recovery_view::show_manifest_recovery(
+                &mut self.app_state,
+                ctx,
+                &mut self.action_queue,
+            );
+        }
+
+        // --- Strat Editor ---
+        if self.app_state.editing_strat.is_some() {
+            ui::strat_editor_view::show_strat_editor(
+                &mut self.app_state,
+                ctx,
+                &mut self.action_queue,
+            );
+        }
+
+        // --- Strat Playback ---
+        if self.app_state.strat_playback.is_some() {
+            ui::strat_playback_view::show_strat_playback(
+                &mut self.app_state,
+                ctx,
+                &mut self.action_queue,
+            );
+        }
+
+        // --- Practice Config ---
+        if self.app_state.practice_config_selection.is_some() {
+            ui::practice_config_view::show_practice_config(
+                &mut self.app_state,
+                ctx,
+                &mut self.action_queue,
+            );
+        }
+
         // --- Update Dialog ---
         self.update_dialog.show(ctx);
 
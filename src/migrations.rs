@@ -0,0 +1,239 @@
+// src/migrations.rs
+use crate::object_store;
+use crate::persistence::ImageManifest;
+use std::path::Path;
+
+/// One step in the manifest schema history, upgrading a manifest at `from` to `from + 1`.
+/// Steps must be idempotent: a manifest that already has what a step adds is left alone.
+/// Steps that touch files get the data directory and set `moves_files`.
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    pub apply: fn(&mut ImageManifest, &Path),
+    pub moves_files: bool,
+}
+
+/// Every migration, in order. `MIGRATIONS[n].from` is always `n`; append new steps here.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        description: "give lineups a grid order",
+        apply: |manifest, _| manifest.migrate_image_order(),
+        moves_files: false,
+    },
+    Migration {
+        from: 1,
+        description: "give lineups a stable id",
+        apply: |manifest, _| {
+            manifest.assign_missing_ids();
+        },
+        moves_files: false,
+    },
+    Migration {
+        from: 2,
+        description: "move images into the content-addressed store",
+        apply: object_store::adopt_legacy_images,
+        moves_files: true,
+    },
+    Migration {
+        from: 3,
+        description: "drop the webp_migration_completed flag",
+        // Uploads are converted to WebP, so nothing read the flag. Serde drops it when an
+        // older manifest loads, and the save after the bump writes the manifest without it.
+        apply: |_, _| {},
+        moves_files: false,
+    },
+];
+
+/// Schema version of manifests written by this build
+pub const MANIFEST_SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Bring `manifest` up to `MANIFEST_SCHEMA_VERSION`, running every step it's missing.
+/// Returns true if any step ran, i.e. the manifest should be saved.
+///
+/// A manifest from a newer build is left as it is.
+pub fn migrate(manifest: &mut ImageManifest, data_dir: &Path) -> bool {
+    run_migrations(manifest, Some(data_dir))
+}
+
+/// Like `migrate`, but stops before the first step that moves files, so a manifest that's
+/// only being looked at, like a backup the user may not restore, leaves the data directory
+/// alone. Its `schema_version` stays at that step, and `migrate` picks up from there once
+/// the manifest is used.
+pub fn migrate_in_memory(manifest: &mut ImageManifest) -> bool {
+    run_migrations(manifest, None)
+}
+
+fn run_migrations(manifest: &mut ImageManifest, data_dir: Option<&Path>) -> bool {
+    if manifest.schema_version > MANIFEST_SCHEMA_VERSION {
+        log::warn!(
+            "Manifest schema {} is newer than this version of Nadex reads ({}); fields it doesn't know are dropped on save.",
+            manifest.schema_version,
+            MANIFEST_SCHEMA_VERSION
+        );
+        return false;
+    }
+    let start = manifest.schema_version;
+    for migration in &MIGRATIONS[start as usize..] {
+        let data_dir = match data_dir {
+            Some(data_dir) => data_dir,
+            None if migration.moves_files => break,
+            // Steps that leave files alone don't look at the data directory
+            None => Path::new(""),
+        };
+        log::info!(
+            "Migrating manifest from schema {} to {}: {}",
+            migration.from,
+            migration.from + 1,
+            migration.description
+        );
+        (migration.apply)(manifest, data_dir);
+        manifest.schema_version = migration.from + 1;
+    }
+    manifest.schema_version != start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // One manifest per schema. `unordered.json` is hand-written in the shape 1.2.1 still
+    // reads from before grid ordering; the rest were saved by the build that wrote that
+    // schema: 1.2.1, then the commits that added schemas 2, 3 and 4.
+    const UNORDERED: &str = include_str!("../tests/fixtures/manifests/unordered.json");
+    const V1_2: &str = include_str!("../tests/fixtures/manifests/v1.2.json");
+    const SCHEMA_2: &str = include_str!("../tests/fixtures/manifests/schema2.json");
+    const SCHEMA_3: &str = include_str!("../tests/fixtures/manifests/schema3.json");
+    const SCHEMA_4: &str = include_str!("../tests/fixtures/manifests/schema4.json");
+
+    fn load_fixture(json: &str) -> ImageManifest {
+        serde_json::from_str(json).expect("Fixture should parse")
+    }
+
+    /// Run only the first `steps` migrations, as an older build would have
+    /// The fixtures' image files aren't on disk, so the file steps leave their names alone.
+    fn migrate_to(manifest: &mut ImageManifest, steps: u32) {
+        let data_dir = tempfile::tempdir().unwrap();
+        for migration in &MIGRATIONS[manifest.schema_version as usize..steps as usize] {
+            (migration.apply)(manifest, data_dir.path());
+            manifest.schema_version = migration.from + 1;
+        }
+    }
+
+    #[test]
+    fn test_registry_is_in_order() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.from, index as u32, "{}", migration.description);
+        }
+        assert_eq!(
+            ImageManifest::default().schema_version,
+            MANIFEST_SCHEMA_VERSION
+        );
+    }
+
+    #[test]
+    fn test_order_step_on_every_version() {
+        let mut unordered = load_fixture(UNORDERED);
+        assert_eq!(unordered.schema_version, 0);
+        migrate_to(&mut unordered, 1);
+        let mirage = &unordered.images["de_mirage"];
+        assert_eq!(mirage[0].filename, "jungle_20240101.webp");
+        assert_eq!(mirage[0].order, 0);
+        assert_eq!(mirage[1].filename, "window_20240102.webp");
+        assert_eq!(mirage[1].order, 1);
+        assert_eq!(unordered.images["de_inferno"][0].order, 0);
+
+        // An order the user already set is kept
+        let mut v1_2 = load_fixture(V1_2);
+        let before = v1_2.clone();
+        migrate_to(&mut v1_2, 1);
+        assert_eq!(v1_2.images, before.images);
+    }
+
+    #[test]
+    fn test_id_step_on_every_version() {
+        for fixture in [UNORDERED, V1_2] {
+            let mut manifest = load_fixture(fixture);
+            migrate_to(&mut manifest, 1);
+            assert!(
+                manifest
+                    .images
+                    .values()
+                    .flatten()
+                    .all(|img| img.id.is_empty())
+            );
+            migrate_to(&mut manifest, 2);
+            let ids: HashSet<&str> = manifest
+                .images
+                .values()
+                .flatten()
+                .map(|img| img.id.as_str())
+                .collect();
+            assert!(!ids.contains(""));
+            assert_eq!(
+                ids.len(),
+                manifest.images.values().map(Vec::len).sum::<usize>()
+            );
+        }
+
+        // Existing ids, which strats point at, are kept
+        let mut schema_2 = load_fixture(SCHEMA_2);
+        let before = schema_2.clone();
+        schema_2.schema_version = 1;
+        migrate_to(&mut schema_2, 2);
+        assert_eq!(schema_2, before);
+    }
+
+    #[test]
+    fn test_migrate_brings_every_version_up_to_date() {
+        let data_dir = tempfile::tempdir().unwrap();
+        for fixture in [UNORDERED, V1_2, SCHEMA_2, SCHEMA_3, SCHEMA_4] {
+            let mut manifest = load_fixture(fixture);
+            let was_current = manifest.schema_version == MANIFEST_SCHEMA_VERSION;
+            assert_eq!(migrate(&mut manifest, data_dir.path()), !was_current);
+            assert_eq!(manifest.schema_version, MANIFEST_SCHEMA_VERSION);
+
+            // Running every step again changes nothing
+            let migrated = manifest.clone();
+            manifest.schema_version = 0;
+            assert!(migrate(&mut manifest, data_dir.path()));
+            assert_eq!(manifest, migrated);
+            assert!(!migrate(&mut manifest, data_dir.path()));
+        }
+
+        let mut newer = load_fixture(SCHEMA_4);
+        newer.schema_version = MANIFEST_SCHEMA_VERSION + 1;
+        assert!(!migrate(&mut newer, data_dir.path()));
+        assert_eq!(newer.schema_version, MANIFEST_SCHEMA_VERSION + 1);
+    }
+
+    #[test]
+    fn test_webp_flag_is_dropped_on_save() {
+        assert!(SCHEMA_3.contains("webp_migration_completed"));
+        let mut manifest = load_fixture(SCHEMA_3);
+        assert!(migrate_in_memory(&mut manifest));
+        let saved = serde_json::to_string(&manifest).unwrap();
+        assert!(!saved.contains("webp_migration_completed"));
+    }
+
+    #[test]
+    fn test_migrate_in_memory_stops_before_moving_files() {
+        let mut manifest = load_fixture(V1_2);
+        assert!(migrate_in_memory(&mut manifest));
+        assert_eq!(manifest.schema_version, 2);
+        assert!(
+            manifest
+                .images
+                .values()
+                .flatten()
+                .all(|img| !img.id.is_empty())
+        );
+        assert!(!migrate_in_memory(&mut manifest));
+
+        // The file step runs once the manifest is migrated for real
+        let data_dir = tempfile::tempdir().unwrap();
+        assert!(migrate(&mut manifest, data_dir.path()));
+        assert_eq!(manifest.schema_version, MANIFEST_SCHEMA_VERSION);
+    }
+}
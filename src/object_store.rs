@@ -0,0 +1,487 @@
+// src/object_store.rs
+//! Content-addressed storage for lineup images.
+//!
+//! Every image is stored once under the SHA-256 of its content, at
+//! `objects/<first two hash digits>/<rest of the hash>.<extension>`, and lineups refer to it
+//! by that blob name (`<hash>.<extension>`). Storing the same image again is free, and
+//! removing a lineup never removes a file another lineup (or a manifest backup) still uses;
+//! unreferenced blobs are left for `collect_garbage`.
+//!
+//! Libraries from before the store keep working: a filename that isn't a blob name is read
+//! from the map's folder, and the schema 3 migration (`adopt_legacy_images`) moves those
+//! files into the store.
+use crate::persistence::ImageManifest;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Folder of the object store, inside the data directory
+pub const OBJECTS_DIR: &str = "objects";
+
+/// Where the store remembers which blob each moved `map/filename` became, so manifests
+/// saved before the move (backups, pre-migration copies) still find their images
+const LEGACY_NAMES_FILE: &str = "legacy-names.json";
+
+/// Whether `filename` names a blob: a lowercase hex SHA-256 and an extension
+pub fn is_blob_name(filename: &str) -> bool {
+    filename.split_once('.').is_some_and(|(hash, extension)| {
+        hash.len() == 64
+            && hash
+                .bytes()
+                .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+            && !extension.is_empty()
+            && extension.bytes().all(|byte| byte.is_ascii_alphanumeric())
+    })
+}
+
+/// The blob name of content hashing to `hash`, stored with `extension`
+pub fn blob_name(hash: &str, extension: &str) -> String {
+    format!("{}.{}", hash, extension.to_ascii_lowercase())
+}
+
+/// Where an image of a lineup on `map` is stored: in the object store for blob names, in
+/// the map's folder for images from before the store
+pub fn image_path(data_dir: &Path, map: &str, filename: &str) -> PathBuf {
+    if is_blob_name(filename) {
+        data_dir
+            .join(OBJECTS_DIR)
+            .join(&filename[..2])
+            .join(&filename[2..])
+    } else {
+        data_dir.join(map).join(filename)
+    }
+}
+
+/// Copy `reader` into `writer` a chunk at a time, returning the hex SHA-256 of the data
+pub fn copy_hashed(reader: &mut impl Read, writer: &mut impl Write) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read])?;
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Hex SHA-256 of a file, read a chunk at a time
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    copy_hashed(&mut File::open(path)?, &mut io::sink())
+}
+
+/// A new temporary file in the store, for writing an image before it's stored with
+/// `store_temp_file`. `extension` lets converters tell the format from the path.
+pub fn temp_file(data_dir: &Path, extension: &str) -> io::Result<tempfile::NamedTempFile> {
+    let objects_dir = data_dir.join(OBJECTS_DIR);
+    fs::create_dir_all(&objects_dir)?;
+    tempfile::Builder::new()
+        .suffix(&format!(".{}", extension))
+        .tempfile_in(objects_dir)
+}
+
+/// Store the content of a file from `temp_file` and return its blob name. If the store
+/// already has that content, the temporary file is dropped instead.
+pub fn store_temp_file(
+    data_dir: &Path,
+    file: tempfile::NamedTempFile,
+    extension: &str,
+) -> io::Result<String> {
+    let name = blob_name(&sha256_file(file.path())?, extension);
+    let target = image_path(data_dir, "", &name);
+    if target.exists() {
+        log::info!("{} is already stored", name);
+        return Ok(name);
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    file.persist(&target).map_err(|e| e.error)?;
+    Ok(name)
+}
+
+/// Copy any file into the store and return its blob name
+pub fn copy_into_store(data_dir: &Path, src: &Path, extension: &str) -> io::Result<String> {
+    let mut file = temp_file(data_dir, extension)?;
+    io::copy(&mut File::open(src)?, &mut file)?;
+    store_temp_file(data_dir, file, extension)
+}
+
+/// Move `src`, which must be on the same drive as the data directory, into the store as
+/// the blob `name`. Returns false, leaving `src` alone, if the blob is already stored.
+pub fn move_into_store(data_dir: &Path, src: &Path, name: &str) -> io::Result<bool> {
+    let target = image_path(data_dir, "", name);
+    if target.exists() {
+        return Ok(false);
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(src, &target)?;
+    Ok(true)
+}
+
+/// The blobs `manifest` refers to, with how many lineup steps use each. Names from before
+/// the store are looked up in `legacy_names`.
+pub fn blob_references(
+    manifest: &ImageManifest,
+    legacy_names: &BTreeMap<String, String>,
+) -> HashMap<String, usize> {
+    let mut references = HashMap::new();
+    for (map, images) in &manifest.images {
+        for step in images.iter().flat_map(|image| image.steps()) {
+            let blob = if is_blob_name(&step.filename) {
+                Some(step.filename)
+            } else {
+                legacy_names.get(&legacy_key(map, &step.filename)).cloned()
+            };
+            if let Some(blob) = blob {
+                *references.entry(blob).or_insert(0) += 1;
+            }
+        }
+    }
+    references
+}
+
+/// What `collect_garbage` removed
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct GarbageReport {
+    pub removed: usize,
+    pub freed_bytes: u64,
+}
+
+/// Remove every blob none of `roots` refers to. Leftover temporary files are not touched,
+/// since an upload may be writing them; the library check lists them.
+pub fn collect_garbage(data_dir: &Path, roots: &[ImageManifest]) -> io::Result<GarbageReport> {
+    let objects_dir = data_dir.join(OBJECTS_DIR);
+    let mut report = GarbageReport::default();
+    if !objects_dir.is_dir() {
+        return Ok(report);
+    }
+    let legacy_names = load_legacy_names(data_dir);
+    let referenced: HashSet<String> = roots
+        .iter()
+        .flat_map(|manifest| blob_references(manifest, &legacy_names).into_keys())
+        .collect();
+
+    for fan_out in fs::read_dir(&objects_dir)?.flatten() {
+        if !fan_out.file_type()?.is_dir() {
+            continue;
+        }
+        let prefix = fan_out.file_name().to_string_lossy().into_owned();
+        for entry in fs::read_dir(fan_out.path())?.flatten() {
+            let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+            if !is_blob_name(&name) || referenced.contains(&name) {
+                continue;
+            }
+            let size = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+            match fs::remove_file(entry.path()) {
+                Ok(()) => {
+                    report.removed += 1;
+                    report.freed_bytes += size;
+                }
+                Err(e) => log::warn!("Failed to remove unused image {}: {}", name, e),
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn legacy_key(map: &str, filename: &str) -> String {
+    format!("{}/{}", map, filename)
+}
+
+fn legacy_extension(filename: &str) -> String {
+    Path::new(filename).extension().map_or_else(
+        || "webp".to_string(),
+        |ext| ext.to_string_lossy().into_owned(),
+    )
+}
+
+/// The `map/filename` -> blob names recorded by `adopt_legacy_images`
+pub fn load_legacy_names(data_dir: &Path) -> BTreeMap<String, String> {
+    let path = data_dir.join(OBJECTS_DIR).join(LEGACY_NAMES_FILE);
+    match fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            log::error!("{} is not valid: {}", path.display(), e);
+            BTreeMap::new()
+        }),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+fn save_legacy_names(data_dir: &Path, names: &BTreeMap<String, String>) -> io::Result<()> {
+    let objects_dir = data_dir.join(OBJECTS_DIR);
+    fs::create_dir_all(&objects_dir)?;
+    let json = serde_json::to_string_pretty(names).map_err(io::Error::other)?;
+    let mut file = tempfile::NamedTempFile::new_in(&objects_dir)?;
+    file.write_all(json.as_bytes())?;
+    file.persist(objects_dir.join(LEGACY_NAMES_FILE))
+        .map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Migration step: move every image still in a map folder into the store and point the
+/// manifest at the blobs. Images that are missing are left as they are.
+///
+/// The blob each file becomes is recorded before any file moves, so a run interrupted
+/// halfway, or a manifest saved before the move, still resolves every image.
+pub fn adopt_legacy_images(manifest: &mut ImageManifest, data_dir: &Path) {
+    let mut legacy_names = load_legacy_names(data_dir);
+    let mut pending_moves = Vec::new();
+    for (map, images) in &manifest.images {
+        for step in images.iter().flat_map(|image| image.steps()) {
+            if is_blob_name(&step.filename) {
+                continue;
+            }
+            let key = legacy_key(map, &step.filename);
+            let path = data_dir.join(map).join(&step.filename);
+            if !path.is_file() || pending_moves.iter().any(|(moving, _)| *moving == path) {
+                continue;
+            }
+            match sha256_file(&path) {
+                Ok(hash) => {
+                    let name = blob_name(&hash, &legacy_extension(&step.filename));
+                    legacy_names.insert(key, name.clone());
+                    pending_moves.push((path, name));
+                }
+                Err(e) => log::error!("Failed to read {}: {}", path.display(), e),
+            }
+        }
+    }
+    if !pending_moves.is_empty()
+        && let Err(e) = save_legacy_names(data_dir, &legacy_names)
+    {
+        // Without the record, older manifests couldn't find the moved files
+        log::error!("Failed to record the images moving into the store: {}", e);
+        return;
+    }
+    for (path, name) in &pending_moves {
+        let moved = move_into_store(data_dir, path, name).and_then(|moved| {
+            if !moved {
+                // Already stored from another lineup or an earlier run
+                fs::remove_file(path)?;
+            }
+            Ok(())
+        });
+        if let Err(e) = moved {
+            log::error!("Failed to move {} into the store: {}", path.display(), e);
+        }
+    }
+
+    for (map, images) in manifest.images.iter_mut() {
+        for image in images.iter_mut() {
+            let filenames = std::iter::once(&mut image.filename).chain(
+                image
+                    .extra_images
+                    .iter_mut()
+                    .map(|extra| &mut extra.filename),
+            );
+            for filename in filenames {
+                if let Some(name) = legacy_names.get(&legacy_key(map, filename))
+                    && image_path(data_dir, map, name).exists()
+                {
+                    *filename = name.clone();
+                }
+            }
+        }
+    }
+}
+
+/// Move one image still in a map folder into the store, recording its blob like
+/// `adopt_legacy_images` does, so a lineup restored with the old name finds it again.
+pub fn stash_legacy_image(data_dir: &Path, map: &str, filename: &str) -> io::Result<()> {
+    let path = data_dir.join(map).join(filename);
+    let name = blob_name(&sha256_file(&path)?, &legacy_extension(filename));
+    let mut legacy_names = load_legacy_names(data_dir);
+    legacy_names.insert(legacy_key(map, filename), name.clone());
+    save_legacy_names(data_dir, &legacy_names)?;
+    if !move_into_store(data_dir, &path, &name)? {
+        fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{ImageMeta, ImageRole, LineupImage};
+
+    fn lineup(map: &str, filename: &str, extras: &[&str]) -> ImageMeta {
+        ImageMeta {
+            id: filename.to_string(),
+            filename: filename.to_string(),
+            map: map.to_string(),
+            extra_images: extras
+                .iter()
+                .map(|extra| LineupImage {
+                    filename: extra.to_string(),
+                    role: ImageRole::Aim,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_blob_names_and_paths() {
+        let hash = "ab".repeat(32);
+        let name = blob_name(&hash, "WEBP");
+        assert_eq!(name, format!("{}.webp", hash));
+        assert!(is_blob_name(&name));
+        for legacy in [
+            "smoke_20240101.webp",
+            "abc.webp",
+            &hash,
+            &format!("{}.", hash),
+        ] {
+            assert!(!is_blob_name(legacy), "{}", legacy);
+        }
+        assert!(!is_blob_name(&format!("{}.webp", "AB".repeat(32))));
+
+        let data_dir = Path::new("data");
+        assert_eq!(
+            image_path(data_dir, "de_dust2", &name),
+            data_dir
+                .join(OBJECTS_DIR)
+                .join("ab")
+                .join(format!("{}.webp", &hash[2..]))
+        );
+        assert_eq!(
+            image_path(data_dir, "de_dust2", "smoke.webp"),
+            data_dir.join("de_dust2").join("smoke.webp")
+        );
+    }
+
+    #[test]
+    fn test_same_content_is_stored_once() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let source = data_dir.path().join("source.webp");
+        fs::write(&source, b"same image").unwrap();
+
+        let first = copy_into_store(data_dir.path(), &source, "webp").unwrap();
+        let second = copy_into_store(data_dir.path(), &source, "webp").unwrap();
+        assert_eq!(first, second);
+        assert_eq!(
+            fs::read(image_path(data_dir.path(), "", &first)).unwrap(),
+            b"same image"
+        );
+        let stored: Vec<_> = fs::read_dir(data_dir.path().join(OBJECTS_DIR))
+            .unwrap()
+            .flatten()
+            .collect();
+        // One fan-out folder holding one blob, and no temporary files left behind
+        assert_eq!(stored.len(), 1);
+        assert_eq!(fs::read_dir(stored[0].path()).unwrap().count(), 1);
+
+        fs::write(&source, b"another image").unwrap();
+        assert_ne!(
+            copy_into_store(data_dir.path(), &source, "webp").unwrap(),
+            first
+        );
+    }
+
+    #[test]
+    fn test_collect_garbage_keeps_blobs_any_root_uses() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut blobs = Vec::new();
+        for content in ["current", "backup", "unused"] {
+            let source = data_dir.path().join(content);
+            fs::write(&source, content).unwrap();
+            blobs.push(copy_into_store(data_dir.path(), &source, "webp").unwrap());
+        }
+        let mut current = ImageManifest::default();
+        current = current.clone_and_add(lineup("de_dust2", &blobs[0], &[&blobs[0]]), "de_dust2");
+        let mut backup = ImageManifest::default();
+        backup = backup.clone_and_add(lineup("de_dust2", "smoke.webp", &[&blobs[1]]), "de_dust2");
+
+        assert_eq!(blob_references(&current, &BTreeMap::new())[&blobs[0]], 2);
+        let report = collect_garbage(data_dir.path(), &[current, backup]).unwrap();
+        assert_eq!(report.removed, 1);
+        assert_eq!(report.freed_bytes, "unused".len() as u64);
+        assert!(image_path(data_dir.path(), "", &blobs[0]).exists());
+        assert!(image_path(data_dir.path(), "", &blobs[1]).exists());
+        assert!(!image_path(data_dir.path(), "", &blobs[2]).exists());
+    }
+
+    #[test]
+    fn test_adopt_legacy_images() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let map_dir = data_dir.path().join("de_mirage");
+        fs::create_dir_all(&map_dir).unwrap();
+        fs::write(map_dir.join("jungle.webp"), b"jungle").unwrap();
+        fs::write(map_dir.join("jungle_copy.webp"), b"jungle").unwrap();
+        fs::write(map_dir.join("window.webp"), b"window").unwrap();
+        let mut manifest = ImageManifest::default();
+        for image in [
+            lineup("de_mirage", "jungle.webp", &["window.webp"]),
+            lineup("de_mirage", "jungle_copy.webp", &[]),
+            lineup("de_mirage", "missing.webp", &[]),
+        ] {
+            manifest = manifest.clone_and_add(image, "de_mirage");
+        }
+        let before = manifest.clone();
+
+        adopt_legacy_images(&mut manifest, data_dir.path());
+        let images = &manifest.images["de_mirage"];
+        // Both copies of the jungle screenshot now share one blob
+        assert!(is_blob_name(&images[0].filename));
+        assert_eq!(images[0].filename, images[1].filename);
+        assert!(is_blob_name(&images[0].extra_images[0].filename));
+        assert_eq!(images[2].filename, "missing.webp");
+        assert!(!map_dir.join("jungle.webp").exists());
+        assert!(!map_dir.join("jungle_copy.webp").exists());
+        for image in images.iter().take(2) {
+            for step in image.steps() {
+                assert!(image_path(data_dir.path(), "de_mirage", &step.filename).exists());
+            }
+        }
+
+        // Running it again, or on a manifest saved before the move, gives the same result
+        let adopted = manifest.clone();
+        adopt_legacy_images(&mut manifest, data_dir.path());
+        assert_eq!(manifest, adopted);
+        let mut older = before;
+        adopt_legacy_images(&mut older, data_dir.path());
+        assert_eq!(older, adopted);
+    }
+
+    #[test]
+    fn test_stashed_legacy_image_is_found_again() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let map_dir = data_dir.path().join("de_mirage");
+        fs::create_dir_all(&map_dir).unwrap();
+        fs::write(map_dir.join("jungle.webp"), b"jungle").unwrap();
+
+        stash_legacy_image(data_dir.path(), "de_mirage", "jungle.webp").unwrap();
+        assert!(!map_dir.join("jungle.webp").exists());
+
+        // The lineup comes back, e.g. on undo, still under its old name
+        let mut manifest = ImageManifest::default()
+            .clone_and_add(lineup("de_mirage", "jungle.webp", &[]), "de_mirage");
+        adopt_legacy_images(&mut manifest, data_dir.path());
+        let filename = &manifest.images["de_mirage"][0].filename;
+        assert!(is_blob_name(filename));
+        assert_eq!(
+            fs::read(image_path(data_dir.path(), "de_mirage", filename)).unwrap(),
+            b"jungle"
+        );
+
+        assert_eq!(
+            stash_legacy_image(data_dir.path(), "de_mirage", "jungle.webp")
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+}
@@ -1,5 +1,6 @@
+use crate::migrations::MANIFEST_SCHEMA_VERSION;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::time::SystemTime;
 use strum_macros::EnumIter;
 
@@ -15,28 +16,588 @@ pub enum NadeType {
     Grenade,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
-pub struct ImageMeta {
+/// Which side a lineup is thrown from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter)]
+pub enum Side {
+    CT,
+    T,
+    #[default]
+    Either,
+}
+
+impl Side {
+    /// Whether a lineup for this side should be shown when filtering by `filter`.
+    /// Lineups usable by either side match every filter.
+    pub fn matches(self, filter: Side) -> bool {
+        self == Side::Either || filter == Side::Either || self == filter
+    }
+}
+
+/// How the thrower moves while releasing the grenade
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter)]
+pub enum Movement {
+    #[default]
+    Stand,
+    Walk,
+    Run,
+    Crouch,
+    Jump,
+}
+
+/// Which mouse button(s) the grenade is released with
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter)]
+pub enum ThrowClick {
+    #[default]
+    Left,
+    Right,
+    Both,
+}
+
+/// Structured description of how a lineup is thrown
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ThrowTechnique {
+    #[serde(default)]
+    pub movement: Movement,
+    #[serde(default)]
+    pub click: ThrowClick,
+    #[serde(default)]
+    pub jump_throw_bind: bool, // Only reliable with a jump-throw bind
+}
+
+/// Grid filter over `ThrowTechnique`. `None` fields match everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TechniqueFilter {
+    pub movement: Option<Movement>,
+    pub click: Option<ThrowClick>,
+    pub without_jump_throw_bind: bool, // Hide lineups that need a jump-throw bind
+}
+
+impl TechniqueFilter {
+    pub fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+
+    pub fn matches(&self, technique: &ThrowTechnique) -> bool {
+        self.movement.is_none_or(|m| m == technique.movement)
+            && self.click.is_none_or(|c| c == technique.click)
+            && !(self.without_jump_throw_bind && technique.jump_throw_bind)
+    }
+}
+
+/// What a single screenshot of a lineup shows
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter)]
+pub enum ImageRole {
+    #[default]
+    Stand, // Where to stand
+    Aim,     // Where to aim
+    Landing, // Where the grenade lands
+}
+
+impl ImageRole {
+    /// Suggested role for the image at `index` when a lineup is built up step by step
+    pub fn for_step(index: usize) -> Self {
+        match index {
+            0 => ImageRole::Stand,
+            1 => ImageRole::Aim,
+            _ => ImageRole::Landing,
+        }
+    }
+}
+
+/// One image file belonging to a lineup
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LineupImage {
     pub filename: String,
+    pub role: ImageRole,
+}
+
+/// A point in game world units, as printed by the console `getpos` command
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct WorldPos {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+/// View angles in degrees, as in the console `setang pitch yaw roll` command
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ViewAngles {
+    pub pitch: f32,
+    pub yaw: f32,
+}
+
+/// A player position and view, as printed by the console `getpos` command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Setpos {
+    pub pos: WorldPos,
+    pub angles: Option<ViewAngles>,
+}
+
+impl Setpos {
+    /// Parse pasted `getpos` (or `getpos_exact`) output, e.g.
+    /// `setpos -1234.5 567.0 -160.0;setang 4.5 -90.0 0.0`.
+    /// The position is required; the angles are optional.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut pos = None;
+        let mut angles = None;
+        for command in text.split([';', '\n']) {
+            let mut tokens = command.split_whitespace();
+            let Some(name) = tokens.next() else {
+                continue;
+            };
+            let values: Vec<f32> = tokens.map_while(|t| t.parse().ok()).collect();
+            match name.to_ascii_lowercase().as_str() {
+                "setpos" | "setpos_exact" if values.len() >= 3 => {
+                    pos = Some(WorldPos {
+                        x: values[0],
+                        y: values[1],
+                        z: values[2],
+                    });
+                }
+                "setang" | "setang_exact" if values.len() >= 2 => {
+                    angles = Some(ViewAngles {
+                        pitch: values[0],
+                        yaw: values[1],
+                    });
+                }
+                _ => {}
+            }
+        }
+        pos.map(|pos| Self { pos, angles })
+    }
+
+    /// Console command that teleports to this position (and view, if known)
+    pub fn command(&self) -> String {
+        let mut command = format!("setpos {} {} {}", self.pos.x, self.pos.y, self.pos.z);
+        if let Some(angles) = self.angles {
+            command.push_str(&format!(";setang {} {} 0", angles.pitch, angles.yaw));
+        }
+        command
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ImageMeta {
+    #[serde(default)]
+    pub id: String, // Stable unique identifier, independent of the stored filename
+    pub filename: String, // First step of the lineup, also used as the grid thumbnail
     pub map: String,
     pub nade_type: NadeType,
     pub notes: String,    // How to throw
     pub position: String, // Where this nade is for (e.g., "A Main Smoke")
     #[serde(default)]
+    pub side: Side,
+    #[serde(default)]
+    pub technique: ThrowTechnique,
+    #[serde(default)]
+    pub role: ImageRole, // Role of `filename`
+    #[serde(default)]
+    pub extra_images: Vec<LineupImage>, // Further steps after `filename`, in order
+    #[serde(default)]
+    pub tags: Vec<String>, // Normalized with `normalize_tag`, e.g. "retake", "one-way"
+    #[serde(default)]
+    pub throw_pos: Option<WorldPos>, // Where the player stands when throwing
+    #[serde(default)]
+    pub landing_pos: Option<WorldPos>, // Where the grenade ends up
+    #[serde(default)]
+    pub view_angles: Option<ViewAngles>, // Where to look when throwing, from `getpos`
+    #[serde(default)]
     pub order: usize, // Order position for reordering images
 }
 
+impl ImageMeta {
+    /// All image files of this lineup in step order, starting with `filename`.
+    pub fn steps(&self) -> Vec<LineupImage> {
+        std::iter::once(LineupImage {
+            filename: self.filename.clone(),
+            role: self.role,
+        })
+        .chain(self.extra_images.iter().cloned())
+        .collect()
+    }
+
+    /// The throw position and view angles, if a throw position is known
+    pub fn setpos(&self) -> Option<Setpos> {
+        self.throw_pos.map(|pos| Setpos {
+            pos,
+            angles: self.view_angles,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct MapMeta {
     pub last_accessed: SystemTime,
 }
 
+/// Days a deleted lineup stays in the trash unless the user changes it
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
+/// A deleted lineup, kept until it's restored or purged
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrashedLineup {
+    pub image: ImageMeta, // As it was when deleted; `image.map` is the map it came from
+    pub deleted_at: SystemTime,
+}
+
+/// The trash bin, stored in `.trash/trash.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Trash {
+    #[serde(default = "default_trash_retention_days")]
+    pub retention_days: u32, // 0 keeps lineups until the trash is emptied by hand
+    #[serde(default)]
+    pub lineups: Vec<TrashedLineup>, // Oldest first
+}
+
+fn default_trash_retention_days() -> u32 {
+    DEFAULT_TRASH_RETENTION_DAYS
+}
+
+impl Default for Trash {
+    fn default() -> Self {
+        Self {
+            retention_days: DEFAULT_TRASH_RETENTION_DAYS,
+            lineups: Vec::new(),
+        }
+    }
+}
+
+impl Trash {
+    /// Add a deleted lineup, replacing an earlier entry for the same id
+    pub fn add(&mut self, image: ImageMeta, deleted_at: SystemTime) {
+        self.take(&image.id);
+        self.lineups.push(TrashedLineup { image, deleted_at });
+    }
+
+    /// Take the lineup with image `id` out of the trash
+    pub fn take(&mut self, id: &str) -> Option<TrashedLineup> {
+        let index = self.lineups.iter().position(|entry| entry.image.id == id)?;
+        Some(self.lineups.remove(index))
+    }
+
+    /// When a lineup deleted at `deleted_at` is purged, or `None` if it's kept until emptied
+    pub fn expires_at(&self, deleted_at: SystemTime) -> Option<SystemTime> {
+        (self.retention_days > 0).then(|| {
+            deleted_at + std::time::Duration::from_secs(u64::from(self.retention_days) * 86_400)
+        })
+    }
+
+    /// Drop every lineup whose retention period is over at `now`. Returns how many went.
+    pub fn purge_expired(&mut self, now: SystemTime) -> usize {
+        let lineups = std::mem::take(&mut self.lineups);
+        let before = lineups.len();
+        self.lineups = lineups
+            .into_iter()
+            .filter(|entry| {
+                self.expires_at(entry.deleted_at)
+                    .is_none_or(|expires_at| expires_at > now)
+            })
+            .collect();
+        before - self.lineups.len()
+    }
+
+    /// The trashed lineups as a manifest, so garbage collection keeps their images
+    pub fn as_manifest(&self) -> ImageManifest {
+        let mut manifest = ImageManifest::default();
+        for entry in &self.lineups {
+            manifest
+                .images
+                .entry(entry.image.map.clone())
+                .or_default()
+                .push(entry.image.clone());
+        }
+        manifest
+    }
+}
+
+/// Radar image and overview parameters of a map, as in the game's overview files
+/// (`resource/overviews/<map>.txt`). `pos_x`/`pos_y` is the world position of the
+/// radar's top-left corner and `scale` is world units per radar pixel.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RadarOverview {
+    #[serde(default)]
+    pub image: Option<String>, // Filename in the `.radars` directory
+    pub pos_x: f32,
+    pub pos_y: f32,
+    pub scale: f32,
+}
+
+impl Default for RadarOverview {
+    fn default() -> Self {
+        Self {
+            image: None,
+            pos_x: 0.0,
+            pos_y: 0.0,
+            scale: 1.0,
+        }
+    }
+}
+
+impl RadarOverview {
+    /// Radar pixel coordinates of a world position. World y grows north, radar y grows down.
+    pub fn world_to_radar(&self, pos: WorldPos) -> (f32, f32) {
+        (
+            (pos.x - self.pos_x) / self.scale,
+            (self.pos_y - pos.y) / self.scale,
+        )
+    }
+
+    /// Read `pos_x`, `pos_y` and `scale` from the text of an overview file.
+    /// Returns `None` unless all three are present and the scale is positive.
+    pub fn parse_overview(text: &str) -> Option<(f32, f32, f32)> {
+        let (mut pos_x, mut pos_y, mut scale) = (None, None, None);
+        for line in text.lines() {
+            let mut tokens = line.split('"').map(str::trim).filter(|t| !t.is_empty());
+            let (Some(key), Some(value)) = (tokens.next(), tokens.next()) else {
+                continue;
+            };
+            let Ok(value) = value.parse::<f32>() else {
+                continue;
+            };
+            match key.to_ascii_lowercase().as_str() {
+                "pos_x" => pos_x = Some(value),
+                "pos_y" => pos_y = Some(value),
+                "scale" => scale = Some(value),
+                _ => {}
+            }
+        }
+        match (pos_x, pos_y, scale) {
+            (Some(x), Some(y), Some(s)) if s > 0.0 => Some((x, y, s)),
+            _ => None,
+        }
+    }
+}
+
+/// Maps seeded into a fresh map list (the active-duty pool plus a few classics).
+pub const DEFAULT_MAPS: [&str; 10] = [
+    "de_ancient",
+    "de_anubis",
+    "de_cache",
+    "de_dust2",
+    "de_inferno",
+    "de_mirage",
+    "de_nuke",
+    "de_overpass",
+    "de_train",
+    "de_vertigo",
+];
+
+/// One entry in the user's map list.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MapEntry {
+    /// Map name as used by the game. Also the data folder and manifest key, so it never changes.
+    pub name: String,
+    /// Optional label shown in the UI instead of `name`
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// Hidden maps keep their lineups but are left out of the map selector
+    #[serde(default)]
+    pub hidden: bool,
+    /// Steam Workshop file id, for community and scrim maps
+    #[serde(default)]
+    pub workshop_id: Option<u64>,
+    /// Radar shown in the radar panel
+    #[serde(default)]
+    pub radar: RadarOverview,
+}
+
+impl MapEntry {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            display_name: None,
+            hidden: false,
+            workshop_id: None,
+            radar: RadarOverview::default(),
+        }
+    }
+
+    /// The name to show in the UI
+    pub fn label(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// The user's ordered map list, persisted separately from the manifest in `maps.json`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MapConfig {
+    pub maps: Vec<MapEntry>,
+}
+
+impl Default for MapConfig {
+    fn default() -> Self {
+        Self {
+            maps: DEFAULT_MAPS
+                .iter()
+                .map(|name| MapEntry::new(name))
+                .collect(),
+        }
+    }
+}
+
+impl MapConfig {
+    pub fn find(&self, name: &str) -> Option<&MapEntry> {
+        self.maps.iter().find(|entry| entry.name == name)
+    }
+
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut MapEntry> {
+        self.maps.iter_mut().find(|entry| entry.name == name)
+    }
+
+    /// Display label for a map name, falling back to the name itself for unknown maps
+    pub fn label_for<'a>(&'a self, name: &'a str) -> &'a str {
+        self.find(name).map_or(name, MapEntry::label)
+    }
+
+    /// Maps shown in the map selector, in the user's order
+    pub fn visible_maps(&self) -> impl Iterator<Item = &MapEntry> {
+        self.maps.iter().filter(|entry| !entry.hidden)
+    }
+
+    /// Add a new map at the end of the list.
+    /// The name becomes a folder name, so path separators and blank names are rejected.
+    pub fn add_map(&mut self, name: &str, workshop_id: Option<u64>) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Map name cannot be empty.".to_string());
+        }
+        if name == "." || name == ".." || name.contains(['/', '\\', ':']) {
+            return Err(format!("'{}' is not a valid map name.", name));
+        }
+        if self.find(name).is_some() {
+            return Err(format!("Map '{}' is already in the list.", name));
+        }
+        self.maps.push(MapEntry {
+            workshop_id,
+            ..MapEntry::new(name)
+        });
+        Ok(())
+    }
+
+    /// Move the map at `from_index` to `to_index`. Out of range indices are ignored.
+    pub fn move_map(&mut self, from_index: usize, to_index: usize) {
+        if from_index < self.maps.len() && to_index < self.maps.len() {
+            let entry = self.maps.remove(from_index);
+            self.maps.insert(to_index, entry);
+        }
+    }
+
+    /// Append any map that has lineups in the manifest but is missing from the list.
+    /// Returns true if the list changed.
+    pub fn discover_maps(&mut self, manifest: &ImageManifest) -> bool {
+        let mut missing: Vec<&String> = manifest
+            .images
+            .iter()
+            .filter(|(name, images)| !images.is_empty() && self.find(name).is_none())
+            .map(|(name, _)| name)
+            .collect();
+        missing.sort();
+        for name in &missing {
+            self.maps.push(MapEntry::new(name));
+        }
+        !missing.is_empty()
+    }
+}
+
+/// Number of players a strat hands utility to
+pub const STRAT_PLAYERS: u8 = 5;
+
+/// One piece of utility in a strat: which lineup, who throws it, and when
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StratStep {
+    pub image_id: String,
+    pub player: u8, // Player role, 1 to STRAT_PLAYERS
+    pub order: u32, // Timing or throw order; steps with the same number are thrown together
+}
+
+/// A named team execute: lineups on one map, each assigned to a player and a timing
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Strat {
+    pub id: String,
+    pub name: String, // e.g., "A execute"
+    pub map: String,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub steps: Vec<StratStep>,
+}
+
+impl Strat {
+    pub fn new(name: &str, map: &str) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            map: map.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Append a lineup as the next throw, handing it to the next player in turn.
+    /// Returns false if the lineup is already part of the strat.
+    pub fn add_lineup(&mut self, image_id: &str) -> bool {
+        if self.steps.iter().any(|step| step.image_id == image_id) {
+            return false;
+        }
+        let order = self
+            .steps
+            .iter()
+            .map(|step| step.order)
+            .max()
+            .map_or(1, |o| o + 1);
+        self.steps.push(StratStep {
+            image_id: image_id.to_string(),
+            player: (self.steps.len() % STRAT_PLAYERS as usize) as u8 + 1,
+            order,
+        });
+        true
+    }
+
+    /// The steps grouped by timing, in throw order. Within a group steps are sorted by player.
+    pub fn timeline(&self) -> Vec<(u32, Vec<&StratStep>)> {
+        let mut steps: Vec<&StratStep> = self.steps.iter().collect();
+        steps.sort_by_key(|step| (step.order, step.player));
+        let mut timeline: Vec<(u32, Vec<&StratStep>)> = Vec::new();
+        for step in steps {
+            match timeline.last_mut() {
+                Some((order, group)) if *order == step.order => group.push(step),
+                _ => timeline.push((step.order, vec![step])),
+            }
+        }
+        timeline
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ImageManifest {
+    #[serde(default)]
+    pub schema_version: u32, // 0 for manifests written before versioning; see `migrations`
     pub images: HashMap<String, Vec<ImageMeta>>, // map_name -> Vec<ImageMeta>
     pub maps: HashMap<String, MapMeta>,          // map_name -> MapMeta
     #[serde(default)]
-    pub webp_migration_completed: bool, // Tracks if the one-time WebP migration has been performed
+    pub strats: Vec<Strat>, // Team executes built from the lineups above
+}
+
+impl Default for ImageManifest {
+    fn default() -> Self {
+        // A new manifest needs no migrations
+        Self {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            images: HashMap::new(),
+            maps: HashMap::new(),
+            strats: Vec::new(),
+        }
+    }
+}
+
+/// Normalize a user-entered tag (trimmed, lowercase). Returns `None` for blank input.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() { None } else { Some(tag) }
+}
+
+/// Generate a new unique identifier for an `ImageMeta`.
+pub fn new_image_id() -> String {
+    uuid::Uuid::new_v4().to_string()
 }
 
 impl ImageManifest {
@@ -46,6 +607,9 @@ impl ImageManifest {
 
         // Assign order value based on current number of images for this map
         new_image.order = images_for_map.len();
+        if new_image.id.is_empty() {
+            new_image.id = new_image_id();
+        }
 
         images_for_map.push(new_image);
         // Optionally, update map metadata if needed, e.g., last_accessed
@@ -58,14 +622,14 @@ impl ImageManifest {
     pub fn migrate_image_order(&mut self) {
         for (_map_name, images) in self.images.iter_mut() {
             // Check if images need order migration (all have order 0 or inconsistent ordering)
-            let needs_migration = images.len() > 1 && 
-                (images.iter().all(|img| img.order == 0) || 
-                 !Self::has_consistent_ordering(images));
-            
+            let needs_migration = images.len() > 1
+                && (images.iter().all(|img| img.order == 0)
+                    || !Self::has_consistent_ordering(images));
+
             if needs_migration {
                 // Sort by filename first to maintain some consistency
                 images.sort_by(|a, b| a.filename.cmp(&b.filename));
-                
+
                 // Assign sequential order values
                 for (idx, image) in images.iter_mut().enumerate() {
                     image.order = idx;
@@ -74,15 +638,148 @@ impl ImageManifest {
         }
     }
 
+    /// Backfill ids for images created before the `id` field existed.
+    /// Returns true if any image was assigned a new id.
+    pub fn assign_missing_ids(&mut self) -> bool {
+        let mut changed = false;
+        for image in self.images.values_mut().flatten() {
+            if image.id.is_empty() {
+                image.id = new_image_id();
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Find an image by its id across all maps
+    pub fn find_image(&self, id: &str) -> Option<&ImageMeta> {
+        self.images.values().flatten().find(|img| img.id == id)
+    }
+
+    /// A copy holding only the images `keep` accepts, the metadata of maps that still have
+    /// images, and the strats whose every lineup was kept.
+    pub fn subset(&self, keep: impl Fn(&ImageMeta) -> bool) -> Self {
+        let mut images = HashMap::new();
+        for (map_name, map_images) in &self.images {
+            let kept: Vec<ImageMeta> = map_images.iter().filter(|img| keep(img)).cloned().collect();
+            if !kept.is_empty() {
+                images.insert(map_name.clone(), kept);
+            }
+        }
+        let maps = self
+            .maps
+            .iter()
+            .filter(|(map_name, _)| images.contains_key(*map_name))
+            .map(|(map_name, meta)| (map_name.clone(), meta.clone()))
+            .collect();
+        let kept_ids: HashSet<&str> = images
+            .values()
+            .flatten()
+            .map(|img| img.id.as_str())
+            .collect();
+        let strats = self
+            .strats
+            .iter()
+            .filter(|strat| {
+                !strat.steps.is_empty()
+                    && strat
+                        .steps
+                        .iter()
+                        .all(|step| kept_ids.contains(step.image_id.as_str()))
+            })
+            .cloned()
+            .collect();
+
+        Self {
+            schema_version: self.schema_version,
+            images,
+            maps,
+            strats,
+        }
+    }
+
+    /// Find a strat by its id
+    pub fn find_strat(&self, id: &str) -> Option<&Strat> {
+        self.strats.iter().find(|strat| strat.id == id)
+    }
+
+    /// Strats for a map, sorted by name
+    pub fn strats_for_map(&self, map_name: &str) -> Vec<&Strat> {
+        let mut strats: Vec<&Strat> = self
+            .strats
+            .iter()
+            .filter(|strat| strat.map == map_name)
+            .collect();
+        strats.sort_by_key(|strat| strat.name.to_lowercase());
+        strats
+    }
+
+    /// Take a lineup out of its map and every strat that uses it. A map left without
+    /// lineups leaves the manifest too. Returns false if the lineup's map wasn't found.
+    pub fn remove_image(&mut self, image: &ImageMeta) -> bool {
+        self.remove_image_from_strats(&image.id);
+        let Some(images_in_map) = self.images.get_mut(&image.map) else {
+            return false;
+        };
+        images_in_map.retain(|meta| meta.id != image.id);
+        if images_in_map.is_empty() {
+            self.images.remove(&image.map);
+            self.maps.remove(&image.map);
+        }
+        true
+    }
+
+    /// Drop a lineup from every strat that uses it. Returns true if any strat changed.
+    pub fn remove_image_from_strats(&mut self, image_id: &str) -> bool {
+        let mut changed = false;
+        for strat in &mut self.strats {
+            let before = strat.steps.len();
+            strat.steps.retain(|step| step.image_id != image_id);
+            changed |= strat.steps.len() != before;
+        }
+        changed
+    }
+
+    /// Every tag in use, with the number of images carrying it, sorted by tag
+    pub fn tag_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for tag in self.images.values().flatten().flat_map(|img| &img.tags) {
+            *counts.entry(tag.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Rename `from` to `to` on every image. Images that already carry `to`
+    /// keep a single copy, so renaming onto an existing tag merges the two.
+    /// Returns the number of images changed.
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> usize {
+        let mut changed = 0;
+        for image in self.images.values_mut().flatten() {
+            if !image.tags.iter().any(|t| t == from) {
+                continue;
+            }
+            let already_has_target = image.tags.iter().any(|t| t == to);
+            if already_has_target {
+                image.tags.retain(|t| t != from);
+            } else {
+                for tag in image.tags.iter_mut().filter(|t| *t == from) {
+                    *tag = to.to_string();
+                }
+            }
+            changed += 1;
+        }
+        changed
+    }
+
     /// Check if images have consistent ordering (no duplicates, sequential)
     fn has_consistent_ordering(images: &[ImageMeta]) -> bool {
         if images.is_empty() {
             return true;
         }
-        
+
         let mut orders: Vec<usize> = images.iter().map(|img| img.order).collect();
         orders.sort_unstable();
-        
+
         // Check if orders are sequential starting from 0
         orders.iter().enumerate().all(|(idx, &order)| order == idx)
     }
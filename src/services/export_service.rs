@@ -1,18 +1,35 @@
 // src/services/export_service.rs
-use crate::persistence::ImageManifest;
+use crate::object_store::{self, copy_hashed, sha256_file};
+use crate::persistence::{ImageManifest, ImageMeta, LineupImage, Strat, StratStep, new_image_id};
 use crate::services::persistence_service::{PersistenceService, PersistenceServiceError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{File, create_dir_all};
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tempfile::TempDir;
 use zip::{ZipWriter, write::FileOptions};
 
+/// Version of the `.nadex` package layout written by this build. Bump it whenever a
+/// package can no longer be read correctly by older builds.
+pub const PACKAGE_FORMAT_VERSION: u32 = 1;
+
 #[derive(Debug)]
 pub enum ExportServiceError {
     IoError(std::io::Error),
     ZipError(zip::result::ZipError),
     SerializationError(String),
     ImportError(String),
+    NothingToExport, // The selection matched no lineups
+    UnsupportedPackageVersion {
+        format_version: u32,
+        app_version: String, // Nadex version that wrote the package
+    },
+    CorruptedFiles(Vec<String>), // Package files whose checksum doesn't match the header
+    Cancelled,
 }
 
 impl std::fmt::Display for ExportServiceError {
@@ -24,6 +41,21 @@ impl std::fmt::Display for ExportServiceError {
                 write!(f, "Serialization error: {}", msg)
             }
             ExportServiceError::ImportError(msg) => write!(f, "Import error: {}", msg),
+            ExportServiceError::NothingToExport => write!(f, "No lineups match the selection"),
+            ExportServiceError::UnsupportedPackageVersion {
+                format_version,
+                app_version,
+            } => write!(
+                f,
+                "This package was made with Nadex {} (package format {}), but this version only reads format {} and older. Update Nadex to import it.",
+                app_version, format_version, PACKAGE_FORMAT_VERSION
+            ),
+            ExportServiceError::CorruptedFiles(files) => write!(
+                f,
+                "The package is damaged; these files don't match their checksums: {}",
+                files.join(", ")
+            ),
+            ExportServiceError::Cancelled => write!(f, "Cancelled"),
         }
     }
 }
@@ -64,26 +96,262 @@ impl From<PersistenceServiceError> for ExportServiceError {
     }
 }
 
+/// `package.json`, the header of a `.nadex` package. Packages written before the header
+/// existed don't have one and are read as they are.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageHeader {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub author: Option<String>,
+    pub entry_count: usize, // Number of lineups in the manifest
+    #[serde(default)]
+    pub checksums: BTreeMap<String, String>, // Zip path -> hex SHA-256 of every image
+}
+
+/// A read package: its header (if any), manifest, and staged images
+type PackageContents = (Option<PackageHeader>, ImageManifest, StagedImages);
+
+/// The images of a package, unpacked into a staging folder inside the data dir so they
+/// can be renamed into place. The folder and whatever is left in it go away on drop.
+#[derive(Debug)]
+struct StagedImages {
+    dir: TempDir,
+    files: HashMap<String, StagedImage>, // Zip path -> staged file
+}
+
 #[derive(Debug)]
+struct StagedImage {
+    path: PathBuf,
+    size: u64,
+    checksum: String, // Hex SHA-256
+}
+
+/// How a lineup in an import package relates to the library
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportStatus {
+    New,
+    Duplicate {
+        existing_id: String, // Same lineup with identical details and images
+    },
+    Conflict {
+        existing_id: String,
+        differences: Vec<&'static str>, // What differs, e.g. "notes" or "images"
+    },
+}
+
+/// A lineup from an import package and how it relates to the library
+#[derive(Debug, Clone)]
+pub struct ImportItem {
+    pub incoming: ImageMeta,
+    pub status: ImportStatus,
+}
+
+/// What to do with a package lineup that conflicts with one in the library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictResolution {
+    #[default]
+    KeepMine,
+    TakeTheirs, // Replace the library's details and images, keeping its id and order
+    KeepBoth,
+}
+
+/// The outcome of `ExportService::analyze_import`: every lineup in a package classified
+/// against the library. Nothing is written until it's passed to `apply_import`.
+pub struct ImportPlan {
+    pub items: Vec<ImportItem>,
+    pub new_strats: Vec<String>, // Names of strats the library doesn't have yet
+    pub skipped: usize,          // Lineups whose image is missing from the package
+    pub header: Option<PackageHeader>,
+    package_manifest: ImageManifest,
+    staged: StagedImages,
+}
+
+// Summarized, since plans travel through the logged `AppAction` channel
+impl std::fmt::Debug for ImportPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImportPlan")
+            .field("items", &self.items.len())
+            .field("new_strats", &self.new_strats)
+            .field("skipped", &self.skipped)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ImportPlan {
+    /// Number of items with a status matching `predicate`
+    pub fn count(&self, predicate: impl Fn(&ImportStatus) -> bool) -> usize {
+        self.items
+            .iter()
+            .filter(|item| predicate(&item.status))
+            .count()
+    }
+}
+
+/// The outcome of `ExportService::apply_import`: lineups whose images are in the object
+/// store, waiting to be merged into the library with `merge_import`.
+pub struct ImportedLineups {
+    entries: Vec<ImportedEntry>,
+    strats: Vec<Strat>,  // Every strat in the package, with package lineup ids
+    moved: Vec<PathBuf>, // Images this import added to the store
+}
+
+// Summarized like `ImportPlan`, since it's sent through the `AppAction` channel too
+impl std::fmt::Debug for ImportedLineups {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImportedLineups")
+            .field("entries", &self.entries.len())
+            .field("strats", &self.strats.len())
+            .field("moved", &self.moved.len())
+            .finish()
+    }
+}
+
+enum ImportedEntry {
+    Link {
+        incoming_id: String,
+        existing_id: String, // Library lineup the package's is the same as, or lost to
+    },
+    Replace {
+        existing_id: String,
+        meta: ImageMeta, // Still has the package's id, map and order
+    },
+    Add {
+        meta: ImageMeta,
+    },
+}
+
 pub struct ExportService {
     persistence_service: Arc<PersistenceService>,
+    author: Option<String>, // Written to the header of exported packages
+    progress: Option<Box<dyn Fn(usize, usize) + Send>>, // Called with (files done, total files)
+    cancel_flag: Option<Arc<AtomicBool>>,
 }
 
 impl ExportService {
     pub fn new(persistence_service: Arc<PersistenceService>) -> Self {
         Self {
             persistence_service,
+            author: None,
+            progress: None,
+            cancel_flag: None,
         }
     }
 
+    /// Call `progress` with (files done, total files) as a package is written, read,
+    /// or moved into the library
+    pub fn with_progress(mut self, progress: impl Fn(usize, usize) + Send + 'static) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Stop with `ExportServiceError::Cancelled` at the next file once `cancel_flag` is set.
+    /// Nothing is left behind: a partial export is deleted and an import is rolled back.
+    pub fn with_cancel_flag(mut self, cancel_flag: Arc<AtomicBool>) -> Self {
+        self.cancel_flag = Some(cancel_flag);
+        self
+    }
+
+    fn report_progress(&self, done: usize, total: usize) {
+        if let Some(progress) = &self.progress {
+            progress(done, total);
+        }
+    }
+
+    fn check_cancelled(&self) -> Result<(), ExportServiceError> {
+        match &self.cancel_flag {
+            Some(flag) if flag.load(Ordering::Relaxed) => Err(ExportServiceError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sign exported packages with `author`; blank names are left out
+    pub fn with_author(mut self, author: &str) -> Self {
+        let author = author.trim();
+        self.author = (!author.is_empty()).then(|| author.to_string());
+        self
+    }
+
     /// Export the entire library to a zip file
     pub fn export_library(
         &self,
         export_path: &Path,
         data_dir: &Path,
     ) -> Result<(), ExportServiceError> {
-        let manifest = self.persistence_service.load_manifest();
-        let manifest_json = serde_json::to_string_pretty(&manifest)
+        let manifest = self.persistence_service.load_manifest()?;
+        self.write_package(&manifest, export_path, data_dir)
+    }
+
+    /// Export only the lineups `keep` accepts, e.g. one map or the current filter.
+    /// Strats are included when all of their lineups are. Returns the number of lineups exported.
+    pub fn export_matching(
+        &self,
+        export_path: &Path,
+        data_dir: &Path,
+        keep: impl Fn(&ImageMeta) -> bool,
+    ) -> Result<usize, ExportServiceError> {
+        let manifest = self.persistence_service.load_manifest()?.subset(keep);
+        let count = manifest.images.values().map(Vec::len).sum();
+        if count == 0 {
+            return Err(ExportServiceError::NothingToExport);
+        }
+        self.write_package(&manifest, export_path, data_dir)?;
+        Ok(count)
+    }
+
+    /// Export a hand-picked set of lineups by id
+    pub fn export_images(
+        &self,
+        export_path: &Path,
+        data_dir: &Path,
+        image_ids: &[String],
+    ) -> Result<usize, ExportServiceError> {
+        self.export_matching(export_path, data_dir, |img| image_ids.contains(&img.id))
+    }
+
+    /// Export a strat together with its lineups
+    pub fn export_strat(
+        &self,
+        export_path: &Path,
+        data_dir: &Path,
+        strat_id: &str,
+    ) -> Result<usize, ExportServiceError> {
+        let manifest = self.persistence_service.load_manifest()?;
+        let strat = manifest
+            .find_strat(strat_id)
+            .ok_or(ExportServiceError::NothingToExport)?;
+        let image_ids: Vec<String> = strat
+            .steps
+            .iter()
+            .map(|step| step.image_id.clone())
+            .collect();
+        self.export_images(export_path, data_dir, &image_ids)
+    }
+
+    /// Write `manifest` and every image it references to a zip file, with a
+    /// `package.json` header holding the format version and a checksum per image.
+    /// The file is deleted again if writing fails or is cancelled.
+    fn write_package(
+        &self,
+        manifest: &ImageManifest,
+        export_path: &Path,
+        data_dir: &Path,
+    ) -> Result<(), ExportServiceError> {
+        let result = self.write_zip(manifest, export_path, data_dir);
+        if result.is_err() && export_path.exists() {
+            let _ = std::fs::remove_file(export_path);
+        }
+        result
+    }
+
+    fn write_zip(
+        &self,
+        manifest: &ImageManifest,
+        export_path: &Path,
+        data_dir: &Path,
+    ) -> Result<(), ExportServiceError> {
+        let manifest_json = serde_json::to_string_pretty(manifest)
             .map_err(|e| ExportServiceError::SerializationError(e.to_string()))?;
 
         // Create the zip file
@@ -97,293 +365,635 @@ impl ExportService {
         zip.start_file("manifest.json", options)?;
         zip.write_all(manifest_json.as_bytes())?;
 
-        // Add all images to zip
-        for (map_name, images) in &manifest.images {
-            for image_meta in images {
-                let image_path = data_dir.join(map_name).join(&image_meta.filename);
-
-                if image_path.exists() {
-                    // Create the relative path structure in the zip file
-                    let zip_path = format!("images/{}/{}", map_name, image_meta.filename);
-                    zip.start_file(&zip_path, options)?;
-
-                    // Read and write the image file
-                    let mut image_file = File::open(image_path)?;
-                    let mut buffer = Vec::new();
-                    image_file.read_to_end(&mut buffer)?;
-                    zip.write_all(&buffer)?;
-                }
-            }
+        // Add all images to zip, including every step of multi-image lineups
+        let mut written = HashSet::new();
+        let image_paths: Vec<(String, PathBuf)> = manifest
+            .images
+            .iter()
+            .flat_map(|(map_name, images)| {
+                images
+                    .iter()
+                    .flat_map(|image_meta| image_meta.steps())
+                    .map(move |step| {
+                        // Create the relative path structure in the zip file
+                        (
+                            format!("images/{}/{}", map_name, step.filename),
+                            object_store::image_path(data_dir, map_name, &step.filename),
+                        )
+                    })
+            })
+            .filter(|(_, image_path)| image_path.exists())
+            // Lineups on a map that share an image share its zip path; write it once
+            .filter(|(zip_path, _)| written.insert(zip_path.clone()))
+            .collect();
+        let mut checksums = BTreeMap::new();
+        for (index, (zip_path, image_path)) in image_paths.iter().enumerate() {
+            self.check_cancelled()?;
+            zip.start_file(zip_path, options)?;
+
+            // Stream the image file into the zip
+            let mut image_file = File::open(image_path)?;
+            let checksum = copy_hashed(&mut image_file, &mut zip)?;
+            checksums.insert(zip_path.clone(), checksum);
+            self.report_progress(index + 1, image_paths.len());
         }
 
+        let header = PackageHeader {
+            format_version: PACKAGE_FORMAT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: Utc::now(),
+            author: self.author.clone(),
+            entry_count: manifest.images.values().map(Vec::len).sum(),
+            checksums,
+        };
+        let header_json = serde_json::to_string_pretty(&header)
+            .map_err(|e| ExportServiceError::SerializationError(e.to_string()))?;
+        zip.start_file("package.json", options)?;
+        zip.write_all(header_json.as_bytes())?;
+
         zip.finish()?;
         Ok(())
     }
 
-    /// Import a library from a zip file, handling potential duplicates
-    pub fn import_library(&self, import_path: &Path) -> Result<ImageManifest, ExportServiceError> {
-        // Get the data directory using the new getter method
+    /// Read a package and classify every lineup in it against the library, without
+    /// touching the library. A lineup matches an existing one with the same id or,
+    /// failing that, the same map, position and nade type.
+    ///
+    /// The package's images are unpacked into a staging folder in the data dir, which
+    /// is removed when the plan is dropped.
+    pub fn analyze_import(&self, import_path: &Path) -> Result<ImportPlan, ExportServiceError> {
         let data_dir = self.persistence_service.get_data_dir();
+        let (header, package_manifest, staged) = self.read_package(import_path, &data_dir)?;
+
+        // Load the existing manifest, making sure every entry has an id to compare against
+        let mut current_manifest = self.persistence_service.load_manifest()?;
+        current_manifest.assign_missing_ids();
+
+        let mut map_names: Vec<&String> = package_manifest.images.keys().collect();
+        map_names.sort();
+        let mut items = Vec::new();
+        let mut skipped = 0;
+        for map_name in map_names {
+            let mut images: Vec<&ImageMeta> = package_manifest.images[map_name].iter().collect();
+            images.sort_by_key(|img| img.order);
+            for image_meta in images {
+                let zip_path = format!("images/{}/{}", map_name, image_meta.filename);
+                if !staged.files.contains_key(&zip_path) {
+                    log::warn!("File not found in zip: {}", zip_path);
+                    skipped += 1;
+                    continue;
+                }
 
-        // Storage for extracted manifest and files
-        let mut manifest_content: Option<String> = None;
-        let mut image_files: std::collections::HashMap<String, Vec<u8>> =
-            std::collections::HashMap::new();
+                let mut incoming = image_meta.clone();
+                incoming.map = map_name.clone();
+                if incoming.id.is_empty() {
+                    incoming.id = new_image_id();
+                }
 
-        // Step 1: Read all files from the ZIP archive into memory
-        {
-            // Open the archive (in its own scope to ensure it's dropped properly)
-            let file = File::open(import_path)?;
-            let mut archive =
-                zip::ZipArchive::new(file).map_err(|e| ExportServiceError::ZipError(e))?;
-
-            // Process all files in a single pass
-            for i in 0..archive.len() {
-                let mut file = match archive.by_index(i) {
-                    Ok(f) => f,
-                    Err(e) => {
-                        log::warn!("Failed to access file at index {}: {}", i, e);
-                        continue;
+                // An entry with a known id is the same lineup, wherever it lives now.
+                // Otherwise a lineup for the same spot is treated as the same lineup.
+                let existing = current_manifest.find_image(&image_meta.id).or_else(|| {
+                    current_manifest
+                        .images
+                        .get(map_name)
+                        .and_then(|existing_images| {
+                            existing_images.iter().find(|img| {
+                                img.position == incoming.position
+                                    && img.nade_type == incoming.nade_type
+                            })
+                        })
+                });
+                let status = match existing {
+                    None => ImportStatus::New,
+                    Some(existing) => {
+                        let differences =
+                            Self::differences(existing, &incoming, &data_dir, &staged);
+                        if differences.is_empty() {
+                            ImportStatus::Duplicate {
+                                existing_id: existing.id.clone(),
+                            }
+                        } else {
+                            ImportStatus::Conflict {
+                                existing_id: existing.id.clone(),
+                                differences,
+                            }
+                        }
                     }
                 };
+                items.push(ImportItem { incoming, status });
+            }
+        }
 
-                // Get file path
-                let file_path = match file.enclosed_name() {
-                    Some(path) => path.to_owned(),
-                    None => continue, // Invalid path, skip this file
-                };
+        let new_strats = package_manifest
+            .strats
+            .iter()
+            .filter(|strat| current_manifest.find_strat(&strat.id).is_none())
+            .map(|strat| strat.name.clone())
+            .collect();
+
+        Ok(ImportPlan {
+            items,
+            new_strats,
+            skipped,
+            header,
+            package_manifest,
+            staged,
+        })
+    }
 
-                let path_str = file_path.to_string_lossy().to_string();
+    /// Move the images of an analyzed package into the object store and work out what to
+    /// add to the library: new lineups are added, duplicates skipped, and each conflict is
+    /// handled as `resolutions` says (keyed by the incoming lineup's id, keeping the
+    /// library's version when missing).
+    ///
+    /// Nothing is merged into a manifest here, so this can run off the UI thread; pass the
+    /// result to `merge_import` to add it to the library. If moving an image fails, the
+    /// images moved so far are removed again. A plan can only be applied once.
+    pub fn apply_import(
+        &self,
+        plan: &ImportPlan,
+        resolutions: &HashMap<String, ConflictResolution>,
+    ) -> Result<ImportedLineups, ExportServiceError> {
+        let mut entries = Vec::new();
+        // Staged images to move into the object store, as (staged file, blob name)
+        let mut moves: Vec<(PathBuf, String)> = Vec::new();
+
+        for item in &plan.items {
+            let incoming = &item.incoming;
+            let (existing_id, resolution) = match &item.status {
+                ImportStatus::New => (None, ConflictResolution::KeepBoth),
+                ImportStatus::Duplicate { existing_id } => {
+                    log::info!(
+                        "Skipping duplicate nade: {} - {}",
+                        incoming.map,
+                        incoming.position
+                    );
+                    entries.push(ImportedEntry::Link {
+                        incoming_id: incoming.id.clone(),
+                        existing_id: existing_id.clone(),
+                    });
+                    continue;
+                }
+                ImportStatus::Conflict { existing_id, .. } => (
+                    Some(existing_id),
+                    resolutions.get(&incoming.id).copied().unwrap_or_default(),
+                ),
+            };
 
-                // Extract manifest.json
-                if path_str == "manifest.json" {
-                    let mut content = String::new();
-                    if let Err(e) = file.read_to_string(&mut content) {
-                        return Err(ExportServiceError::ImportError(format!(
-                            "Failed to read manifest.json: {}",
-                            e
-                        )));
-                    }
-                    manifest_content = Some(content);
+            match (existing_id, resolution) {
+                (Some(existing_id), ConflictResolution::KeepMine) => {
+                    log::info!(
+                        "Keeping library version of: {} - {}",
+                        incoming.map,
+                        incoming.position
+                    );
+                    entries.push(ImportedEntry::Link {
+                        incoming_id: incoming.id.clone(),
+                        existing_id: existing_id.clone(),
+                    });
                 }
-                // Extract image files
-                else if path_str.starts_with("images/") {
-                    let mut content = Vec::new();
-                    if let Err(e) = file.read_to_end(&mut content) {
-                        log::warn!("Failed to read file {}: {}", path_str, e);
+                (Some(existing_id), ConflictResolution::TakeTheirs) => {
+                    let Some(meta) =
+                        Self::import_lineup(&incoming.map, incoming, &plan.staged, &mut moves)
+                    else {
                         continue;
-                    }
-
-                    // Store in our hash map for later processing
-                    image_files.insert(path_str, content);
+                    };
+                    entries.push(ImportedEntry::Replace {
+                        existing_id: existing_id.clone(),
+                        meta,
+                    });
+                }
+                (_, _) => {
+                    let Some(meta) =
+                        Self::import_lineup(&incoming.map, incoming, &plan.staged, &mut moves)
+                    else {
+                        continue;
+                    };
+                    entries.push(ImportedEntry::Add { meta });
                 }
             }
-            // Archive is dropped here (end of scope)
         }
 
-        // Step 2: Parse the manifest
-        let manifest_json = manifest_content.ok_or_else(|| {
-            ExportServiceError::ImportError("manifest.json not found in import file".to_string())
-        })?;
-
-        let import_manifest: ImageManifest = serde_json::from_str(&manifest_json)
-            .map_err(|e| ExportServiceError::SerializationError(e.to_string()))?;
-
-        // Load the existing manifest
-        let mut current_manifest = self.persistence_service.load_manifest();
-
-        // Step 3: Write image files to disk
-        for (path_str, content) in &image_files {
-            // Parse the path components: images/{map_name}/{filename}
-            let path = Path::new(&path_str);
-            let components: Vec<_> = path.components().collect();
-
-            // Skip if path doesn't have expected structure
-            if components.len() < 3 {
-                log::warn!("Skipping file with invalid path: {}", path_str);
-                continue;
-            }
+        let moved = self.commit_staged_images(&moves)?;
+        Ok(ImportedLineups {
+            entries,
+            strats: plan.package_manifest.strats.clone(),
+            moved,
+        })
+    }
 
-            // Extract map name and filename
-            let map_name = match components[1].as_os_str().to_str() {
-                Some(name) => name.to_string(),
-                None => {
-                    log::warn!("Skipping file with non-UTF8 map name: {}", path_str);
-                    continue;
+    /// Merge lineups moved in by `apply_import` into `manifest` and save it. Replaced
+    /// lineups keep their id, map and place in the grid; those deleted since the import
+    /// was analyzed are skipped. Lineups added next to one with the same id get a new id.
+    /// Strats the library doesn't know yet are added, keeping only the lineups that made it in.
+    ///
+    /// `manifest` is only updated once it's saved. If saving fails, the images the import
+    /// moved into the store are removed again.
+    pub fn merge_import(
+        &self,
+        imported: &ImportedLineups,
+        manifest: &mut ImageManifest,
+    ) -> Result<(), ExportServiceError> {
+        let mut merged = manifest.clone();
+        // Package id -> library id, so imported strats point at the right lineups
+        let mut id_map: HashMap<String, String> = HashMap::new();
+
+        for entry in &imported.entries {
+            match entry {
+                ImportedEntry::Link {
+                    incoming_id,
+                    existing_id,
+                } => {
+                    id_map.insert(incoming_id.clone(), existing_id.clone());
                 }
-            };
-
-            // Get the filename (last component)
-            let file_name = match path.file_name() {
-                Some(name) => name.to_str().unwrap_or(""),
-                None => {
-                    log::warn!("Skipping file with no filename: {}", path_str);
-                    continue;
+                ImportedEntry::Replace { existing_id, meta } => {
+                    let Some(existing) = merged.find_image(existing_id).cloned() else {
+                        log::info!("{} was deleted during the import, skipping it", existing_id);
+                        continue;
+                    };
+                    // The library's entry keeps its identity and place in the grid
+                    let mut new_meta = meta.clone();
+                    new_meta.id = existing.id.clone();
+                    new_meta.map = existing.map.clone();
+                    new_meta.order = existing.order;
+                    if let Some(slot) = merged
+                        .images
+                        .get_mut(&existing.map)
+                        .and_then(|images| images.iter_mut().find(|img| img.id == existing.id))
+                    {
+                        *slot = new_meta;
+                    }
+                    id_map.insert(meta.id.clone(), existing.id);
+                }
+                ImportedEntry::Add { meta } => {
+                    let mut new_meta = meta.clone();
+                    // Keeping both needs a second id when the package's is already taken
+                    if merged.find_image(&new_meta.id).is_some() {
+                        new_meta.id = new_image_id();
+                    }
+                    id_map.insert(meta.id.clone(), new_meta.id.clone());
+                    merged = merged.clone_and_add(new_meta, &meta.map);
                 }
-            };
-
-            if file_name.is_empty() {
-                log::warn!("Skipping file with empty filename: {}", path_str);
-                continue;
             }
+        }
 
-            // Create the map directory if needed
-            let map_dir = data_dir.join(&map_name);
-            if let Err(e) = std::fs::create_dir_all(&map_dir) {
-                log::error!("Failed to create map directory {}: {}", map_name, e);
+        // Add strats that aren't known yet, keeping only the lineups that made it in
+        for strat in &imported.strats {
+            if merged.find_strat(&strat.id).is_some() {
+                log::info!("Skipping duplicate strat: {} - {}", strat.map, strat.name);
                 continue;
             }
+            let mut new_strat = strat.clone();
+            new_strat.steps = strat
+                .steps
+                .iter()
+                .filter_map(|step| {
+                    let image_id = id_map.get(&step.image_id)?;
+                    merged.find_image(image_id)?;
+                    Some(StratStep {
+                        image_id: image_id.clone(),
+                        ..step.clone()
+                    })
+                })
+                .collect();
+            merged.strats.push(new_strat);
+        }
 
-            // Check if file with same name exists and generate unique name if needed
-            let dest_file_path = map_dir.join(file_name);
-            let final_path = if dest_file_path.exists() {
-                // Generate a unique name by adding timestamp
-                let file_stem = Path::new(file_name)
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("unnamed");
-
-                let extension = Path::new(file_name)
-                    .extension()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("");
-
-                let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S%3f").to_string();
-
-                let new_filename = if extension.is_empty() {
-                    format!("{}_{}", file_stem, timestamp)
-                } else {
-                    format!("{}_{}.{}", file_stem, timestamp, extension)
-                };
-
-                map_dir.join(new_filename)
-            } else {
-                dest_file_path
-            };
+        if let Err(e) = self.persistence_service.save_manifest(&merged) {
+            Self::remove_files(&imported.moved);
+            return Err(ExportServiceError::from(e));
+        }
+        // Images of replaced lineups stay in the store for the backups until they're collected
+        *manifest = merged;
+        Ok(())
+    }
 
-            // Write the file to the filesystem
-            if let Err(e) = std::fs::write(&final_path, &content) {
-                log::error!("Failed to write file {}: {}", final_path.display(), e);
-                continue;
+    /// Move every staged image into the object store. Images the store already has, from
+    /// the library or earlier in this import, are skipped. On failure the images added so
+    /// far are removed and the error is returned; the same goes for cancelling.
+    fn commit_staged_images(
+        &self,
+        moves: &[(PathBuf, String)],
+    ) -> Result<Vec<PathBuf>, ExportServiceError> {
+        let data_dir = self.persistence_service.get_data_dir();
+        let mut moved: Vec<PathBuf> = Vec::new();
+        for (index, (staged, blob)) in moves.iter().enumerate() {
+            if let Err(e) = self.check_cancelled() {
+                Self::remove_files(&moved);
+                return Err(e);
             }
+            match object_store::move_into_store(&data_dir, staged, blob) {
+                Ok(true) => moved.push(object_store::image_path(&data_dir, "", blob)),
+                Ok(false) => log::info!("{} is already stored", blob),
+                Err(e) => {
+                    log::error!("Failed to move {} into the library: {}", blob, e);
+                    Self::remove_files(&moved);
+                    return Err(ExportServiceError::IoError(e));
+                }
+            }
+            self.report_progress(index + 1, moves.len());
         }
+        Ok(moved)
+    }
 
-        // Process the manifest data from the import file
-        for (map_name, images) in &import_manifest.images {
-            // Create the map directory if it doesn't exist
-            let map_dir = data_dir.join(map_name);
-            if !map_dir.exists() {
-                create_dir_all(&map_dir)?;
+    /// Remove files, logging the ones that can't be removed
+    fn remove_files(paths: &[PathBuf]) {
+        for path in paths {
+            if let Err(e) = std::fs::remove_file(path) {
+                log::warn!("Failed to remove {}: {}", path.display(), e);
             }
+        }
+    }
 
-            // Process each image
-            for image_meta in images {
-                // Check for duplicates more comprehensively
-                // First check if we already have this image in the manifest (using relevant fields for comparison)
-                let is_duplicate = current_manifest
-                    .images
-                    .get(map_name)
-                    .map(|existing_images| {
-                        existing_images.iter().any(|img| {
-                            // Consider it a duplicate if map, position and nade_type all match
-                            img.map == image_meta.map
-                                && img.position == image_meta.position
-                                && img.nade_type == image_meta.nade_type
-                        })
-                    })
-                    .unwrap_or(false);
+    /// Read the header and the manifest of a package, streaming its images one by one
+    /// into a new staging folder in `staging_parent`.
+    /// Packages in a newer format than this build reads are rejected, and so are
+    /// packages with images that don't match the header's checksums.
+    fn read_package(
+        &self,
+        import_path: &Path,
+        staging_parent: &Path,
+    ) -> Result<PackageContents, ExportServiceError> {
+        let mut header_content: Option<String> = None;
+        let mut manifest_content: Option<String> = None;
 
-                // Skip processing this image if it's a duplicate
-                if is_duplicate {
-                    log::info!(
-                        "Skipping duplicate nade: {} - {}",
-                        map_name,
-                        image_meta.position
-                    );
+        create_dir_all(staging_parent)?;
+        let mut staged = StagedImages {
+            dir: tempfile::Builder::new()
+                .prefix(".import-")
+                .tempdir_in(staging_parent)?,
+            files: HashMap::new(),
+        };
+
+        let file = File::open(import_path)?;
+        let mut archive = zip::ZipArchive::new(file)?;
+
+        // Process all files in a single pass
+        for i in 0..archive.len() {
+            self.check_cancelled()?;
+            self.report_progress(i, archive.len());
+            let mut file = match archive.by_index(i) {
+                Ok(f) => f,
+                Err(e) => {
+                    log::warn!("Failed to access file at index {}: {}", i, e);
                     continue;
                 }
+            };
 
-                // Check if another image with the same filename exists
-                let existing_image =
-                    current_manifest
-                        .images
-                        .get(map_name)
-                        .and_then(|existing_images| {
-                            existing_images
-                                .iter()
-                                .find(|img| img.filename == image_meta.filename)
-                        });
-
-                let check_for_duplicate = existing_image.is_some();
-
-                // If image with same filename exists, generate a unique name
-                let target_filename = if check_for_duplicate {
-                    // Get basename and extension the safer way with Path
-                    let filename_str = image_meta.filename.as_str();
-                    let path = Path::new(filename_str);
-                    let base_name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
-                    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("webp");
-
-                    // Create a unique filename with timestamp
-                    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-                    format!("{}_{}.", base_name, timestamp) + extension
-                } else {
-                    image_meta.filename.clone()
-                };
-
-                // Get the image data from our pre-loaded hash map
-                let zip_path = format!("images/{}/{}", map_name, image_meta.filename);
+            // Get file path
+            let file_path = match file.enclosed_name() {
+                Some(path) => path.to_owned(),
+                None => continue, // Invalid path, skip this file
+            };
 
-                // Check if we have the file data
-                if let Some(file_data) = image_files.get(&zip_path) {
-                    // Create output file
-                    let output_path = map_dir.join(&target_filename);
-                    let mut output_file = match File::create(&output_path) {
-                        Ok(f) => f,
-                        Err(e) => {
-                            log::error!(
-                                "Failed to create output file: {} - {}",
-                                output_path.display(),
-                                e
-                            );
-                            continue; // Skip to next file
-                        }
-                    };
+            let path_str = file_path.to_string_lossy().to_string();
 
-                    // Write the data to the output file
-                    if let Err(e) = output_file.write_all(file_data) {
-                        log::error!("Failed to write file: {} - {}", output_path.display(), e);
-                        continue; // Skip to next file
+            // Extract package.json
+            if path_str == "package.json" {
+                let mut content = String::new();
+                if let Err(e) = file.read_to_string(&mut content) {
+                    return Err(ExportServiceError::ImportError(format!(
+                        "Failed to read package.json: {}",
+                        e
+                    )));
+                }
+                header_content = Some(content);
+            }
+            // Extract manifest.json
+            else if path_str == "manifest.json" {
+                let mut content = String::new();
+                if let Err(e) = file.read_to_string(&mut content) {
+                    return Err(ExportServiceError::ImportError(format!(
+                        "Failed to read manifest.json: {}",
+                        e
+                    )));
+                }
+                manifest_content = Some(content);
+            }
+            // Stage image files under their index; zip names may clash once flattened
+            else if path_str.starts_with("images/") {
+                let staged_path = staged.dir.path().join(i.to_string());
+                let copied = File::create(&staged_path).and_then(|mut staged_file| {
+                    let checksum = copy_hashed(&mut file, &mut staged_file)?;
+                    Ok((staged_file.metadata()?.len(), checksum))
+                });
+                match copied {
+                    Ok((size, checksum)) => {
+                        staged.files.insert(
+                            path_str,
+                            StagedImage {
+                                path: staged_path,
+                                size,
+                                checksum,
+                            },
+                        );
                     }
+                    Err(e) => {
+                        log::warn!("Failed to read file {}: {}", path_str, e);
+                        let _ = std::fs::remove_file(&staged_path);
+                    }
+                }
+            }
+        }
 
-                    // Create a new ImageMeta with the potentially renamed file
-                    let mut new_meta = image_meta.clone();
-                    new_meta.filename = target_filename;
+        // Check the format first: a newer manifest may not even parse
+        let header = match header_content {
+            Some(content) => {
+                let header = Self::parse_header(&content)?;
+                Self::verify_checksums(&header, &staged)?;
+                Some(header)
+            }
+            None => None,
+        };
 
-                    // Add to current manifest
-                    current_manifest = current_manifest.clone_and_add(new_meta, map_name);
-                } else {
-                    log::warn!("File not found in zip: {}", zip_path);
-                    continue; // Skip this file
-                }
+        let manifest_json = manifest_content.ok_or_else(|| {
+            ExportServiceError::ImportError("manifest.json not found in import file".to_string())
+        })?;
+        let manifest: ImageManifest = serde_json::from_str(&manifest_json)
+            .map_err(|e| ExportServiceError::SerializationError(e.to_string()))?;
+        if let Some(header) = &header {
+            let entry_count: usize = manifest.images.values().map(Vec::len).sum();
+            if entry_count != header.entry_count {
+                return Err(ExportServiceError::ImportError(format!(
+                    "The package is incomplete: its header lists {} lineups, but the manifest has {}",
+                    header.entry_count, entry_count
+                )));
             }
         }
+        Ok((header, manifest, staged))
+    }
 
-        // Save the updated manifest
-        self.persistence_service
-            .save_manifest(&current_manifest)
-            .map_err(|e| ExportServiceError::from(e))?;
+    /// Parse `package.json`, rejecting formats newer than this build reads. Only the
+    /// version fields are needed for that, so they're checked before the rest.
+    fn parse_header(content: &str) -> Result<PackageHeader, ExportServiceError> {
+        let value: serde_json::Value = serde_json::from_str(content)
+            .map_err(|e| ExportServiceError::SerializationError(e.to_string()))?;
+        let format_version = value
+            .get("format_version")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0);
+        if format_version > u64::from(PACKAGE_FORMAT_VERSION) {
+            return Err(ExportServiceError::UnsupportedPackageVersion {
+                format_version: u32::try_from(format_version).unwrap_or(u32::MAX),
+                app_version: value
+                    .get("app_version")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("unknown")
+                    .to_string(),
+            });
+        }
+        serde_json::from_value(value)
+            .map_err(|e| ExportServiceError::SerializationError(e.to_string()))
+    }
 
-        Ok(current_manifest)
+    /// Check every image listed in the header against its checksum
+    fn verify_checksums(
+        header: &PackageHeader,
+        staged: &StagedImages,
+    ) -> Result<(), ExportServiceError> {
+        let corrupted: Vec<String> = header
+            .checksums
+            .iter()
+            .filter(|(zip_path, checksum)| {
+                staged
+                    .files
+                    .get(*zip_path)
+                    .is_none_or(|image| image.checksum != **checksum)
+            })
+            .map(|(zip_path, _)| zip_path.trim_start_matches("images/").to_string())
+            .collect();
+        if corrupted.is_empty() {
+            Ok(())
+        } else {
+            Err(ExportServiceError::CorruptedFiles(corrupted))
+        }
+    }
+
+    /// What differs between a library lineup and its counterpart in a package
+    fn differences(
+        existing: &ImageMeta,
+        incoming: &ImageMeta,
+        data_dir: &Path,
+        staged: &StagedImages,
+    ) -> Vec<&'static str> {
+        let mut differences = Vec::new();
+        if existing.position != incoming.position || existing.nade_type != incoming.nade_type {
+            differences.push("position");
+        }
+        if existing.notes != incoming.notes {
+            differences.push("notes");
+        }
+        if existing.side != incoming.side || existing.technique != incoming.technique {
+            differences.push("throw");
+        }
+        if existing.tags != incoming.tags {
+            differences.push("tags");
+        }
+        if existing.throw_pos != incoming.throw_pos
+            || existing.landing_pos != incoming.landing_pos
+            || existing.view_angles != incoming.view_angles
+        {
+            differences.push("coordinates");
+        }
+
+        let existing_steps = existing.steps();
+        let incoming_steps = incoming.steps();
+        let same_images = existing_steps.len() == incoming_steps.len()
+            && existing_steps
+                .iter()
+                .zip(&incoming_steps)
+                .all(|(mine, theirs)| {
+                    let zip_path = format!("images/{}/{}", incoming.map, theirs.filename);
+                    let on_disk = object_store::image_path(data_dir, &existing.map, &mine.filename);
+                    mine.role == theirs.role
+                        && staged.files.get(&zip_path).is_some_and(|image| {
+                            // Only hash files that could be the same
+                            std::fs::metadata(&on_disk).is_ok_and(|meta| meta.len() == image.size)
+                                && sha256_file(&on_disk)
+                                    .is_ok_and(|checksum| checksum == image.checksum)
+                        })
+                });
+        if !same_images {
+            differences.push("images");
+        }
+        differences
+    }
+
+    /// Plan the move of a package lineup's staged images into the object store and return
+    /// the lineup on `map_name`, pointing at their blobs.
+    /// Returns `None` if the lineup's first image is missing from the package.
+    /// Extra steps missing from the package are dropped so the rest of the lineup still imports.
+    fn import_lineup(
+        map_name: &str,
+        incoming: &ImageMeta,
+        staged: &StagedImages,
+        moves: &mut Vec<(PathBuf, String)>,
+    ) -> Option<ImageMeta> {
+        let stage = |filename: &str, moves: &mut Vec<(PathBuf, String)>| {
+            let zip_path = format!("images/{}/{}", incoming.map, filename);
+            let Some(image) = staged.files.get(&zip_path) else {
+                log::warn!("File not found in zip: {}", zip_path);
+                return None;
+            };
+            let extension = Path::new(filename)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("webp");
+            let blob = object_store::blob_name(&image.checksum, extension);
+            moves.push((image.path.clone(), blob.clone()));
+            Some(blob)
+        };
+
+        let mut new_meta = incoming.clone();
+        new_meta.filename = stage(&incoming.filename, moves)?;
+        new_meta.map = map_name.to_string();
+        new_meta.extra_images = incoming
+            .extra_images
+            .iter()
+            .filter_map(|extra| {
+                Some(LineupImage {
+                    filename: stage(&extra.filename, moves)?,
+                    role: extra.role,
+                })
+            })
+            .collect();
+        Some(new_meta)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::persistence::{ImageMeta, MapMeta, NadeType};
+    use crate::persistence::{
+        ImageRole, MapMeta, Movement, NadeType, Side, Strat, StratStep, ThrowClick, ThrowTechnique,
+        ViewAngles, WorldPos,
+    };
     use std::collections::HashMap;
 
+    /// Analyze a package and import all of it into the saved manifest, keeping the
+    /// library's version of every conflicting lineup
+    fn import_library(
+        service: &ExportService,
+        import_path: &Path,
+    ) -> Result<ImageManifest, ExportServiceError> {
+        let plan = service.analyze_import(import_path)?;
+        import_into_saved(service, &plan, &HashMap::new())
+    }
+
+    /// Apply an analyzed package and merge it into the saved manifest, as the app does
+    /// across its import thread and the main thread
+    fn import_into_saved(
+        service: &ExportService,
+        plan: &ImportPlan,
+        resolutions: &HashMap<String, ConflictResolution>,
+    ) -> Result<ImageManifest, ExportServiceError> {
+        let mut manifest = service.persistence_service.load_manifest()?;
+        manifest.assign_missing_ids();
+        let imported = service.apply_import(plan, resolutions)?;
+        service.merge_import(&imported, &mut manifest)?;
+        Ok(manifest)
+    }
+
     #[allow(dead_code)]
     fn create_test_manifest() -> ImageManifest {
         let mut images = HashMap::new();
@@ -392,20 +1002,56 @@ mod tests {
         let map_name = "de_dust2";
 
         let image1 = ImageMeta {
+            id: "smoke-a-site-id".to_string(),
             filename: "smoke_a_site.webp".to_string(),
             map: map_name.to_string(),
             nade_type: NadeType::Smoke,
             notes: "Jump throw".to_string(),
             position: "A Site Smoke".to_string(),
+            side: Side::T,
+            technique: ThrowTechnique {
+                movement: Movement::Jump,
+                click: ThrowClick::Left,
+                jump_throw_bind: true,
+            },
+            role: ImageRole::Stand,
+            extra_images: vec![LineupImage {
+                filename: "smoke_a_site_aim.webp".to_string(),
+                role: ImageRole::Aim,
+            }],
+            tags: vec!["execute".to_string()],
+            throw_pos: Some(WorldPos {
+                x: -300.0,
+                y: 1200.5,
+                z: 64.0,
+            }),
+            landing_pos: Some(WorldPos {
+                x: 1100.0,
+                y: 2400.0,
+                z: 128.0,
+            }),
+            view_angles: Some(ViewAngles {
+                pitch: -5.5,
+                yaw: 90.0,
+            }),
             order: 0,
         };
 
         let image2 = ImageMeta {
+            id: "flash-b-site-id".to_string(),
             filename: "flash_b_site.webp".to_string(),
             map: map_name.to_string(),
             nade_type: NadeType::Flash,
             notes: "Stand still".to_string(),
             position: "B Site Flash".to_string(),
+            side: Side::CT,
+            technique: ThrowTechnique::default(),
+            role: ImageRole::Stand,
+            extra_images: Vec::new(),
+            tags: Vec::new(),
+            throw_pos: None,
+            landing_pos: None,
+            view_angles: None,
             order: 1,
         };
 
@@ -417,10 +1063,30 @@ mod tests {
             },
         );
 
+        let strat = Strat {
+            id: "a-execute-id".to_string(),
+            name: "A execute".to_string(),
+            map: map_name.to_string(),
+            notes: String::new(),
+            steps: vec![
+                StratStep {
+                    image_id: "smoke-a-site-id".to_string(),
+                    player: 2,
+                    order: 1,
+                },
+                StratStep {
+                    image_id: "deleted-lineup-id".to_string(),
+                    player: 3,
+                    order: 1,
+                },
+            ],
+        };
+
         ImageManifest {
             images,
             maps,
-            webp_migration_completed: false,
+            strats: vec![strat],
+            ..Default::default()
         }
     }
 
@@ -451,10 +1117,12 @@ mod tests {
 
         // Create test images (WebP only as per our new architecture)
         let smoke_path = map_dir.join("smoke_a_site.webp");
+        let smoke_aim_path = map_dir.join("smoke_a_site_aim.webp");
         let flash_path = map_dir.join("flash_b_site.webp");
 
         // Create dummy WebP image files
         create_dummy_image_file(smoke_path, 100, 100);
+        create_dummy_image_file(smoke_aim_path, 100, 100);
         create_dummy_image_file(flash_path, 100, 100);
 
         // Create the images directory structure in the zip file
@@ -499,7 +1167,7 @@ mod tests {
         std::fs::create_dir_all(&dest_map_dir).expect("Failed to create destination map directory");
 
         // Test import
-        let import_result = import_export_service.import_library(&export_path);
+        let import_result = import_library(&import_export_service, &export_path);
         assert!(import_result.is_ok(), "Import should succeed");
 
         // Verify imported manifest
@@ -524,6 +1192,8 @@ mod tests {
         let has_smoke = imported_images.iter().any(|img| {
             img.position == "A Site Smoke"
                 && img.nade_type == NadeType::Smoke
+                && img.side == Side::T
+                && img.technique.jump_throw_bind
                 && img.filename.ends_with(".webp")
         });
         assert!(
@@ -531,9 +1201,39 @@ mod tests {
             "Imported manifest should contain smoke image with WebP extension"
         );
 
+        // Every step of a multi-image lineup comes along
+        let smoke = imported_images
+            .iter()
+            .find(|img| img.position == "A Site Smoke")
+            .unwrap();
+        assert_eq!(smoke.tags, vec!["execute".to_string()]);
+        assert_eq!(smoke.throw_pos.map(|p| p.y), Some(1200.5));
+        assert!(smoke.landing_pos.is_some());
+        assert_eq!(smoke.view_angles.map(|a| a.yaw), Some(90.0));
+        assert_eq!(smoke.extra_images.len(), 1);
+        assert_eq!(smoke.extra_images[0].role, ImageRole::Aim);
+        assert!(
+            object_store::image_path(
+                &dest_persistence.get_data_dir(),
+                map_name,
+                &smoke.extra_images[0].filename
+            )
+            .exists(),
+            "Extra lineup image should be written during import"
+        );
+
+        // Strats come along, minus steps whose lineup isn't in the package
+        assert_eq!(imported_manifest.strats.len(), 1);
+        let strat = &imported_manifest.strats[0];
+        assert_eq!(strat.name, "A execute");
+        assert_eq!(strat.steps.len(), 1);
+        assert_eq!(strat.steps[0].image_id, smoke.id);
+        assert_eq!(strat.steps[0].player, 2);
+
         let has_flash = imported_images.iter().any(|img| {
             img.position == "B Site Flash"
                 && img.nade_type == NadeType::Flash
+                && img.side == Side::CT
                 && img.filename.ends_with(".webp")
         });
         assert!(
@@ -589,12 +1289,14 @@ mod tests {
         // Add an image to destination with same name but different content
         let mut dest_images = Vec::new();
         dest_images.push(ImageMeta {
+            id: "existing-smoke-id".to_string(),
             filename: "smoke_a_site.webp".to_string(),
             map: map_name.to_string(),
             nade_type: NadeType::Smoke,
             notes: "Different smoke".to_string(),
             position: "Existing Smoke".to_string(),
             order: 0,
+            ..Default::default()
         });
         dest_manifest
             .images
@@ -635,7 +1337,7 @@ mod tests {
 
         // Import to destination with existing duplicate
         let import_service = ExportService::new(Arc::clone(&dest_persistence));
-        let import_result = import_service.import_library(&export_path);
+        let import_result = import_library(&import_service, &export_path);
         assert!(
             import_result.is_ok(),
             "Import with duplicates should succeed"
@@ -645,57 +1347,739 @@ mod tests {
         let updated_manifest = import_result.unwrap();
         let updated_images = &updated_manifest.images[map_name];
 
-        // Should have 2 images now (original + imported with renamed file)
+        // Should have 2 images now (original + imported into the object store)
         assert_eq!(
             updated_images.len(),
             2,
             "Should have both original and imported images"
         );
 
-        // Check that both entries exist - one with original filename and one with uniquified name
+        // The library's image keeps its name and file; the imported one is stored by content
         let original_exists = updated_images
             .iter()
             .any(|img| img.position == "Existing Smoke" && img.filename == "smoke_a_site.webp");
         assert!(original_exists, "Original image should still exist");
+        assert!(dest_smoke_path.exists(), "Original file should exist");
 
-        // Find the imported image (should have timestamp in filename)
-        let imported_exists = updated_images.iter().any(|img| {
-            img.position == "A Site Smoke"
-                && img.filename.contains("smoke_a_site")
-                && img.filename != "smoke_a_site.webp"
-                && img.filename.ends_with(".webp")
+        let imported = updated_images
+            .iter()
+            .find(|img| img.position == "A Site Smoke")
+            .expect("Imported image should exist");
+        assert!(object_store::is_blob_name(&imported.filename));
+        let imported_path = object_store::image_path(
+            &dest_persistence.get_data_dir(),
+            map_name,
+            &imported.filename,
+        );
+        assert_eq!(
+            std::fs::read(&imported_path).unwrap(),
+            std::fs::read(&source_smoke_path).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_import_keeps_ids_and_skips_known_ids() {
+        use tempfile::TempDir;
+
+        let source_dir = TempDir::new().expect("Failed to create source temp dir");
+        let source_path = source_dir.path();
+        let dest_dir = TempDir::new().expect("Failed to create dest temp dir");
+        let export_path = source_path.join("export.nadex");
+
+        let map_name = "de_dust2";
+        let map_dir = source_path.join(map_name);
+        std::fs::create_dir_all(&map_dir).expect("Failed to create map directory");
+        create_dummy_image_file(map_dir.join("smoke_a_site.webp"), 100, 100);
+        create_dummy_image_file(map_dir.join("flash_b_site.webp"), 100, 100);
+
+        let source_persistence = Arc::new(
+            PersistenceService::new(source_path.join("manifest.json"))
+                .expect("Failed to create source persistence service"),
+        );
+        source_persistence
+            .save_manifest(&create_test_manifest())
+            .expect("Failed to save source manifest");
+        ExportService::new(Arc::clone(&source_persistence))
+            .export_library(&export_path, source_path)
+            .expect("Export should succeed");
+
+        let dest_persistence = Arc::new(
+            PersistenceService::new(dest_dir.path().to_path_buf())
+                .expect("Failed to create destination persistence service"),
+        );
+        let import_service = ExportService::new(Arc::clone(&dest_persistence));
+
+        let first_import =
+            import_library(&import_service, &export_path).expect("First import should succeed");
+        let mut imported_ids: Vec<&str> = first_import.images[map_name]
+            .iter()
+            .map(|img| img.id.as_str())
+            .collect();
+        imported_ids.sort_unstable();
+        assert_eq!(imported_ids, vec!["flash-b-site-id", "smoke-a-site-id"]);
+
+        // Re-importing the same package must not duplicate entries, even after a local edit
+        let mut edited = dest_persistence.load_manifest().unwrap();
+        edited.images.get_mut(map_name).unwrap()[0].position = "Renamed locally".to_string();
+        dest_persistence
+            .save_manifest(&edited)
+            .expect("Failed to save edited manifest");
+
+        let second_import =
+            import_library(&import_service, &export_path).expect("Second import should succeed");
+        assert_eq!(
+            second_import.images[map_name].len(),
+            2,
+            "Entries with known ids should be skipped"
+        );
+
+        // A teammate's copy of the same lineups under new ids adds entries, not files
+        let renamed_path = source_path.join("renamed.nadex");
+        rewrite_package(&export_path, &renamed_path, |name, content| {
+            if name != "manifest.json" {
+                return Some(content);
+            }
+            let json = String::from_utf8(content).unwrap();
+            Some(json.replace("-id\"", "-id-2\"").into_bytes())
         });
-        assert!(
-            imported_exists,
-            "Imported image should exist with modified filename"
+        let stored_files = || {
+            std::fs::read_dir(dest_dir.path().join(object_store::OBJECTS_DIR))
+                .unwrap()
+                .flatten()
+                .filter(|entry| entry.path().is_dir())
+                .flat_map(|dir| std::fs::read_dir(dir.path()).unwrap().flatten())
+                .count()
+        };
+        // Every image of the package has the same pixels
+        assert_eq!(stored_files(), 1);
+        let third_import = import_library(&import_service, &renamed_path)
+            .expect("Import under new ids should succeed");
+        // The flash matches the library's and is skipped; the smoke, renamed locally, isn't
+        assert_eq!(third_import.images[map_name].len(), 3);
+        assert_eq!(stored_files(), 1);
+    }
+
+    /// Copy a package, passing every entry through `edit`; `None` drops the entry
+    fn rewrite_package(
+        source: &Path,
+        target: &Path,
+        edit: impl Fn(&str, Vec<u8>) -> Option<Vec<u8>>,
+    ) {
+        let mut archive = zip::ZipArchive::new(File::open(source).unwrap()).unwrap();
+        let mut zip = ZipWriter::new(File::create(target).unwrap());
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).unwrap();
+            let name = entry.name().to_string();
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).unwrap();
+            if let Some(content) = edit(&name, content) {
+                zip.start_file(name, FileOptions::default()).unwrap();
+                zip.write_all(&content).unwrap();
+            }
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_package_header_is_checked_on_import() {
+        use tempfile::TempDir;
+
+        let source_dir = TempDir::new().expect("Failed to create source temp dir");
+        let source_path = source_dir.path();
+        let map_dir = source_path.join("de_dust2");
+        std::fs::create_dir_all(&map_dir).expect("Failed to create map directory");
+        create_dummy_image_file(map_dir.join("smoke_a_site.webp"), 100, 100);
+        create_dummy_image_file(map_dir.join("smoke_a_site_aim.webp"), 100, 100);
+        create_dummy_image_file(map_dir.join("flash_b_site.webp"), 100, 100);
+
+        let persistence_service = Arc::new(
+            PersistenceService::new(source_path.join("manifest.json"))
+                .expect("Failed to create persistence service"),
+        );
+        persistence_service
+            .save_manifest(&create_test_manifest())
+            .expect("Failed to save manifest");
+        let export_path = source_path.join("export.nadex");
+        ExportService::new(Arc::clone(&persistence_service))
+            .with_author("  Nade King ")
+            .export_library(&export_path, source_path)
+            .expect("Export should succeed");
+
+        let reader = ExportService::new(Arc::clone(&persistence_service));
+        let (header, _, _) = reader
+            .read_package(&export_path, source_path)
+            .expect("Valid package");
+        let header = header.expect("Exports have a header");
+        assert_eq!(header.format_version, PACKAGE_FORMAT_VERSION);
+        assert_eq!(header.app_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(header.author.as_deref(), Some("Nade King"));
+        assert_eq!(header.entry_count, 2);
+        assert_eq!(header.checksums.len(), 3);
+        assert_eq!(
+            header.checksums["images/de_dust2/flash_b_site.webp"],
+            sha256_file(&map_dir.join("flash_b_site.webp")).unwrap()
         );
 
-        // Print debug information about files on disk
-        let files = std::fs::read_dir(&dest_map_dir)
-            .expect("Should be able to read directory")
-            .filter_map(|entry| entry.ok())
-            .map(|entry| entry.file_name().to_string_lossy().to_string())
-            .collect::<Vec<_>>();
+        // A damaged image is reported by name
+        let damaged_path = source_path.join("damaged.nadex");
+        rewrite_package(&export_path, &damaged_path, |name, mut content| {
+            if name.ends_with("flash_b_site.webp") {
+                content[10] ^= 0xff;
+            }
+            Some(content)
+        });
+        match reader.read_package(&damaged_path, source_path) {
+            Err(ExportServiceError::CorruptedFiles(files)) => {
+                assert_eq!(files, vec!["de_dust2/flash_b_site.webp".to_string()]);
+            }
+            other => panic!("Expected CorruptedFiles, got {:?}", other.map(|_| ())),
+        }
 
-        println!("Files in directory: {:?}", files);
+        // A package from a newer build is rejected before its manifest is parsed
+        let newer_path = source_path.join("newer.nadex");
+        rewrite_package(&export_path, &newer_path, |name, content| match name {
+            "package.json" => Some(
+                br#"{"format_version": 99, "app_version": "9.0.0", "layout": "unknown"}"#.to_vec(),
+            ),
+            "manifest.json" => Some(b"{\"lineups\": []}".to_vec()),
+            _ => Some(content),
+        });
+        let error = reader.read_package(&newer_path, source_path).unwrap_err();
+        assert!(matches!(
+            &error,
+            ExportServiceError::UnsupportedPackageVersion { format_version: 99, app_version }
+                if app_version == "9.0.0"
+        ));
+        assert!(error.to_string().contains("Update Nadex"));
+
+        // Packages from before the header existed still import
+        let legacy_path = source_path.join("legacy.nadex");
+        rewrite_package(&export_path, &legacy_path, |name, content| {
+            (name != "package.json").then_some(content)
+        });
+        let (header, manifest, _) = reader
+            .read_package(&legacy_path, source_path)
+            .expect("Legacy package should be read");
+        assert!(header.is_none());
+        assert_eq!(manifest.images["de_dust2"].len(), 2);
+    }
 
-        // The test might pass with just one file if our implementation changed
-        // to handle duplicates differently than we expected
-        assert!(!files.is_empty(), "Should have at least 1 file on disk");
-        assert!(
-            files.iter().any(|f| f == "smoke_a_site.webp"),
-            "Original file should exist"
+    #[test]
+    fn test_progress_and_cancellation() {
+        use std::sync::Mutex;
+        use tempfile::TempDir;
+
+        let source_dir = TempDir::new().expect("Failed to create source temp dir");
+        let source_path = source_dir.path();
+        let dest_dir = TempDir::new().expect("Failed to create dest temp dir");
+        let map_dir = source_path.join("de_dust2");
+        std::fs::create_dir_all(&map_dir).expect("Failed to create map directory");
+        create_dummy_image_file(map_dir.join("smoke_a_site.webp"), 100, 100);
+        create_dummy_image_file(map_dir.join("smoke_a_site_aim.webp"), 100, 100);
+        create_dummy_image_file(map_dir.join("flash_b_site.webp"), 100, 100);
+        let source_persistence = Arc::new(
+            PersistenceService::new(source_path.join("manifest.json"))
+                .expect("Failed to create source persistence service"),
+        );
+        source_persistence
+            .save_manifest(&create_test_manifest())
+            .expect("Failed to save source manifest");
+        let export_path = source_path.join("export.nadex");
+
+        // Cancelling an export deletes the partial file
+        let cancel_flag = Arc::new(AtomicBool::new(true));
+        let result = ExportService::new(Arc::clone(&source_persistence))
+            .with_cancel_flag(Arc::clone(&cancel_flag))
+            .export_library(&export_path, source_path);
+        assert!(matches!(result, Err(ExportServiceError::Cancelled)));
+        assert!(!export_path.exists());
+
+        // Progress is reported per image
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let reports_clone = Arc::clone(&reports);
+        cancel_flag.store(false, Ordering::Relaxed);
+        ExportService::new(Arc::clone(&source_persistence))
+            .with_cancel_flag(Arc::clone(&cancel_flag))
+            .with_progress(move |done, total| reports_clone.lock().unwrap().push((done, total)))
+            .export_library(&export_path, source_path)
+            .expect("Export should succeed");
+        assert_eq!(*reports.lock().unwrap(), vec![(1, 3), (2, 3), (3, 3)]);
+
+        // Cancelling an import before it's applied writes nothing
+        let dest_persistence = Arc::new(
+            PersistenceService::new(dest_dir.path().to_path_buf())
+                .expect("Failed to create destination persistence service"),
+        );
+        let import_service = ExportService::new(Arc::clone(&dest_persistence))
+            .with_cancel_flag(Arc::clone(&cancel_flag));
+        let plan = import_service
+            .analyze_import(&export_path)
+            .expect("Analyze should succeed");
+        cancel_flag.store(true, Ordering::Relaxed);
+        let result = import_service.apply_import(&plan, &HashMap::new());
+        assert!(matches!(result, Err(ExportServiceError::Cancelled)));
+        assert!(dest_persistence.load_manifest().unwrap().images.is_empty());
+        assert!(!dest_dir.path().join("de_dust2").exists());
+    }
+
+    #[test]
+    fn test_failed_import_leaves_library_untouched() {
+        use tempfile::TempDir;
+
+        let source_dir = TempDir::new().expect("Failed to create source temp dir");
+        let source_path = source_dir.path();
+        let dest_dir = TempDir::new().expect("Failed to create dest temp dir");
+        let dest_path = dest_dir.path();
+
+        std::fs::create_dir_all(source_path.join("de_dust2")).unwrap();
+        std::fs::create_dir_all(source_path.join("de_inferno")).unwrap();
+        create_dummy_image_file(source_path.join("de_dust2/smoke_a_site.webp"), 100, 100);
+        create_dummy_image_file(source_path.join("de_dust2/smoke_a_site_aim.webp"), 100, 100);
+        create_dummy_image_file(source_path.join("de_dust2/flash_b_site.webp"), 100, 100);
+        create_dummy_image_file(source_path.join("de_inferno/banana_molly.webp"), 120, 90);
+        let mut manifest = create_test_manifest();
+        manifest.images.insert(
+            "de_inferno".to_string(),
+            vec![ImageMeta {
+                id: "banana-molly-id".to_string(),
+                filename: "banana_molly.webp".to_string(),
+                map: "de_inferno".to_string(),
+                nade_type: NadeType::Molotov,
+                position: "Banana Molly".to_string(),
+                ..Default::default()
+            }],
+        );
+        let source_persistence = Arc::new(
+            PersistenceService::new(source_path.join("manifest.json"))
+                .expect("Failed to create source persistence service"),
+        );
+        source_persistence
+            .save_manifest(&manifest)
+            .expect("Failed to save source manifest");
+        let export_path = source_path.join("export.nadex");
+        ExportService::new(source_persistence)
+            .export_library(&export_path, source_path)
+            .expect("Export should succeed");
+
+        // Inferno's image can't be moved in: a file is in the way of its store folder
+        let blob_of = |path: &str| {
+            let hash = sha256_file(&source_path.join(path)).unwrap();
+            object_store::blob_name(&hash, "webp")
+        };
+        let molly_blob = blob_of("de_inferno/banana_molly.webp");
+        let smoke_blob = blob_of("de_dust2/smoke_a_site.webp");
+        assert_ne!(molly_blob[..2], smoke_blob[..2]);
+        std::fs::create_dir_all(dest_path.join(object_store::OBJECTS_DIR)).unwrap();
+        std::fs::write(
+            dest_path
+                .join(object_store::OBJECTS_DIR)
+                .join(&molly_blob[..2]),
+            b"not a folder",
+        )
+        .unwrap();
+        let dest_persistence = Arc::new(
+            PersistenceService::new(dest_path.to_path_buf())
+                .expect("Failed to create destination persistence service"),
         );
+        let import_service = ExportService::new(Arc::clone(&dest_persistence));
+        let plan = import_service
+            .analyze_import(&export_path)
+            .expect("Analyze should succeed");
+        let result = import_service.apply_import(&plan, &HashMap::new());
+        assert!(matches!(result, Err(ExportServiceError::IoError(_))));
+
+        // Dust2's images were moved first and have been taken out again
+        assert!(dest_persistence.load_manifest().unwrap().images.is_empty());
+        assert!(!object_store::image_path(dest_path, "de_dust2", &smoke_blob).exists());
+        // The staging folder is gone with the plan
+        drop(plan);
+        let leftovers: Vec<_> = std::fs::read_dir(dest_path)
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(".import-"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
 
-        // If we have more than 1 file, then the second one should be a renamed version
-        if files.len() > 1 {
-            assert!(
-                files.iter().any(|f| f != "smoke_a_site.webp"
-                    && f.contains("smoke_a_site")
-                    && f.ends_with(".webp")),
-                "Renamed file should exist"
+    #[test]
+    fn test_import_plan_classifies_and_resolves_conflicts() {
+        use tempfile::TempDir;
+
+        let source_dir = TempDir::new().expect("Failed to create source temp dir");
+        let source_path = source_dir.path();
+        let dest_dir = TempDir::new().expect("Failed to create dest temp dir");
+        let export_path = source_path.join("export.nadex");
+
+        let map_name = "de_dust2";
+        let map_dir = source_path.join(map_name);
+        std::fs::create_dir_all(&map_dir).expect("Failed to create map directory");
+        create_dummy_image_file(map_dir.join("smoke_a_site.webp"), 100, 100);
+        create_dummy_image_file(map_dir.join("smoke_a_site_aim.webp"), 100, 100);
+        create_dummy_image_file(map_dir.join("flash_b_site.webp"), 100, 100);
+
+        let source_persistence = Arc::new(
+            PersistenceService::new(source_path.join("manifest.json"))
+                .expect("Failed to create source persistence service"),
+        );
+        let mut source_manifest = create_test_manifest();
+        source_persistence
+            .save_manifest(&source_manifest)
+            .expect("Failed to save source manifest");
+        let export_service = ExportService::new(Arc::clone(&source_persistence));
+        export_service
+            .export_library(&export_path, source_path)
+            .expect("Export should succeed");
+
+        let dest_persistence = Arc::new(
+            PersistenceService::new(dest_dir.path().to_path_buf())
+                .expect("Failed to create destination persistence service"),
+        );
+        let import_service = ExportService::new(Arc::clone(&dest_persistence));
+
+        // Analyzing writes nothing
+        let plan = import_service
+            .analyze_import(&export_path)
+            .expect("Analyze should succeed");
+        assert_eq!(plan.count(|status| *status == ImportStatus::New), 2);
+        assert_eq!(plan.new_strats, vec!["A execute".to_string()]);
+        assert!(!dest_dir.path().join(map_name).exists());
+        assert!(dest_persistence.load_manifest().unwrap().images.is_empty());
+
+        import_into_saved(&import_service, &plan, &HashMap::new()).expect("Import should succeed");
+        let plan = import_service
+            .analyze_import(&export_path)
+            .expect("Analyze should succeed");
+        assert_eq!(
+            plan.count(|status| matches!(status, ImportStatus::Duplicate { .. })),
+            2
+        );
+        assert!(plan.new_strats.is_empty());
+
+        // A teammate updates the smoke's notes and retakes the flash screenshot
+        source_manifest.images.get_mut(map_name).unwrap()[0].notes = "Jump throw, W first".into();
+        source_persistence
+            .save_manifest(&source_manifest)
+            .expect("Failed to save source manifest");
+        create_dummy_image_file(map_dir.join("flash_b_site.webp"), 120, 90);
+        export_service
+            .export_library(&export_path, source_path)
+            .expect("Export should succeed");
+
+        let plan = import_service
+            .analyze_import(&export_path)
+            .expect("Analyze should succeed");
+        let status_of = |id: &str| {
+            plan.items
+                .iter()
+                .find(|item| item.incoming.id == id)
+                .map(|item| item.status.clone())
+                .unwrap()
+        };
+        assert_eq!(
+            status_of("smoke-a-site-id"),
+            ImportStatus::Conflict {
+                existing_id: "smoke-a-site-id".to_string(),
+                differences: vec!["notes"],
+            }
+        );
+        assert_eq!(
+            status_of("flash-b-site-id"),
+            ImportStatus::Conflict {
+                existing_id: "flash-b-site-id".to_string(),
+                differences: vec!["images"],
+            }
+        );
+
+        let old_smoke = dest_persistence
+            .load_manifest()
+            .unwrap()
+            .find_image("smoke-a-site-id")
+            .cloned()
+            .unwrap();
+        let resolutions = HashMap::from([
+            (
+                "smoke-a-site-id".to_string(),
+                ConflictResolution::TakeTheirs,
+            ),
+            ("flash-b-site-id".to_string(), ConflictResolution::KeepBoth),
+        ]);
+        let imported =
+            import_into_saved(&import_service, &plan, &resolutions).expect("Import should succeed");
+
+        // Taking theirs replaces the entry in place; its screenshot didn't change, so it
+        // still uses the same stored file
+        let smoke = imported.find_image("smoke-a-site-id").unwrap();
+        assert_eq!(smoke.notes, "Jump throw, W first");
+        assert_eq!(smoke.order, old_smoke.order);
+        assert_eq!(smoke.filename, old_smoke.filename);
+        assert!(object_store::image_path(dest_dir.path(), map_name, &smoke.filename).exists());
+
+        // Keeping both adds the teammate's flash under a new id
+        let flashes: Vec<&ImageMeta> = imported.images[map_name]
+            .iter()
+            .filter(|img| img.position == "B Site Flash")
+            .collect();
+        assert_eq!(flashes.len(), 2);
+        assert!(flashes.iter().any(|img| img.id == "flash-b-site-id"));
+        assert_eq!(imported.strats.len(), 1, "Known strats are not duplicated");
+        assert_eq!(dest_persistence.load_manifest().unwrap(), imported);
+    }
+
+    #[test]
+    fn test_import_merges_into_the_library_in_memory() {
+        use tempfile::TempDir;
+
+        let source_dir = TempDir::new().expect("Failed to create source temp dir");
+        let source_path = source_dir.path();
+        let dest_dir = TempDir::new().expect("Failed to create dest temp dir");
+        let export_path = source_path.join("export.nadex");
+
+        let map_dir = source_path.join("de_dust2");
+        std::fs::create_dir_all(&map_dir).expect("Failed to create map directory");
+        create_dummy_image_file(map_dir.join("smoke_a_site.webp"), 100, 100);
+        create_dummy_image_file(map_dir.join("smoke_a_site_aim.webp"), 100, 100);
+        create_dummy_image_file(map_dir.join("flash_b_site.webp"), 100, 100);
+        let source_persistence = Arc::new(
+            PersistenceService::new(source_path.join("manifest.json"))
+                .expect("Failed to create source persistence service"),
+        );
+        source_persistence
+            .save_manifest(&create_test_manifest())
+            .expect("Failed to save source manifest");
+        ExportService::new(source_persistence)
+            .export_library(&export_path, source_path)
+            .expect("Export should succeed");
+
+        let dest_persistence = Arc::new(
+            PersistenceService::new(dest_dir.path().to_path_buf())
+                .expect("Failed to create destination persistence service"),
+        );
+        let import_service = ExportService::new(Arc::clone(&dest_persistence));
+        let plan = import_service
+            .analyze_import(&export_path)
+            .expect("Analyze should succeed");
+        let imported = import_service
+            .apply_import(&plan, &HashMap::new())
+            .expect("Import should succeed");
+        // Applying only moves the images; the manifest is left to `merge_import`
+        assert!(dest_persistence.load_manifest().unwrap().images.is_empty());
+
+        // The library changed while the import ran
+        let mut library = ImageManifest::default();
+        library = library.clone_and_add(
+            ImageMeta {
+                id: "banana-molly-id".to_string(),
+                filename: "banana_molly.webp".to_string(),
+                map: "de_inferno".to_string(),
+                nade_type: NadeType::Molotov,
+                position: "Banana Molly".to_string(),
+                ..Default::default()
+            },
+            "de_inferno",
+        );
+        dest_persistence
+            .save_manifest(&library)
+            .expect("Failed to save destination manifest");
+
+        import_service
+            .merge_import(&imported, &mut library)
+            .expect("Merge should succeed");
+        assert!(library.find_image("banana-molly-id").is_some());
+        assert!(library.find_image("smoke-a-site-id").is_some());
+        assert!(library.find_image("flash-b-site-id").is_some());
+        assert_eq!(library.strats.len(), 1);
+        assert_eq!(dest_persistence.load_manifest().unwrap(), library);
+    }
+
+    #[test]
+    fn test_export_writes_a_shared_image_once() {
+        use tempfile::TempDir;
+
+        let source_dir = TempDir::new().expect("Failed to create source temp dir");
+        let source_path = source_dir.path();
+        let dest_dir = TempDir::new().expect("Failed to create dest temp dir");
+        let export_path = source_path.join("export.nadex");
+
+        // Two lineups, one with the shared image as an extra step, stored as one blob
+        let upload = source_path.join("upload.webp");
+        create_dummy_image_file(&upload, 100, 100);
+        let blob = object_store::copy_into_store(source_path, &upload, "webp").unwrap();
+        let mut manifest = ImageManifest::default();
+        for (id, position) in [("smoke-id", "Window"), ("flash-id", "Connector")] {
+            manifest = manifest.clone_and_add(
+                ImageMeta {
+                    id: id.to_string(),
+                    filename: blob.clone(),
+                    map: "de_mirage".to_string(),
+                    position: position.to_string(),
+                    ..Default::default()
+                },
+                "de_mirage",
             );
         }
+        manifest.images.get_mut("de_mirage").unwrap()[1]
+            .extra_images
+            .push(LineupImage {
+                filename: blob.clone(),
+                role: ImageRole::Aim,
+            });
+        let source_persistence = Arc::new(
+            PersistenceService::new(source_path.to_path_buf())
+                .expect("Failed to create source persistence service"),
+        );
+        source_persistence
+            .save_manifest(&manifest)
+            .expect("Failed to save source manifest");
+        ExportService::new(source_persistence)
+            .export_library(&export_path, source_path)
+            .expect("Export should succeed");
+
+        // `file_names` reads a map, so count the entries themselves
+        let mut archive = zip::ZipArchive::new(File::open(&export_path).unwrap()).unwrap();
+        let mut files: Vec<String> = (0..archive.len())
+            .map(|i| archive.by_index(i).unwrap().name().to_string())
+            .collect();
+        files.sort();
+        let shared = format!("images/de_mirage/{}", blob);
+        assert_eq!(
+            files,
+            vec![shared.as_str(), "manifest.json", "package.json"]
+        );
+
+        // Both lineups import and point at the one image
+        let dest_persistence = Arc::new(
+            PersistenceService::new(dest_dir.path().to_path_buf())
+                .expect("Failed to create destination persistence service"),
+        );
+        let imported = import_library(&ExportService::new(dest_persistence), &export_path)
+            .expect("Import should succeed");
+        let lineups = &imported.images["de_mirage"];
+        assert_eq!(lineups.len(), 2);
+        assert_eq!(lineups[0].filename, lineups[1].filename);
+        assert_eq!(lineups[1].extra_images[0].filename, lineups[0].filename);
+        assert!(
+            object_store::image_path(dest_dir.path(), "de_mirage", &lineups[0].filename).exists()
+        );
+    }
+
+    #[test]
+    fn test_selective_export_contains_only_matching_entries() {
+        use crate::services::persistence_service::PersistenceService;
+        use tempfile::TempDir;
+
+        let source_dir = TempDir::new().expect("Failed to create source temp dir");
+        let source_path = source_dir.path();
+        let export_path = source_path.join("export.nadex");
+
+        let map_dir = source_path.join("de_dust2");
+        std::fs::create_dir_all(&map_dir).expect("Failed to create map directory");
+        create_dummy_image_file(map_dir.join("smoke_a_site.webp"), 100, 100);
+        create_dummy_image_file(map_dir.join("smoke_a_site_aim.webp"), 100, 100);
+        create_dummy_image_file(map_dir.join("flash_b_site.webp"), 100, 100);
+        let anubis_dir = source_path.join("de_anubis");
+        std::fs::create_dir_all(&anubis_dir).expect("Failed to create map directory");
+        create_dummy_image_file(anubis_dir.join("mid_smoke.webp"), 100, 100);
+
+        let mut manifest = create_test_manifest();
+        manifest.images.insert(
+            "de_anubis".to_string(),
+            vec![ImageMeta {
+                id: "anubis-mid-id".to_string(),
+                filename: "mid_smoke.webp".to_string(),
+                map: "de_anubis".to_string(),
+                side: Side::T,
+                ..Default::default()
+            }],
+        );
+        manifest.strats.push(Strat {
+            id: "b-hit-id".to_string(),
+            name: "B hit".to_string(),
+            map: "de_dust2".to_string(),
+            notes: String::new(),
+            steps: vec![StratStep {
+                image_id: "flash-b-site-id".to_string(),
+                player: 1,
+                order: 1,
+            }],
+        });
+        let persistence = Arc::new(
+            PersistenceService::new(source_path.join("manifest.json"))
+                .expect("Failed to create persistence service"),
+        );
+        persistence
+            .save_manifest(&manifest)
+            .expect("Failed to save manifest");
+        let export_service = ExportService::new(Arc::clone(&persistence));
+
+        let read_package = |path: &Path| {
+            let mut archive = zip::ZipArchive::new(File::open(path).unwrap()).unwrap();
+            let mut files: Vec<String> = archive.file_names().map(str::to_string).collect();
+            files.sort();
+            let mut manifest_json = String::new();
+            archive
+                .by_name("manifest.json")
+                .unwrap()
+                .read_to_string(&mut manifest_json)
+                .unwrap();
+            let manifest: ImageManifest = serde_json::from_str(&manifest_json).unwrap();
+            (manifest, files)
+        };
+
+        // Only the T-side lineups of one map, with every step's file
+        let count = export_service
+            .export_matching(&export_path, source_path, |img| {
+                img.map == "de_dust2" && img.side == Side::T
+            })
+            .expect("Export should succeed");
+        assert_eq!(count, 1);
+        let (exported, files) = read_package(&export_path);
+        assert_eq!(
+            files,
+            vec![
+                "images/de_dust2/smoke_a_site.webp",
+                "images/de_dust2/smoke_a_site_aim.webp",
+                "manifest.json",
+                "package.json",
+            ]
+        );
+        assert_eq!(exported.images.len(), 1);
+        assert_eq!(exported.images["de_dust2"][0].id, "smoke-a-site-id");
+        assert!(!exported.maps.contains_key("de_anubis"));
+        assert!(
+            exported.strats.is_empty(),
+            "Strats with lineups outside the selection are left out"
+        );
+
+        // A strat brings its lineups along
+        let count = export_service
+            .export_strat(&export_path, source_path, "b-hit-id")
+            .expect("Strat export should succeed");
+        assert_eq!(count, 1);
+        let (exported, files) = read_package(&export_path);
+        assert_eq!(
+            files,
+            vec![
+                "images/de_dust2/flash_b_site.webp",
+                "manifest.json",
+                "package.json"
+            ]
+        );
+        assert_eq!(exported.strats.len(), 1);
+        assert_eq!(exported.strats[0].name, "B hit");
+
+        // Hand-picked ids across maps
+        let picked = vec!["anubis-mid-id".to_string(), "flash-b-site-id".to_string()];
+        let count = export_service
+            .export_images(&export_path, source_path, &picked)
+            .expect("Export should succeed");
+        assert_eq!(count, 2);
+        let (exported, _) = read_package(&export_path);
+        assert_eq!(exported.images.len(), 2);
+
+        assert!(matches!(
+            export_service.export_images(&export_path, source_path, &[]),
+            Err(ExportServiceError::NothingToExport)
+        ));
     }
 
     // Helper function to create a dummy WebP image file for testing
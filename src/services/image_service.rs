@@ -1,16 +1,24 @@
 use crate::app_actions::AppAction; // For sending actions
-use crate::persistence::{ImageManifest, ImageMeta, NadeType};
+use crate::history::Operation;
+use crate::object_store;
+use crate::persistence::{
+    ImageManifest, ImageMeta, ImageRole, MapMeta, NadeType, STRAT_PLAYERS, Setpos, Side, Strat,
+    ThrowTechnique, Trash, new_image_id, normalize_tag,
+};
 use crate::services::persistence_service::PersistenceService;
 use crate::services::persistence_service::PersistenceServiceError;
 #[cfg(test)]
 use crate::services::thumbnail_service::{SerializableImageError, SerializableIoError};
 use crate::services::thumbnail_service::{ThumbnailServiceError, ThumbnailServiceTrait};
 use crate::ui::edit_view::EditFormData; // Ensure EditFormData is in scope
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
 use image::{self, GenericImageView}; // For image dimension validation and GenericImageView trait
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex}; // For sender type
+use std::time::SystemTime;
 
 #[derive(Debug)]
 pub enum ImageServiceError {
@@ -45,6 +53,9 @@ impl From<PersistenceServiceError> for ImageServiceError {
             PersistenceServiceError::SerializationError(msg) => {
                 ImageServiceError::Other(format!("Manifest serialization error: {}", msg))
             }
+            err @ PersistenceServiceError::CorruptManifest(_) => {
+                ImageServiceError::Other(err.to_string())
+            }
             PersistenceServiceError::ThumbnailGenerationFailed(thumb_err) => {
                 ImageServiceError::Thumbnail(thumb_err)
             }
@@ -61,6 +72,26 @@ impl From<std::io::Error> for ImageServiceError {
     }
 }
 
+/// Normalize and de-duplicate tags entered in a form, keeping their order.
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags.iter().filter_map(|t| normalize_tag(t)) {
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// A change applied to every selected lineup at once from the grid's selection bar
+#[derive(Debug, Clone, PartialEq)]
+pub enum BulkEdit {
+    NadeType(NadeType),
+    Side(Side),
+    AddTag(String),
+    RemoveTag(String),
+}
+
 #[derive(Debug)] // Added derive Debug for ImageService
 pub struct ImageService {
     persistence_service: Arc<PersistenceService>,
@@ -98,6 +129,69 @@ impl ImageService {
             })
     }
 
+    /// Move lineup `moved_id` to where `target_id` is in `images`, a map's lineups in grid
+    /// order, and renumber their `order`. The grid reorders by the ids it shows, so lineups
+    /// hidden by a filter keep their places between the shown ones.
+    /// Returns false if either lineup isn't in `images`.
+    pub fn move_image_to(images: &mut Vec<ImageMeta>, moved_id: &str, target_id: &str) -> bool {
+        let position = |id: &str| images.iter().position(|meta| meta.id == id);
+        let (Some(from), Some(to)) = (position(moved_id), position(target_id)) else {
+            return false;
+        };
+        let item = images.remove(from);
+        images.insert(to, item);
+        for (index, image_meta) in images.iter_mut().enumerate() {
+            image_meta.order = index;
+        }
+        true
+    }
+
+    /// Search lineups on every map, best matches first.
+    ///
+    /// The query is split on whitespace and every term has to fuzzily match at least one of
+    /// position, tags, map name or notes. A term's score is its best match, weighted so that
+    /// position hits rank above tag and map hits, which rank above notes.
+    pub fn search_images(&self, image_manifest: &ImageManifest, query: &str) -> Vec<ImageMeta> {
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let matcher = SkimMatcherV2::default().ignore_case();
+
+        let mut scored: Vec<(i64, &ImageMeta)> = image_manifest
+            .images
+            .values()
+            .flatten()
+            .filter_map(|meta| {
+                let mut total = 0;
+                for term in &terms {
+                    let position_score = matcher.fuzzy_match(&meta.position, term).map(|s| s * 3);
+                    let tag_score = meta
+                        .tags
+                        .iter()
+                        .filter_map(|tag| matcher.fuzzy_match(tag, term))
+                        .max()
+                        .map(|s| s * 2);
+                    let map_score = matcher.fuzzy_match(&meta.map, term).map(|s| s * 2);
+                    let notes_score = matcher.fuzzy_match(&meta.notes, term);
+                    total += [position_score, tag_score, map_score, notes_score]
+                        .into_iter()
+                        .flatten()
+                        .max()?; // Every term has to match somewhere
+                }
+                Some((total, meta))
+            })
+            .collect();
+
+        scored.sort_by(|(score_a, a), (score_b, b)| {
+            score_b
+                .cmp(score_a)
+                .then_with(|| a.map.cmp(&b.map))
+                .then_with(|| a.order.cmp(&b.order))
+        });
+        scored.into_iter().map(|(_, meta)| meta.clone()).collect()
+    }
+
     pub fn update_image_metadata(
         &self,
         manifest: &mut ImageManifest,
@@ -106,53 +200,174 @@ impl ImageService {
     ) -> Result<(), ImageServiceError> {
         let map_name = &original_image_meta.map;
 
-        // Ensure the filename from form_data matches the original, as a sanity check
-        // Filename should not be editable through this form, but good to verify.
-        if original_image_meta.filename != form_data.filename {
+        // Ensure the id from form_data matches the original, as a sanity check
+        if original_image_meta.id != form_data.id {
             return Err(ImageServiceError::InputError(format!(
-                "Filename mismatch: original '{}', form data '{}'. Cannot update.",
-                original_image_meta.filename, form_data.filename
+                "Image id mismatch: original '{}', form data '{}'. Cannot update.",
+                original_image_meta.id, form_data.id
             )));
         }
 
-        if let Some(images_in_map) = manifest.images.get_mut(map_name) {
-            if let Some(image_to_update) = images_in_map
-                .iter_mut()
-                .find(|img| img.filename == original_image_meta.filename)
-            // Find by original filename
-            {
-                image_to_update.nade_type = form_data.nade_type;
-                image_to_update.position = form_data.position.clone();
-                image_to_update.notes = form_data.notes.clone();
-
-                // After updating in-memory manifest, save it to disk
-                self.persistence_service.save_manifest(manifest)?;
-                Ok(())
-            } else {
-                Err(ImageServiceError::NotFound(format!(
-                    "Image with filename '{}' not found in map '{}'.",
-                    original_image_meta.filename, // Use original filename for error message
-                    map_name
-                )))
-            }
-        } else {
-            Err(ImageServiceError::NotFound(format!(
+        let Some(images_in_map) = manifest.images.get(map_name) else {
+            return Err(ImageServiceError::NotFound(format!(
                 "Map '{}' not found in manifest.",
                 map_name
-            )))
+            )));
+        };
+        if !images_in_map
+            .iter()
+            .any(|img| img.id == original_image_meta.id)
+        {
+            return Err(ImageServiceError::NotFound(format!(
+                "Image '{}' ({}) not found in map '{}'.",
+                original_image_meta.id, original_image_meta.filename, map_name
+            )));
+        }
+
+        // Work on a copy, so a failed move or save leaves the manifest as it was
+        let mut updated = manifest.clone();
+        // A new map moves the lineup there first, or copies it so only the copy is edited
+        let target_id = if form_data.map != *map_name {
+            self.relocate_images(
+                &mut updated,
+                std::slice::from_ref(&form_data.id),
+                &form_data.map,
+                form_data.keep_copy,
+            )?
+            .pop()
+            .unwrap_or_else(|| form_data.id.clone())
+        } else {
+            form_data.id.clone()
+        };
+
+        if let Some(image_to_update) = updated
+            .images
+            .values_mut()
+            .flatten()
+            .find(|img| img.id == target_id)
+        {
+            image_to_update.nade_type = form_data.nade_type;
+            image_to_update.side = form_data.side;
+            image_to_update.technique = form_data.technique;
+            image_to_update.position = form_data.position.clone();
+            image_to_update.notes = form_data.notes.clone();
+            image_to_update.tags = normalize_tags(&form_data.tags);
+            image_to_update.throw_pos = form_data.throw_pos;
+            image_to_update.landing_pos = form_data.landing_pos;
+            image_to_update.view_angles = form_data.view_angles;
         }
+
+        self.persistence_service.save_manifest(&updated)?;
+        *manifest = updated;
+        Ok(())
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn upload_image(
+    /// Move the lineups with `image_ids` to the end of `target_map`'s grid, or with `copy`,
+    /// add copies there under new ids and leave the originals. Saves the manifest and returns
+    /// the ids of the lineups now on `target_map`.
+    ///
+    /// Both maps' grid orders stay sequential, and a map left without lineups is removed.
+    /// Moved lineups leave the strats of their old map; copies join no strats.
+    pub fn move_images_to_map(
         &self,
-        original_file_path: &Path,
-        map_name: &str,
-        nade_type: NadeType,
-        position_details: &str,
-        throw_instructions: &str,
-    ) -> Result<ImageMeta, ImageServiceError> {
-        // 1. Validate the image (open and check dimensions)
+        manifest: &mut ImageManifest,
+        image_ids: &[String],
+        target_map: &str,
+        copy: bool,
+    ) -> Result<Vec<String>, ImageServiceError> {
+        let moved = self.relocate_images(manifest, image_ids, target_map, copy)?;
+        self.persistence_service.save_manifest(manifest)?;
+        Ok(moved)
+    }
+
+    /// `move_images_to_map` without saving
+    fn relocate_images(
+        &self,
+        manifest: &mut ImageManifest,
+        image_ids: &[String],
+        target_map: &str,
+        copy: bool,
+    ) -> Result<Vec<String>, ImageServiceError> {
+        if target_map.trim().is_empty() {
+            return Err(ImageServiceError::InputError(
+                "Map name cannot be empty.".to_string(),
+            ));
+        }
+        if let Some(missing) = image_ids
+            .iter()
+            .find(|id| manifest.find_image(id).is_none())
+        {
+            return Err(ImageServiceError::NotFound(format!(
+                "Image '{}' not found in manifest.",
+                missing
+            )));
+        }
+        // Images from before the store live in their map's folder; once they're in the store,
+        // the lineup can be on any map
+        object_store::adopt_legacy_images(manifest, &self.persistence_service.get_data_dir());
+
+        // In grid order, so lineups keep their order relative to each other
+        let mut images: Vec<ImageMeta> = image_ids
+            .iter()
+            .filter_map(|id| manifest.find_image(id).cloned())
+            .collect();
+        images.sort_by(|a, b| (&a.map, a.order).cmp(&(&b.map, b.order)));
+
+        let mut relocated = Vec::new();
+        let mut source_maps = Vec::new();
+        for image in images {
+            if image.map == target_map && !copy {
+                continue;
+            }
+            let mut placed = image.clone();
+            if copy {
+                placed.id = new_image_id();
+            } else {
+                if let Some(images) = manifest.images.get_mut(&image.map) {
+                    images.retain(|img| img.id != image.id);
+                }
+                manifest.remove_image_from_strats(&image.id);
+                source_maps.push(image.map.clone());
+            }
+            placed.map = target_map.to_string();
+            let target_images = manifest.images.entry(target_map.to_string()).or_default();
+            placed.order = target_images
+                .iter()
+                .map(|img| img.order + 1)
+                .max()
+                .unwrap_or(0);
+            relocated.push(placed.id.clone());
+            target_images.push(placed);
+        }
+
+        for map in source_maps {
+            match manifest.images.get_mut(&map) {
+                Some(images) if images.is_empty() => {
+                    manifest.images.remove(&map);
+                    manifest.maps.remove(&map);
+                }
+                Some(images) => {
+                    images.sort_by_key(|img| img.order);
+                    for (idx, image) in images.iter_mut().enumerate() {
+                        image.order = idx;
+                    }
+                }
+                None => {}
+            }
+        }
+        if !relocated.is_empty() {
+            manifest.maps.insert(
+                target_map.to_string(),
+                MapMeta {
+                    last_accessed: SystemTime::now(),
+                },
+            );
+        }
+        Ok(relocated)
+    }
+
+    /// Check that a file opens as an image and is within the accepted dimensions.
+    fn validate_image_file(original_file_path: &Path) -> Result<(), ImageServiceError> {
         let img = image::open(original_file_path).map_err(|e| {
             ImageServiceError::InputError(format!(
                 "Failed to open image '{}' for validation: {}",
@@ -189,56 +404,211 @@ impl ImageService {
             )));
         }
 
-        // 2. Copy image to data directory and get unique filename.
+        Ok(())
+    }
+
+    /// Rename a tag across the whole manifest, merging it into `to` if that tag already exists.
+    /// Returns the number of images changed; the manifest is only saved if something changed.
+    pub fn rename_tag(
+        &self,
+        manifest: &mut ImageManifest,
+        from: &str,
+        to: &str,
+    ) -> Result<usize, ImageServiceError> {
+        let to = normalize_tag(to).ok_or_else(|| {
+            ImageServiceError::InputError("Tag name cannot be empty.".to_string())
+        })?;
+        if from == to {
+            return Ok(0);
+        }
+
+        let changed = manifest.rename_tag(from, &to);
+        if changed > 0 {
+            self.persistence_service.save_manifest(manifest)?;
+        }
+        Ok(changed)
+    }
+
+    /// Apply `edit` to every lineup in `image_ids` and save. Ids that aren't in the manifest
+    /// are skipped. Returns the number of lineups changed.
+    pub fn bulk_edit_images(
+        &self,
+        manifest: &mut ImageManifest,
+        image_ids: &[String],
+        edit: &BulkEdit,
+    ) -> Result<usize, ImageServiceError> {
+        let edit = match edit {
+            BulkEdit::AddTag(tag) | BulkEdit::RemoveTag(tag) => {
+                let tag = normalize_tag(tag).ok_or_else(|| {
+                    ImageServiceError::InputError("Tag name cannot be empty.".to_string())
+                })?;
+                if matches!(edit, BulkEdit::AddTag(_)) {
+                    BulkEdit::AddTag(tag)
+                } else {
+                    BulkEdit::RemoveTag(tag)
+                }
+            }
+            edit => edit.clone(),
+        };
+
+        let mut changed = 0;
+        for image in manifest
+            .images
+            .values_mut()
+            .flatten()
+            .filter(|img| image_ids.contains(&img.id))
+        {
+            let before = image.clone();
+            match &edit {
+                BulkEdit::NadeType(nade_type) => image.nade_type = *nade_type,
+                BulkEdit::Side(side) => image.side = *side,
+                BulkEdit::AddTag(tag) => {
+                    if !image.tags.contains(tag) {
+                        image.tags.push(tag.clone());
+                    }
+                }
+                BulkEdit::RemoveTag(tag) => image.tags.retain(|t| t != tag),
+            }
+            if *image != before {
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.persistence_service.save_manifest(manifest)?;
+        }
+        Ok(changed)
+    }
+
+    /// Add a strat to the manifest, or replace the stored strat with the same id, and save.
+    /// Every step must point at a lineup on the strat's map and a player from 1 to 5.
+    pub fn save_strat(
+        &self,
+        manifest: &mut ImageManifest,
+        mut strat: Strat,
+    ) -> Result<(), ImageServiceError> {
+        strat.name = strat.name.trim().to_string();
+        if strat.name.is_empty() {
+            return Err(ImageServiceError::InputError(
+                "Strat name cannot be empty.".to_string(),
+            ));
+        }
+        for step in &strat.steps {
+            if !(1..=STRAT_PLAYERS).contains(&step.player) {
+                return Err(ImageServiceError::InputError(format!(
+                    "Player {} is not a valid role (1 to {}).",
+                    step.player, STRAT_PLAYERS
+                )));
+            }
+            if manifest
+                .find_image(&step.image_id)
+                .is_none_or(|meta| meta.map != strat.map)
+            {
+                return Err(ImageServiceError::NotFound(format!(
+                    "Lineup '{}' is not on {}.",
+                    step.image_id, strat.map
+                )));
+            }
+        }
+
+        match manifest.strats.iter_mut().find(|s| s.id == strat.id) {
+            Some(existing) => *existing = strat,
+            None => manifest.strats.push(strat),
+        }
+        self.persistence_service.save_manifest(manifest)?;
+        Ok(())
+    }
+
+    /// Remove a strat from the manifest and save. The lineups it used are kept.
+    pub fn delete_strat(
+        &self,
+        manifest: &mut ImageManifest,
+        strat_id: &str,
+    ) -> Result<(), ImageServiceError> {
+        let before = manifest.strats.len();
+        manifest.strats.retain(|strat| strat.id != strat_id);
+        if manifest.strats.len() == before {
+            return Err(ImageServiceError::NotFound(format!(
+                "Strat '{}' not found.",
+                strat_id
+            )));
+        }
+        self.persistence_service.save_manifest(manifest)?;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn upload_image(
+        &self,
+        image_files: &[(PathBuf, ImageRole)],
+        map_name: &str,
+        nade_type: NadeType,
+        side: Side,
+        technique: ThrowTechnique,
+        tags: &[String],
+        setpos: Option<Setpos>,
+        position_details: &str,
+        throw_instructions: &str,
+    ) -> Result<ImageMeta, ImageServiceError> {
+        // 1. Validate every image of the lineup (open and check dimensions)
+        if image_files.is_empty() {
+            return Err(ImageServiceError::InputError(
+                "No image selected for upload.".to_string(),
+            ));
+        }
+        for (original_file_path, _role) in image_files {
+            Self::validate_image_file(original_file_path)?;
+        }
+
+        // 2. Copy images to data directory and get unique filenames.
         //    This also triggers thumbnail generation via PersistenceService.
-        let (_dest_path, unique_filename) = self.persistence_service.copy_image_to_data(
-            original_file_path,
+        let (_dest_path, new_image_meta) = self.persistence_service.copy_image_to_data(
+            image_files,
             map_name,
-            nade_type,                      // Pass through nade_type
+            nade_type, // Pass through nade_type
+            side,
+            technique,
+            normalize_tags(tags),
+            setpos,
             throw_instructions.to_string(), // Pass through as notes
             position_details.to_string(),   // Pass through as position
             &self.thumbnail_service,
         )?;
 
-        // 3. Create ImageMeta
-        let new_image_meta = ImageMeta {
-            filename: unique_filename,
-            map: map_name.to_string(),
-            nade_type,
-            notes: throw_instructions.to_string(),
-            position: position_details.to_string(),
-            order: 0, // Will be set properly when added to manifest
-        };
-
-        // Manifest update and saving are handled by persistence_service.copy_image_to_data.
+        // Manifest update and saving are handled by persistence_service.copy_image_to_data,
+        // which also assigns the new image its id.
         Ok(new_image_meta)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn orchestrate_full_upload_process(
         self: Arc<Self>,
-        file_path: PathBuf,
+        image_files: Vec<(PathBuf, ImageRole)>,
         map_name: String,
         nade_type: NadeType,
+        side: Side,
+        technique: ThrowTechnique,
+        tags: Vec<String>,
+        setpos: Option<Setpos>,
         position: String,
         notes: String,
         app_action_sender: mpsc::Sender<AppAction>,
     ) {
         log::info!(
-            "ImageService: Orchestrating full upload for map: {}, file: {:?}",
+            "ImageService: Orchestrating full upload for map: {}, files: {:?}",
             map_name,
-            file_path
+            image_files
         );
 
         // Spawn background thread for image processing.
         std::thread::spawn(move || {
             log::info!(
                 "ImageService Orchestration(T1): Starting image processing for {:?}",
-                file_path
+                image_files
             );
 
             let upload_result = self.upload_image(
-                &file_path, &map_name, // map_name is borrowed here
-                nade_type, &position, &notes,
+                &image_files, &map_name, // map_name is borrowed here
+                nade_type, side, technique, &tags, setpos, &position, &notes,
             );
 
             match upload_result {
@@ -287,41 +657,129 @@ impl ImageService {
         });
     }
 
-    pub fn delete_image(
+    /// Move lineups to the trash. The manifest and the trash are saved once for all of them,
+    /// before any file is touched; their thumbnails and legacy image files are cleaned up
+    /// afterwards. If that cleanup fails, every lineup is put back.
+    pub fn delete_images(
         &self,
-        image_to_delete: &ImageMeta,
+        images_to_delete: &[ImageMeta],
         manifest: &mut ImageManifest,
     ) -> Result<(), ImageServiceError> {
-        // 1. Delete image file and its thumbnails from disk
-        self.persistence_service.delete_image_and_thumbnails(
-            &image_to_delete.map,      // This field holds the actual map name
-            &image_to_delete.filename, // This field holds the actual image filename
-            &self.thumbnail_service,
-        )?;
+        let before = manifest.clone();
+
+        // 1. Take the lineups out of a copy of the manifest
+        let mut updated = manifest.clone();
+        for image_to_delete in images_to_delete {
+            // Strats that used the lineup lose that step, and an emptied map goes away
+            if !updated.remove_image(image_to_delete) {
+                // This case should ideally not happen if image_to_delete was valid and came from the manifest.
+                // However, good to log if it does.
+                log::warn!(
+                    "Attempted to delete image from map '{}' which was not found in manifest. Id: {}",
+                    image_to_delete.map,
+                    image_to_delete.id
+                );
+            }
+        }
 
-        // 2. Remove ImageMeta from the manifest
-        if let Some(images_in_map) = manifest.images.get_mut(&image_to_delete.map) {
-            images_in_map.retain(|meta| meta.filename != image_to_delete.filename);
-            // Note: We are not removing the map from manifest.maps even if images_in_map becomes empty.
-            // This is consistent with previous direct deletion logic.
-        } else {
-            // This case should ideally not happen if image_to_delete was valid and came from the manifest.
-            // However, good to log if it does.
-            log::warn!(
-                "Attempted to delete image from map '{}' which was not found in manifest. Filename: {}",
-                image_to_delete.map,
-                image_to_delete.filename
-            );
-            // Optionally, return an error here if strict consistency is required, e.g.:
-            // return Err(ImageServiceError::NotFound(format!(
-            //     "Map '{}' not found in manifest during delete operation for file '{}'.",
-            //     image_to_delete.map, image_to_delete.filename
-            // )));
+        // 2. Keep the lineups in the trash, so they can be restored later, and save both once
+        let trash_before = self.persistence_service.load_trash();
+        let mut trash = trash_before.clone();
+        let now = SystemTime::now();
+        for image_to_delete in images_to_delete {
+            trash.add(image_to_delete.clone(), now);
+        }
+        self.persistence_service.save_trash(&trash)?;
+        if let Err(e) = self.persistence_service.save_manifest(&updated) {
+            self.save_trash_or_log(&trash_before);
+            return Err(e.into());
         }
+        *manifest = updated;
+
+        // 3. Delete their thumbnails from disk and move legacy image files into the store
+        for image_to_delete in images_to_delete {
+            if let Err(e) = self
+                .persistence_service
+                .remove_image_files(image_to_delete, &self.thumbnail_service)
+            {
+                // Put every lineup back. Legacy files of the ones cleaned up so far are in
+                // the store now, so point them at their blobs.
+                *manifest = before;
+                object_store::adopt_legacy_images(
+                    manifest,
+                    &self.persistence_service.get_data_dir(),
+                );
+                if let Err(save_err) = self.persistence_service.save_manifest(manifest) {
+                    log::error!(
+                        "Failed to put the lineups back in the manifest: {}",
+                        save_err
+                    );
+                }
+                self.save_trash_or_log(&trash_before);
+                return Err(e.into());
+            }
+        }
+
+        Ok(())
+    }
 
-        // 3. Save the updated manifest
+    fn save_trash_or_log(&self, trash: &Trash) {
+        if let Err(e) = self.persistence_service.save_trash(trash) {
+            log::error!("Failed to take the lineups back out of the trash: {}", e);
+        }
+    }
+
+    /// Apply an undo or redo step from `AppState::history` to `manifest` and save it.
+    pub fn apply_operation(
+        &self,
+        operation: &Operation,
+        manifest: &mut ImageManifest,
+    ) -> Result<(), ImageServiceError> {
+        operation.apply(manifest);
+        // A restored lineup from before the store was stashed there on delete; point it at
+        // the blob its file became
+        object_store::adopt_legacy_images(manifest, &self.persistence_service.get_data_dir());
         self.persistence_service.save_manifest(manifest)?;
 
+        // Lineups the step takes out go to the trash like any delete; ones it brings back leave it
+        let mut trash = self.persistence_service.load_trash();
+        let trash_before = trash.clone();
+        let now = SystemTime::now();
+        for image in operation.removed_images() {
+            trash.add(image.clone(), now);
+        }
+        for image in operation.added_images() {
+            trash.take(&image.id);
+        }
+        if trash != trash_before {
+            self.persistence_service.save_trash(&trash)?;
+        }
+        Ok(())
+    }
+
+    /// Put a lineup from the trash back into `manifest`, at the end of its map's grid, and
+    /// save both. Strats it was part of don't get it back.
+    pub fn restore_from_trash(
+        &self,
+        id: &str,
+        manifest: &mut ImageManifest,
+    ) -> Result<(), ImageServiceError> {
+        let mut trash = self.persistence_service.load_trash();
+        let mut image = trash
+            .take(id)
+            .ok_or_else(|| {
+                ImageServiceError::NotFound(format!("Lineup {} is not in the trash", id))
+            })?
+            .image;
+        // It may be back already, e.g. through undo
+        if manifest.find_image(id).is_none() {
+            let images = manifest.images.entry(image.map.clone()).or_default();
+            image.order = images.iter().map(|img| img.order + 1).max().unwrap_or(0);
+            images.push(image);
+            object_store::adopt_legacy_images(manifest, &self.persistence_service.get_data_dir());
+            self.persistence_service.save_manifest(manifest)?;
+        }
+        self.persistence_service.save_trash(&trash)?;
         Ok(())
     }
 }
@@ -336,12 +794,11 @@ impl ImageService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::persistence::NadeType;
+    use crate::persistence::{Movement, NadeType, ThrowClick, ViewAngles, WorldPos};
     use crate::services::thumbnail_service::ThumbnailServiceError;
     #[cfg(test)]
     use crate::tests_common::{create_dummy_image_file, setup_test_environment};
     use std::fs;
-    use std::path::Path;
     use std::time::SystemTime;
 
     // Test for successful image upload
@@ -355,6 +812,11 @@ mod tests {
         let nade_type = NadeType::Smoke;
         let position_details = "Site A";
         let throw_instructions = "Align with box, aim at sky, run throw.";
+        let technique = ThrowTechnique {
+            movement: Movement::Run,
+            click: ThrowClick::Left,
+            jump_throw_bind: false,
+        };
 
         // Create the map directory and thumbnails directory
         let map_dir = env.data_dir_path.join(map_name);
@@ -367,11 +829,17 @@ mod tests {
         fs::create_dir_all(&source_image_dir).expect("Failed to create source_image_dir");
         let original_file_path =
             create_dummy_image_file(&source_image_dir, "test_upload_img.png", 1920, 1440);
+        let setpos = Setpos::parse("setpos -1234.5 567.25 -160.0;setang 4.5 -90.0 0.0")
+            .expect("Failed to parse getpos output");
 
         let result = env.image_service.upload_image(
-            &original_file_path,
+            &[(original_file_path.to_path_buf(), ImageRole::Stand)],
             map_name,
             nade_type,
+            Side::T,
+            technique,
+            &[],
+            Some(setpos),
             position_details,
             throw_instructions,
         );
@@ -381,46 +849,30 @@ mod tests {
 
         assert_eq!(image_meta.map, map_name);
         assert_eq!(image_meta.nade_type, nade_type);
+        assert_eq!(image_meta.side, Side::T);
+        assert_eq!(image_meta.technique, technique);
         assert_eq!(image_meta.position, position_details);
         assert_eq!(image_meta.notes, throw_instructions);
+        assert_eq!(image_meta.setpos(), Some(setpos));
+        assert_eq!(
+            image_meta.throw_pos.map(|p| (p.x, p.y, p.z)),
+            Some((-1234.5, 567.25, -160.0))
+        );
         assert!(!image_meta.filename.is_empty());
+        assert!(
+            !image_meta.id.is_empty(),
+            "Uploaded image should be assigned an id"
+        );
 
-        // Use env.data_dir_path for paths within the application's data structure
+        // Uploads go into the object store, named after their content
+        assert!(object_store::is_blob_name(&image_meta.filename));
         let expected_image_path_in_data =
-            env.data_dir_path.join(map_name).join(&image_meta.filename);
+            object_store::image_path(&env.data_dir_path, map_name, &image_meta.filename);
         assert!(
             expected_image_path_in_data.exists(),
             "Uploaded image file does not exist in data directory: {:?}",
             expected_image_path_in_data
         );
-
-        let expected_thumb_dir = env.data_dir_path.join(map_name).join(".thumbnails");
-        assert!(
-            expected_thumb_dir.exists(),
-            ".thumbnails directory was not created: {:?}",
-            expected_thumb_dir
-        );
-
-        // Check if a full-size WebP file was created by the mock
-        let webp_file_path = expected_thumb_dir.join(format!(
-            "{}.webp",
-            Path::new(&image_meta.filename)
-                .file_stem()
-                .unwrap()
-                .to_str()
-                .unwrap()
-        ));
-        assert!(
-            webp_file_path.exists(),
-            "Mock full-size WebP file {:?} does not exist. Mock created paths: {:?}",
-            webp_file_path,
-            env.mock_thumbnail_service
-                .lock()
-                .unwrap()
-                .created_thumbnail_paths
-                .lock()
-                .unwrap()
-        );
     }
 
     #[test]
@@ -446,15 +898,19 @@ mod tests {
             create_dummy_image_file(&source_image_dir, "test_delete_img.png", 1920, 1440);
 
         let upload_result = env.image_service.upload_image(
-            &original_file_path,
+            &[(original_file_path.to_path_buf(), ImageRole::Stand)],
             map_name,
             nade_type,
+            Side::default(),
+            ThrowTechnique::default(),
+            &[],
+            None,
             position_details,
             throw_instructions,
         );
         assert!(
             upload_result.is_ok(),
-            "Setup for delete_image: upload_image failed: {:?}",
+            "Setup for delete_images: upload_image failed: {:?}",
             upload_result.err()
         );
         let image_to_delete_meta = upload_result.unwrap();
@@ -467,75 +923,217 @@ mod tests {
             .expect("Failed to save initial manifest for delete test");
 
         // Verify files exist before deletion
-        let uploaded_image_path_in_data = env
-            .data_dir_path
-            .join(map_name)
-            .join(&image_to_delete_meta.filename);
+        let uploaded_image_path_in_data =
+            object_store::image_path(&env.data_dir_path, map_name, &image_to_delete_meta.filename);
         assert!(
             uploaded_image_path_in_data.exists(),
             "Uploaded image file for deletion {:?} does not exist before delete call",
             uploaded_image_path_in_data
         );
-        let thumb_dir_for_delete = env.data_dir_path.join(map_name).join(".thumbnails");
-        let expected_thumb_path_before_delete = thumb_dir_for_delete.join(format!(
-            "{}.webp",
-            Path::new(&image_to_delete_meta.filename)
-                .file_stem()
-                .unwrap()
-                .to_str()
-                .unwrap()
-        ));
-        assert!(
-            expected_thumb_path_before_delete.exists(),
-            "Expected thumbnail file {:?} does not exist before delete call. Mock created paths: {:?}",
-            expected_thumb_path_before_delete,
-            env.mock_thumbnail_service
-                .lock()
-                .unwrap()
-                .created_thumbnail_paths
-                .lock()
-                .unwrap()
-        );
 
-        // Action: Call delete_image
+        // Action: Call delete_images
         let delete_result = env
             .image_service
-            .delete_image(&image_to_delete_meta, &mut manifest);
+            .delete_images(std::slice::from_ref(&image_to_delete_meta), &mut manifest);
 
         // Assertions
         assert!(
             delete_result.is_ok(),
-            "delete_image failed: {:?}",
+            "delete_images failed: {:?}",
             delete_result.err()
         );
 
+        // Stored images may be shared, so garbage collection removes them later
         assert!(
-            !uploaded_image_path_in_data.exists(),
-            "Original image file {:?} was not deleted",
+            uploaded_image_path_in_data.exists(),
+            "Stored image file {:?} should be left for garbage collection",
             uploaded_image_path_in_data
         );
-        assert!(
-            !expected_thumb_path_before_delete.exists(),
-            "Thumbnail file {:?} was not deleted. Mock created paths after delete: {:?}",
-            expected_thumb_path_before_delete,
-            env.mock_thumbnail_service
-                .lock()
-                .unwrap()
-                .created_thumbnail_paths
-                .lock()
-                .unwrap()
-        );
 
         assert!(manifest.images.get(map_name).map_or(true, |v| {
             !v.iter()
                 .any(|im| im.filename == image_to_delete_meta.filename)
         }));
 
-        let reloaded_manifest: ImageManifest = env.persistence_service.load_manifest();
+        let reloaded_manifest: ImageManifest = env.persistence_service.load_manifest().unwrap();
         assert!(reloaded_manifest.images.get(map_name).map_or(true, |v| {
             !v.iter()
                 .any(|im| im.filename == image_to_delete_meta.filename)
         }));
+        // The map's only lineup is gone, so the map leaves the manifest too
+        assert!(!reloaded_manifest.images.contains_key(map_name));
+        assert!(!reloaded_manifest.maps.contains_key(map_name));
+
+        let stem = Path::new(&image_to_delete_meta.filename)
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        assert!(
+            !thumb_dir.join(format!("{}.webp", stem)).exists(),
+            "Thumbnail should be deleted"
+        );
+    }
+
+    #[test]
+    fn test_undo_delete_restores_legacy_image() {
+        let env = setup_test_environment();
+        let map_name = "de_mirage";
+        let map_dir = env.data_dir_path.join(map_name);
+        fs::create_dir_all(&map_dir).unwrap();
+        fs::write(map_dir.join("window.webp"), b"window").unwrap();
+        let image = ImageMeta {
+            id: "window".to_string(),
+            filename: "window.webp".to_string(),
+            map: map_name.to_string(),
+            ..Default::default()
+        };
+        let mut manifest = ImageManifest::default().clone_and_add(image.clone(), map_name);
+        env.persistence_service.save_manifest(&manifest).unwrap();
+
+        let before = manifest.clone();
+        env.image_service
+            .delete_images(std::slice::from_ref(&image), &mut manifest)
+            .unwrap();
+        assert!(!map_dir.join("window.webp").exists());
+        assert_eq!(env.persistence_service.load_trash().lineups[0].image, image);
+        let operation = Operation::between("delete lineup", &before, &manifest).unwrap();
+
+        env.image_service
+            .apply_operation(&operation.inverse(), &mut manifest)
+            .unwrap();
+        let restored = manifest.find_image("window").unwrap().clone();
+        assert!(object_store::is_blob_name(&restored.filename));
+        assert_eq!(
+            fs::read(object_store::image_path(
+                &env.data_dir_path,
+                map_name,
+                &restored.filename
+            ))
+            .unwrap(),
+            b"window"
+        );
+        assert_eq!(env.persistence_service.load_manifest().unwrap(), manifest);
+        assert!(env.persistence_service.load_trash().lineups.is_empty());
+
+        // Redoing the delete puts it back in the trash
+        env.image_service
+            .apply_operation(&operation, &mut manifest)
+            .unwrap();
+        assert!(manifest.find_image("window").is_none());
+        assert_eq!(env.persistence_service.load_trash().lineups.len(), 1);
+    }
+
+    #[test]
+    fn test_restore_from_trash() {
+        let env = setup_test_environment();
+        let map_name = "de_nuke";
+        let mut manifest = ImageManifest::default();
+        for id in ["outside", "ramp", "secret"] {
+            let source = env.temp_dir.path().join(format!("{}.webp", id));
+            fs::write(&source, id).unwrap();
+            let image = ImageMeta {
+                id: id.to_string(),
+                filename: object_store::copy_into_store(&env.data_dir_path, &source, "webp")
+                    .unwrap(),
+                map: map_name.to_string(),
+                ..Default::default()
+            };
+            manifest = manifest.clone_and_add(image, map_name);
+        }
+        env.persistence_service.save_manifest(&manifest).unwrap();
+
+        let outside = manifest.find_image("outside").unwrap().clone();
+        env.image_service
+            .delete_images(std::slice::from_ref(&outside), &mut manifest)
+            .unwrap();
+        env.image_service
+            .restore_from_trash("outside", &mut manifest)
+            .unwrap();
+
+        // Back at the end of the map's grid, with its image
+        let restored = manifest.find_image("outside").unwrap();
+        assert_eq!(restored.order, 3);
+        assert_eq!(restored.filename, outside.filename);
+        assert_eq!(env.persistence_service.load_manifest().unwrap(), manifest);
+        assert!(env.persistence_service.load_trash().lineups.is_empty());
+
+        match env
+            .image_service
+            .restore_from_trash("outside", &mut manifest)
+        {
+            Err(ImageServiceError::NotFound(_)) => {}
+            other => panic!("Expected NotFound, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_delete_images_trashes_every_lineup() {
+        let env = setup_test_environment();
+        let mut manifest = ImageManifest::default();
+        for id in ["a", "b", "c"] {
+            let source = env.temp_dir.path().join(format!("{}.webp", id));
+            fs::write(&source, id).unwrap();
+            let mut image = lineup_on("de_mirage", id);
+            image.filename =
+                object_store::copy_into_store(&env.data_dir_path, &source, "webp").unwrap();
+            manifest = manifest.clone_and_add(image, "de_mirage");
+        }
+        let mut strat = Strat::new("A execute", "de_mirage");
+        strat.add_lineup("a");
+        strat.add_lineup("c");
+        manifest.strats.push(strat);
+
+        let to_delete = vec![
+            manifest.find_image("a").unwrap().clone(),
+            manifest.find_image("c").unwrap().clone(),
+        ];
+        env.image_service
+            .delete_images(&to_delete, &mut manifest)
+            .unwrap();
+
+        let remaining: Vec<&str> = manifest.images["de_mirage"]
+            .iter()
+            .map(|img| img.id.as_str())
+            .collect();
+        assert_eq!(remaining, vec!["b"]);
+        assert!(manifest.strats[0].steps.is_empty());
+        assert_eq!(env.persistence_service.load_manifest().unwrap(), manifest);
+        let trashed: Vec<String> = env
+            .persistence_service
+            .load_trash()
+            .lineups
+            .into_iter()
+            .map(|entry| entry.image.id)
+            .collect();
+        assert_eq!(trashed, vec!["a".to_string(), "c".to_string()]);
+    }
+
+    #[test]
+    fn test_delete_images_keeps_every_lineup_when_one_fails() {
+        let env = setup_test_environment();
+        let mut manifest = ImageManifest::default();
+        for id in ["a", "b", "c"] {
+            let mut image = lineup_on("de_mirage", id);
+            // "b" points at a legacy file that isn't there, so moving it into the store fails
+            if id != "b" {
+                let source = env.temp_dir.path().join(format!("{}.webp", id));
+                fs::write(&source, id).unwrap();
+                image.filename =
+                    object_store::copy_into_store(&env.data_dir_path, &source, "webp").unwrap();
+            }
+            manifest = manifest.clone_and_add(image, "de_mirage");
+        }
+        env.persistence_service.save_manifest(&manifest).unwrap();
+        let original = manifest.clone();
+
+        let to_delete: Vec<ImageMeta> = manifest.images["de_mirage"].clone();
+        let result = env.image_service.delete_images(&to_delete, &mut manifest);
+
+        assert!(matches!(result, Err(ImageServiceError::Persistence(_))));
+        assert_eq!(manifest, original);
+        assert_eq!(env.persistence_service.load_manifest().unwrap(), original);
+        assert!(env.persistence_service.load_trash().lineups.is_empty());
     }
 
     #[test]
@@ -551,9 +1149,13 @@ mod tests {
             create_dummy_image_file(&source_image_dir, "small_img.png", 100, 100); // Invalid
 
         let result_small = env.image_service.upload_image(
-            &small_image_path,
+            &[(small_image_path.to_path_buf(), ImageRole::Stand)],
             map_name,
             NadeType::Smoke,
+            Side::default(),
+            ThrowTechnique::default(),
+            &[],
+            None,
             "Site B",
             "Too small.",
         );
@@ -581,9 +1183,13 @@ mod tests {
         let large_image_path =
             create_dummy_image_file(&source_image_dir, "large_img.png", 8193, 256); // Invalid, width > MAX_WIDTH
         let result_large = env.image_service.upload_image(
-            &large_image_path,
+            &[(large_image_path.to_path_buf(), ImageRole::Stand)],
             map_name,
             NadeType::Smoke,
+            Side::default(),
+            ThrowTechnique::default(),
+            &[],
+            None,
             "Site C",
             "Too large.",
         );
@@ -632,9 +1238,13 @@ mod tests {
             create_dummy_image_file(&source_image_dir, "thumb_fail_img.png", 1920, 1440);
 
         let result = env.image_service.upload_image(
-            &original_file_path,
+            &[(original_file_path.to_path_buf(), ImageRole::Stand)],
             map_name,
             NadeType::Smoke,
+            Side::default(),
+            ThrowTechnique::default(),
+            &[],
+            None,
             "Position",
             "Instructions",
         );
@@ -720,18 +1330,21 @@ mod tests {
 
         // Create an ImageMeta for an image that won't be in the manifest
         let image_meta_not_in_manifest = ImageMeta {
+            id: "not-in-manifest-id".to_string(),
             filename: image_filename.to_string(),
             map: map_name.to_string(),
             nade_type: NadeType::Molotov,
             position: "A Site".to_string(),
             notes: "Default plant molly".to_string(),
             order: 0,
+            ..Default::default()
         };
 
         let mut manifest = ImageManifest {
             images: std::collections::HashMap::new(),
             maps: std::collections::HashMap::new(),
-            webp_migration_completed: false,
+            strats: Vec::new(),
+            ..Default::default()
         }; // Empty manifest
         // Ensure the map exists in the manifest.maps, but no images for it
         manifest.maps.insert(
@@ -741,9 +1354,12 @@ mod tests {
             },
         );
 
-        // Action: Call delete_image
+        // Action: Call delete_images
         // Current behavior: logs a warning, doesn't remove anything from manifest.images, saves manifest, returns Ok.
-        let result = image_service.delete_image(&image_meta_not_in_manifest, &mut manifest);
+        let result = image_service.delete_images(
+            std::slice::from_ref(&image_meta_not_in_manifest),
+            &mut manifest,
+        );
 
         // Assertions based on current behavior (no error, manifest unchanged regarding this specific image)
         match result {
@@ -756,16 +1372,16 @@ mod tests {
                 );
             }
             Ok(_) => panic!(
-                "delete_image should have failed for a non-existent image, but it succeeded."
+                "delete_images should have failed for a non-existent image, but it succeeded."
             ),
             Err(other_err) => panic!(
-                "delete_image failed with an unexpected error type: {:?}",
+                "delete_images failed with an unexpected error type: {:?}",
                 other_err
             ),
         }
 
         // Verify the manifest on disk does not contain the image (it never did)
-        let reloaded_manifest = env.persistence_service.load_manifest();
+        let reloaded_manifest = env.persistence_service.load_manifest().unwrap();
         assert!(
             reloaded_manifest.images.get(map_name).map_or(true, |v| !v
                 .iter()
@@ -773,7 +1389,7 @@ mod tests {
             "ImageMeta for non-existent image should not be in the manifest."
         );
 
-        // If ImageService::delete_image were changed to return Err for this case:
+        // If ImageService::delete_images were changed to return Err for this case:
         // assert!(result.is_err());
         // match result.err().unwrap() {
         //     ImageServiceError::NotFound(msg) => {
@@ -799,9 +1415,13 @@ mod tests {
             create_dummy_image_file(&source_image_dir, "image_for_delete_test.png", 1920, 1440);
 
         let upload_result = env.image_service.upload_image(
-            &original_upload_path,
+            &[(original_upload_path.to_path_buf(), ImageRole::Stand)],
             map_name,
             NadeType::Smoke,
+            Side::default(),
+            ThrowTechnique::default(),
+            &[],
+            None,
             "A Site",
             "Standard smoke for A site execute.",
         );
@@ -835,12 +1455,12 @@ mod tests {
         // 3. Attempt to delete the image
         let delete_result = env
             .image_service
-            .delete_image(&image_meta_to_delete, &mut manifest);
+            .delete_images(std::slice::from_ref(&image_meta_to_delete), &mut manifest);
 
         // 4. Assertions
         assert!(
             delete_result.is_err(),
-            "Expected delete_image to fail due to thumbnail removal error"
+            "Expected delete_images to fail due to thumbnail removal error"
         );
         match delete_result.err().unwrap() {
             ImageServiceError::ThumbnailDeletion(ThumbnailServiceError::FileRemoval(
@@ -865,10 +1485,8 @@ mod tests {
         }
 
         // 5. Verify original image file still exists (assuming transactional failure or early exit)
-        let expected_image_path_in_data = env
-            .data_dir_path
-            .join(map_name)
-            .join(&image_meta_to_delete.filename);
+        let expected_image_path_in_data =
+            object_store::image_path(&env.data_dir_path, map_name, &image_meta_to_delete.filename);
         assert!(
             expected_image_path_in_data.exists(),
             "Original image file {:?} should still exist after failed thumbnail deletion, but it does not.",
@@ -876,7 +1494,7 @@ mod tests {
         );
 
         // 6. Verify manifest (reloaded from disk) still contains the image
-        let reloaded_manifest: ImageManifest = env.persistence_service.load_manifest();
+        let reloaded_manifest: ImageManifest = env.persistence_service.load_manifest().unwrap();
         let image_still_in_manifest = reloaded_manifest.images.get(map_name).map_or(false, |v| {
             v.iter()
                 .any(|im| im.filename == image_meta_to_delete.filename)
@@ -899,19 +1517,260 @@ mod tests {
             .lock()
             .unwrap()
             .clone();
-        let expected_thumb_filename_stem = Path::new(&image_meta_to_delete.filename)
-            .file_stem()
-            .unwrap()
-            .to_str()
+        assert!(
+            !mock_paths_after_failed_remove.is_empty(),
+            "MockThumbnailService should still track the converted image after a simulated removal failure"
+        );
+    }
+
+    fn lineup_on(map: &str, id: &str) -> ImageMeta {
+        ImageMeta {
+            id: id.to_string(),
+            filename: format!("{}.webp", id),
+            map: map.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_move_images_to_map() {
+        let env = setup_test_environment();
+        let mut manifest = ImageManifest::default();
+        for id in ["a", "b", "c"] {
+            manifest = manifest.clone_and_add(lineup_on("de_mirage", id), "de_mirage");
+        }
+        manifest = manifest.clone_and_add(lineup_on("de_inferno", "x"), "de_inferno");
+        let mut strat = Strat::new("A execute", "de_mirage");
+        strat.add_lineup("a");
+        strat.add_lineup("b");
+        manifest.strats.push(strat);
+
+        let ids = vec!["c".to_string(), "a".to_string()];
+        let moved = env
+            .image_service
+            .move_images_to_map(&mut manifest, &ids, "de_inferno", false)
+            .unwrap();
+        assert_eq!(moved, vec!["a".to_string(), "c".to_string()]);
+        let order_of = |manifest: &ImageManifest, map: &str| -> Vec<(String, usize)> {
+            let mut images: Vec<(String, usize)> = manifest.images[map]
+                .iter()
+                .map(|img| (img.id.clone(), img.order))
+                .collect();
+            images.sort_by_key(|(_, order)| *order);
+            images
+        };
+        assert_eq!(order_of(&manifest, "de_mirage"), vec![("b".to_string(), 0)]);
+        assert_eq!(
+            order_of(&manifest, "de_inferno"),
+            vec![
+                ("x".to_string(), 0),
+                ("a".to_string(), 1),
+                ("c".to_string(), 2)
+            ]
+        );
+        assert_eq!(manifest.find_image("a").unwrap().map, "de_inferno");
+        assert_eq!(
+            manifest.strats[0].steps.len(),
+            1,
+            "Moved lineups leave the strat"
+        );
+        assert!(manifest.maps.contains_key("de_inferno"));
+        assert_eq!(env.persistence_service.load_manifest().unwrap(), manifest);
+
+        // The last lineup leaving a map takes the map with it
+        env.image_service
+            .move_images_to_map(&mut manifest, &["b".to_string()], "de_inferno", false)
             .unwrap();
-        let thumbnail_was_tracked = mock_paths_after_failed_remove
+        assert!(!manifest.images.contains_key("de_mirage"));
+        assert!(!manifest.maps.contains_key("de_mirage"));
+
+        assert!(matches!(
+            env.image_service.move_images_to_map(
+                &mut manifest,
+                &["gone".to_string()],
+                "de_nuke",
+                false
+            ),
+            Err(ImageServiceError::NotFound(_))
+        ));
+        assert!(matches!(
+            env.image_service
+                .move_images_to_map(&mut manifest, &["a".to_string()], " ", false),
+            Err(ImageServiceError::InputError(_))
+        ));
+    }
+
+    #[test]
+    fn test_reorder_with_a_filter_on() {
+        let mut images: Vec<ImageMeta> = ["a", "b", "c", "d", "e"]
             .iter()
-            .any(|p| p.to_string_lossy().contains(expected_thumb_filename_stem));
-        assert!(
-            thumbnail_was_tracked,
-            "MockThumbnailService should still track the thumbnail path ({:?}) after a simulated removal failure, but paths are: {:?}",
-            expected_thumb_filename_stem, mock_paths_after_failed_remove
+            .enumerate()
+            .map(|(order, id)| ImageMeta {
+                order,
+                nade_type: if order % 2 == 0 {
+                    NadeType::Smoke
+                } else {
+                    NadeType::Flash
+                },
+                ..lineup_on("de_mirage", id)
+            })
+            .collect();
+
+        // With only smokes shown, the grid drags its third tile onto its first
+        let visible: Vec<String> = images
+            .iter()
+            .filter(|img| img.nade_type == NadeType::Smoke)
+            .map(|img| img.id.clone())
+            .collect();
+        assert!(ImageService::move_image_to(
+            &mut images,
+            &visible[2],
+            &visible[0]
+        ));
+
+        let order: Vec<(&str, usize)> = images
+            .iter()
+            .map(|img| (img.id.as_str(), img.order))
+            .collect();
+        assert_eq!(
+            order,
+            vec![("e", 0), ("a", 1), ("b", 2), ("c", 3), ("d", 4)]
+        );
+        assert!(!ImageService::move_image_to(&mut images, "a", "gone"));
+    }
+
+    #[test]
+    fn test_bulk_edit_images() {
+        let env = setup_test_environment();
+        let mut manifest = ImageManifest::default();
+        for id in ["a", "b", "c"] {
+            manifest = manifest.clone_and_add(lineup_on("de_mirage", id), "de_mirage");
+        }
+        manifest.images.get_mut("de_mirage").unwrap()[1].tags = vec!["retake".to_string()];
+        let ids = vec!["a".to_string(), "b".to_string(), "gone".to_string()];
+
+        let edit = |manifest: &mut ImageManifest, edit: BulkEdit| {
+            env.image_service.bulk_edit_images(manifest, &ids, &edit)
+        };
+        assert_eq!(
+            edit(&mut manifest, BulkEdit::NadeType(NadeType::Flash)).unwrap(),
+            2
+        );
+        assert_eq!(edit(&mut manifest, BulkEdit::Side(Side::T)).unwrap(), 2);
+        // Only the lineup without the tag changes, and the tag is normalized
+        assert_eq!(
+            edit(&mut manifest, BulkEdit::AddTag(" Retake ".to_string())).unwrap(),
+            1
+        );
+        for id in ["a", "b"] {
+            let image = manifest.find_image(id).unwrap();
+            assert_eq!(image.nade_type, NadeType::Flash);
+            assert_eq!(image.side, Side::T);
+            assert_eq!(image.tags, vec!["retake".to_string()]);
+        }
+        let untouched = manifest.find_image("c").unwrap();
+        assert_eq!(untouched.nade_type, NadeType::default());
+        assert!(untouched.tags.is_empty());
+        assert_eq!(env.persistence_service.load_manifest().unwrap(), manifest);
+
+        assert_eq!(
+            edit(&mut manifest, BulkEdit::RemoveTag("retake".to_string())).unwrap(),
+            2
         );
+        assert!(manifest.find_image("b").unwrap().tags.is_empty());
+        assert!(matches!(
+            edit(&mut manifest, BulkEdit::AddTag("  ".to_string())),
+            Err(ImageServiceError::InputError(_))
+        ));
+    }
+
+    #[test]
+    fn test_copy_image_to_map_keeps_original() {
+        let env = setup_test_environment();
+        let mut manifest =
+            ImageManifest::default().clone_and_add(lineup_on("de_inferno", "x"), "de_inferno");
+
+        let copies = env
+            .image_service
+            .move_images_to_map(&mut manifest, &["x".to_string()], "de_mirage", true)
+            .unwrap();
+        assert_eq!(copies.len(), 1);
+        assert_ne!(copies[0], "x");
+        let copy = manifest.find_image(&copies[0]).unwrap();
+        assert_eq!(copy.map, "de_mirage");
+        assert_eq!(copy.order, 0);
+        assert_eq!(copy.filename, "x.webp", "Copies share the stored image");
+        assert_eq!(manifest.find_image("x").unwrap().map, "de_inferno");
+    }
+
+    #[test]
+    fn test_edit_moves_legacy_image_to_another_map() {
+        let env = setup_test_environment();
+        let map_dir = env.data_dir_path.join("de_mirage");
+        fs::create_dir_all(&map_dir).unwrap();
+        fs::write(map_dir.join("a.webp"), b"jungle").unwrap();
+        let original = lineup_on("de_mirage", "a");
+        let mut manifest = ImageManifest::default().clone_and_add(original.clone(), "de_mirage");
+
+        let mut form_data = EditFormData::from_meta(&original);
+        form_data.map = "de_inferno".to_string();
+        form_data.position = "Jungle".to_string();
+        env.image_service
+            .update_image_metadata(&mut manifest, &original, &form_data)
+            .unwrap();
+
+        // The file moved into the store, so the lineup finds it from its new map
+        let moved = manifest.find_image("a").unwrap();
+        assert_eq!(moved.map, "de_inferno");
+        assert_eq!(moved.position, "Jungle");
+        assert!(object_store::is_blob_name(&moved.filename));
+        assert_eq!(
+            fs::read(object_store::image_path(
+                &env.data_dir_path,
+                &moved.map,
+                &moved.filename
+            ))
+            .unwrap(),
+            b"jungle"
+        );
+        assert!(!map_dir.join("a.webp").exists());
+        assert_eq!(env.persistence_service.load_manifest().unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_edit_with_keep_copy_only_changes_the_copy() {
+        let env = setup_test_environment();
+        let map_dir = env.data_dir_path.join("de_mirage");
+        fs::create_dir_all(&map_dir).unwrap();
+        fs::write(map_dir.join("a.webp"), b"jungle").unwrap();
+        let original = lineup_on("de_mirage", "a");
+        let mut manifest = ImageManifest::default().clone_and_add(original.clone(), "de_mirage");
+
+        // A failed copy leaves the lineup unedited
+        let mut form_data = EditFormData::from_meta(&original);
+        form_data.map = " ".to_string();
+        form_data.keep_copy = true;
+        form_data.position = "Jungle".to_string();
+        let before = manifest.clone();
+        assert!(matches!(
+            env.image_service
+                .update_image_metadata(&mut manifest, &original, &form_data),
+            Err(ImageServiceError::InputError(_))
+        ));
+        assert_eq!(manifest, before);
+
+        form_data.map = "de_inferno".to_string();
+        env.image_service
+            .update_image_metadata(&mut manifest, &original, &form_data)
+            .unwrap();
+
+        let kept = manifest.find_image("a").unwrap();
+        assert_eq!(kept.map, "de_mirage");
+        assert_eq!(kept.position, original.position);
+        let copy = &manifest.images["de_inferno"][0];
+        assert_ne!(copy.id, "a");
+        assert_eq!(copy.position, "Jungle");
+        assert_eq!(env.persistence_service.load_manifest().unwrap(), manifest);
     }
 
     #[test]
@@ -927,12 +1786,14 @@ mod tests {
         let original_filename = "non_existent_for_update.png".to_string();
 
         let image_meta_not_in_manifest = ImageMeta {
+            id: "not-in-manifest-id".to_string(),
             filename: original_filename.clone(),
             map: map_name.clone(),
             nade_type: NadeType::Smoke,
             position: "Original Position".to_string(),
             notes: "Original Notes".to_string(),
             order: 0,
+            ..Default::default()
         };
 
         let mut manifest = ImageManifest::default();
@@ -940,10 +1801,19 @@ mod tests {
         manifest.images.insert(map_name.clone(), Vec::new());
 
         let form_data = EditFormData {
+            id: image_meta_not_in_manifest.id.clone(),
             filename: original_filename.clone(),
+            map: map_name.clone(),
+            keep_copy: false,
             nade_type: NadeType::Molotov,
+            side: Side::Either,
+            technique: ThrowTechnique::default(),
+            tags: Vec::new(),
             position: "Updated Position".to_string(),
             notes: "Updated Notes".to_string(),
+            throw_pos: None,
+            landing_pos: None,
+            view_angles: None,
         };
 
         let result = image_service.update_image_metadata(
@@ -984,9 +1854,16 @@ mod tests {
             create_dummy_image_file(&source_image_dir, "image_to_be_updated.png", 800, 600);
 
         let upload_result = image_service.upload_image(
-            &original_file_path_for_upload,
+            &[(
+                original_file_path_for_upload.to_path_buf(),
+                ImageRole::Stand,
+            )],
             map_name,
             original_nade_type,
+            Side::default(),
+            ThrowTechnique::default(),
+            &[],
+            None,
             original_position,
             original_notes,
         );
@@ -998,7 +1875,7 @@ mod tests {
         let image_to_update_meta = upload_result.unwrap();
 
         // 2. Load the manifest that was saved by upload_image (via persistence_service)
-        let mut manifest: ImageManifest = persistence_service.load_manifest();
+        let mut manifest: ImageManifest = persistence_service.load_manifest().unwrap();
         assert!(
             manifest.images.get(map_name).map_or(false, |v| v
                 .iter()
@@ -1010,12 +1887,38 @@ mod tests {
         let updated_nade_type = NadeType::Molotov;
         let updated_position = "Updated Position A";
         let updated_notes = "These are the updated notes for the image.";
+        let updated_technique = ThrowTechnique {
+            movement: Movement::Jump,
+            click: ThrowClick::Both,
+            jump_throw_bind: true,
+        };
+        let updated_throw_pos = WorldPos {
+            x: -1200.0,
+            y: 640.25,
+            z: -160.0,
+        };
 
         let form_data = EditFormData {
-            filename: image_to_update_meta.filename.clone(), // Filename must match
+            id: image_to_update_meta.id.clone(), // Id must match
+            filename: image_to_update_meta.filename.clone(),
+            map: map_name.to_string(),
+            keep_copy: false,
             nade_type: updated_nade_type,
+            side: Side::CT,
+            technique: updated_technique,
+            tags: vec![
+                " Retake ".to_string(),
+                "one-way".to_string(),
+                "retake".to_string(),
+            ],
             position: updated_position.to_string(),
             notes: updated_notes.to_string(),
+            throw_pos: Some(updated_throw_pos),
+            landing_pos: None,
+            view_angles: Some(ViewAngles {
+                pitch: -12.5,
+                yaw: 135.0,
+            }),
         };
 
         // 4. Call update_image_metadata
@@ -1044,14 +1947,27 @@ mod tests {
         );
         let meta_mem = updated_meta_in_memory.unwrap();
         assert_eq!(meta_mem.nade_type, updated_nade_type);
+        assert_eq!(meta_mem.side, Side::CT);
+        assert_eq!(meta_mem.technique, updated_technique);
+        assert_eq!(
+            meta_mem.tags,
+            vec!["retake".to_string(), "one-way".to_string()],
+            "Tags should be normalized and de-duplicated"
+        );
         assert_eq!(meta_mem.position, updated_position);
         assert_eq!(meta_mem.notes, updated_notes);
+        assert_eq!(meta_mem.throw_pos, Some(updated_throw_pos));
+        assert_eq!(meta_mem.landing_pos, None);
+        assert_eq!(
+            meta_mem.setpos().map(|s| s.command()),
+            Some("setpos -1200 640.25 -160;setang -12.5 135 0".to_string())
+        );
         assert_eq!(meta_mem.map, map_name); // Should not change
         assert_eq!(meta_mem.filename, image_to_update_meta.filename); // Should not change
 
         // 5b. Verify the manifest on disk is updated
         // ImageService::update_image_metadata calls self.persistence_service.save_manifest(&manifest)
-        let reloaded_manifest: ImageManifest = persistence_service.load_manifest();
+        let reloaded_manifest: ImageManifest = persistence_service.load_manifest().unwrap();
         let updated_meta_on_disk = reloaded_manifest.images.get(map_name).and_then(|v| {
             v.iter()
                 .find(|im| im.filename == image_to_update_meta.filename)
@@ -1063,8 +1979,11 @@ mod tests {
         );
         let meta_disk = updated_meta_on_disk.unwrap();
         assert_eq!(meta_disk.nade_type, updated_nade_type);
+        assert_eq!(meta_disk.side, Side::CT);
+        assert_eq!(meta_disk.technique, updated_technique);
         assert_eq!(meta_disk.position, updated_position);
         assert_eq!(meta_disk.notes, updated_notes);
+        assert_eq!(meta_disk.throw_pos, Some(updated_throw_pos));
     }
 
     #[test]
@@ -1105,12 +2024,14 @@ mod tests {
         let map_name = "de_mirage_single";
 
         let image1_meta = ImageMeta {
+            id: "image1-id".to_string(),
             filename: "image_a.png".to_string(),
             map: map_name.to_string(),
             nade_type: NadeType::Smoke,
             position: "A Site".to_string(),
             notes: "Notes for A".to_string(),
             order: 0,
+            ..Default::default()
         };
 
         let mut manifest = ImageManifest::default();
@@ -1130,28 +2051,34 @@ mod tests {
         let map_name = "de_overpass_multiple";
 
         let image_c_meta = ImageMeta {
+            id: "image_c-id".to_string(),
             filename: "image_c.png".to_string(),
             map: map_name.to_string(),
             nade_type: NadeType::Flash,
             position: "Long".to_string(),
             notes: "Notes for C".to_string(),
             order: 0,
+            ..Default::default()
         };
         let image_a_meta = ImageMeta {
+            id: "image_a-id".to_string(),
             filename: "image_a.png".to_string(),
             map: map_name.to_string(),
             nade_type: NadeType::Smoke,
             position: "Connector".to_string(),
             notes: "Notes for A".to_string(),
             order: 1,
+            ..Default::default()
         };
         let image_b_meta = ImageMeta {
+            id: "image_b-id".to_string(),
             filename: "image_b.png".to_string(),
             map: map_name.to_string(),
             nade_type: NadeType::Molotov,
             position: "Monster".to_string(),
             notes: "Notes for B".to_string(),
             order: 2,
+            ..Default::default()
         };
 
         let mut manifest = ImageManifest::default();
@@ -1171,4 +2098,241 @@ mod tests {
         assert_eq!(result[1].filename, "image_a.png");
         assert_eq!(result[2].filename, "image_b.png");
     }
+
+    #[test]
+    fn test_rename_tag_renames_and_merges_across_maps() {
+        let env = setup_test_environment();
+        let image_service = env.image_service;
+
+        let tagged = |id: &str, map: &str, tags: &[&str]| ImageMeta {
+            id: id.to_string(),
+            filename: format!("{}.webp", id),
+            map: map.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut manifest = ImageManifest::default();
+        manifest.images.insert(
+            "de_mirage".to_string(),
+            vec![
+                tagged("a", "de_mirage", &["retake", "one-way"]),
+                tagged("b", "de_mirage", &["oneway"]),
+            ],
+        );
+        manifest.images.insert(
+            "de_inferno".to_string(),
+            vec![tagged("c", "de_inferno", &["oneway", "one-way"])],
+        );
+
+        // Merge "oneway" into the existing "one-way" tag
+        let changed = image_service
+            .rename_tag(&mut manifest, "oneway", " One-Way ")
+            .expect("rename_tag failed");
+        assert_eq!(changed, 2);
+        assert_eq!(manifest.find_image("b").unwrap().tags, vec!["one-way"]);
+        assert_eq!(
+            manifest.find_image("c").unwrap().tags,
+            vec!["one-way"],
+            "Merged tag should not be duplicated"
+        );
+
+        let counts = manifest.tag_counts();
+        assert_eq!(counts.get("one-way"), Some(&3));
+        assert!(!counts.contains_key("oneway"));
+
+        // The change is persisted
+        let reloaded = env.persistence_service.load_manifest().unwrap();
+        assert_eq!(reloaded.tag_counts(), counts);
+
+        // Blank names are rejected
+        assert!(matches!(
+            image_service.rename_tag(&mut manifest, "retake", "   "),
+            Err(ImageServiceError::InputError(_))
+        ));
+    }
+
+    #[test]
+    fn test_save_and_delete_strat() {
+        let env = setup_test_environment();
+        let image_service = env.image_service;
+
+        let lineup = |id: &str, map: &str| ImageMeta {
+            id: id.to_string(),
+            filename: format!("{}.webp", id),
+            map: map.to_string(),
+            ..Default::default()
+        };
+        let mut manifest = ImageManifest::default();
+        manifest.images.insert(
+            "de_mirage".to_string(),
+            (1..=6)
+                .map(|n| lineup(&format!("m{}", n), "de_mirage"))
+                .collect(),
+        );
+        manifest
+            .images
+            .insert("de_inferno".to_string(), vec![lineup("i1", "de_inferno")]);
+
+        // Lineups are handed out to players 1-5 in turn, each as the next throw
+        let mut strat = Strat::new("  A execute ", "de_mirage");
+        for n in 1..=6 {
+            assert!(strat.add_lineup(&format!("m{}", n)));
+        }
+        assert!(!strat.add_lineup("m1"), "A lineup is only added once");
+        let players: Vec<u8> = strat.steps.iter().map(|step| step.player).collect();
+        assert_eq!(players, vec![1, 2, 3, 4, 5, 1]);
+
+        // Steps with the same timing are grouped, sorted by player
+        strat.steps[0].order = 2;
+        strat.steps[1].order = 2;
+        let timeline: Vec<(u32, Vec<&str>)> = strat
+            .timeline()
+            .into_iter()
+            .map(|(order, steps)| {
+                let ids = steps.iter().map(|step| step.image_id.as_str()).collect();
+                (order, ids)
+            })
+            .collect();
+        assert_eq!(timeline[0], (2, vec!["m1", "m2"]));
+        assert_eq!(timeline[1], (3, vec!["m3"]));
+        assert_eq!(timeline.len(), 5);
+
+        image_service
+            .save_strat(&mut manifest, strat.clone())
+            .expect("save_strat failed");
+        assert_eq!(manifest.strats.len(), 1);
+        assert_eq!(manifest.strats[0].name, "A execute");
+
+        // Saving again replaces the stored strat
+        strat.name = "A split".to_string();
+        image_service
+            .save_strat(&mut manifest, strat.clone())
+            .expect("save_strat failed");
+        assert_eq!(manifest.strats_for_map("de_mirage").len(), 1);
+        assert_eq!(manifest.find_strat(&strat.id).unwrap().name, "A split");
+        assert!(manifest.strats_for_map("de_inferno").is_empty());
+
+        // Invalid strats are rejected
+        let mut invalid = strat.clone();
+        invalid.name = " ".to_string();
+        assert!(matches!(
+            image_service.save_strat(&mut manifest, invalid),
+            Err(ImageServiceError::InputError(_))
+        ));
+        let mut invalid = strat.clone();
+        invalid.steps[0].player = 6;
+        assert!(matches!(
+            image_service.save_strat(&mut manifest, invalid),
+            Err(ImageServiceError::InputError(_))
+        ));
+        let mut invalid = strat.clone();
+        invalid.add_lineup("i1");
+        assert!(matches!(
+            image_service.save_strat(&mut manifest, invalid),
+            Err(ImageServiceError::NotFound(_))
+        ));
+
+        // Strats are persisted in the manifest
+        let reloaded = env.persistence_service.load_manifest().unwrap();
+        assert_eq!(reloaded.strats, manifest.strats);
+
+        // Deleting a lineup drops it from the strat
+        assert!(manifest.remove_image_from_strats("m3"));
+        assert_eq!(manifest.strats[0].steps.len(), 5);
+        assert!(!manifest.remove_image_from_strats("m3"));
+
+        image_service
+            .delete_strat(&mut manifest, &strat.id)
+            .expect("delete_strat failed");
+        assert!(manifest.strats.is_empty());
+        assert!(matches!(
+            image_service.delete_strat(&mut manifest, &strat.id),
+            Err(ImageServiceError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_setpos_parses_getpos_output() {
+        // Console echo and getpos_exact variants are accepted
+        let exact = Setpos::parse(
+            "] getpos_exact\nsetpos_exact 1296.250000 -1123.500000 -167.968750;setang_exact 2.244000 -179.792007 0.000000",
+        )
+        .expect("Failed to parse getpos_exact output");
+        assert_eq!(
+            (exact.pos.x, exact.pos.y, exact.pos.z),
+            (1296.25, -1123.5, -167.96875)
+        );
+        let angles = exact.angles.expect("Angles should be parsed");
+        assert_eq!((angles.pitch, angles.yaw), (2.244, -179.792));
+
+        // Angles are optional, the position is not
+        let position_only = Setpos::parse("  setpos 10 20 30  ").expect("Failed to parse setpos");
+        assert_eq!(position_only.angles, None);
+        assert_eq!(position_only.command(), "setpos 10 20 30");
+        assert_eq!(Setpos::parse("setang 1 2 0"), None);
+        assert_eq!(Setpos::parse("setpos 10 twenty 30"), None);
+        assert_eq!(Setpos::parse("hello"), None);
+    }
+
+    #[test]
+    fn test_search_images_ranks_matches_across_maps() {
+        let env = setup_test_environment();
+        let image_service = env.image_service;
+
+        let lineup = |id: &str, map: &str, position: &str, notes: &str, tags: &[&str]| ImageMeta {
+            id: id.to_string(),
+            filename: format!("{}.webp", id),
+            map: map.to_string(),
+            position: position.to_string(),
+            notes: notes.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut manifest = ImageManifest::default();
+        manifest.images.insert(
+            "de_mirage".to_string(),
+            vec![
+                lineup("jungle", "de_mirage", "Jungle smoke", "From T spawn", &[]),
+                lineup(
+                    "window",
+                    "de_mirage",
+                    "Window",
+                    "Aim at the jungle roof",
+                    &[],
+                ),
+                lineup("ct", "de_mirage", "CT smoke", "", &["retake"]),
+            ],
+        );
+        manifest.images.insert(
+            "de_inferno".to_string(),
+            vec![lineup("coffins", "de_inferno", "Coffins", "", &["retake"])],
+        );
+
+        // A position hit ranks above a notes hit
+        let ids = |results: Vec<ImageMeta>| -> Vec<String> {
+            results.into_iter().map(|m| m.id).collect()
+        };
+        assert_eq!(
+            ids(image_service.search_images(&manifest, "jungle")),
+            vec!["jungle", "window"]
+        );
+
+        // Fuzzy and case-insensitive, across every map
+        assert_eq!(
+            ids(image_service.search_images(&manifest, "RTK")),
+            vec!["coffins", "ct"]
+        );
+        assert_eq!(
+            ids(image_service.search_images(&manifest, "infrn")),
+            vec!["coffins"]
+        );
+
+        // Every term has to match somewhere
+        assert_eq!(
+            ids(image_service.search_images(&manifest, "retake mirage")),
+            vec!["ct"]
+        );
+        assert!(image_service.search_images(&manifest, "xyzzy").is_empty());
+        assert!(image_service.search_images(&manifest, "   ").is_empty());
+    }
 } // Closes `mod tests`
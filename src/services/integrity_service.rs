@@ -0,0 +1,737 @@
+// src/services/integrity_service.rs
+use crate::object_store;
+use crate::persistence::{ImageManifest, ImageMeta, MapMeta, new_image_id};
+use crate::services::persistence_service::{PersistenceService, PersistenceServiceError};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// File extensions treated as lineup images when looking for orphans
+const IMAGE_EXTENSIONS: &[&str] = &["webp", "png", "jpg", "jpeg"];
+
+/// Position given to orphaned images adopted as lineups
+pub const ADOPTED_POSITION: &str = "Untitled";
+
+#[derive(Debug)]
+pub enum IntegrityServiceError {
+    IoError(std::io::Error),
+    Persistence(PersistenceServiceError),
+    NotFound(String),      // The lineup an issue refers to is gone
+    InvalidRepair(String), // The repair doesn't apply to the issue
+}
+
+impl std::fmt::Display for IntegrityServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegrityServiceError::IoError(err) => write!(f, "I/O error: {}", err),
+            IntegrityServiceError::Persistence(err) => write!(f, "{}", err),
+            IntegrityServiceError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            IntegrityServiceError::InvalidRepair(msg) => write!(f, "Invalid repair: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for IntegrityServiceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IntegrityServiceError::IoError(err) => Some(err),
+            IntegrityServiceError::Persistence(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for IntegrityServiceError {
+    fn from(err: std::io::Error) -> Self {
+        IntegrityServiceError::IoError(err)
+    }
+}
+
+impl From<PersistenceServiceError> for IntegrityServiceError {
+    fn from(err: PersistenceServiceError) -> Self {
+        IntegrityServiceError::Persistence(err)
+    }
+}
+
+/// A mismatch between the manifest and the data directory, found by `IntegrityService::check`
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntegrityIssue {
+    /// A lineup step whose image file isn't on disk
+    MissingFile {
+        image_id: String,
+        map: String,
+        filename: String,
+    },
+    /// A lineup step whose image file can't be decoded
+    UndecodableImage {
+        image_id: String,
+        map: String,
+        filename: String,
+        error: String,
+    },
+    /// An image in a map directory that no lineup refers to
+    OrphanFile { map: String, filename: String },
+    /// A temporary file left behind by a failed upload, in a map directory or the store
+    LeftoverTempFile { path: PathBuf },
+    /// A file in a `.thumbnails` directory whose image is gone
+    StaleThumbnail { path: PathBuf },
+    /// A map whose lineups have duplicate or gapped `order` values
+    BadOrder { map: String },
+}
+
+/// How to fix an `IntegrityIssue`. Missing and undecodable images can be re-linked or
+/// deleted, orphans adopted or deleted, leftovers deleted, and bad orders renumbered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepairAction {
+    Relink(PathBuf), // Point the lineup step at this image instead
+    Adopt,           // Add the orphaned image as an untitled lineup
+    Delete,          // Remove the file, or the lineup step without a usable file
+    Renumber,        // Give the map's lineups the order values 0, 1, 2, ...
+}
+
+impl IntegrityIssue {
+    /// One line describing the issue for the check window
+    pub fn describe(&self, manifest: &ImageManifest) -> String {
+        let position = |image_id: &str| {
+            manifest
+                .find_image(image_id)
+                .map_or("[unknown lineup]", |meta| meta.position.as_str())
+                .to_string()
+        };
+        match self {
+            IntegrityIssue::MissingFile {
+                image_id,
+                map,
+                filename,
+            } => format!(
+                "'{}' ({}): {} is missing",
+                position(image_id),
+                map,
+                filename
+            ),
+            IntegrityIssue::UndecodableImage {
+                image_id,
+                map,
+                filename,
+                error,
+            } => format!(
+                "'{}' ({}): {} can't be read: {}",
+                position(image_id),
+                map,
+                filename,
+                error
+            ),
+            IntegrityIssue::OrphanFile { map, filename } => {
+                format!("{}/{} isn't part of any lineup", map, filename)
+            }
+            IntegrityIssue::LeftoverTempFile { path } => {
+                format!("Leftover from a failed upload: {}", path.display())
+            }
+            IntegrityIssue::StaleThumbnail { path } => {
+                format!("Thumbnail of a removed image: {}", path.display())
+            }
+            IntegrityIssue::BadOrder { map } => {
+                format!("{}: lineups have duplicate or missing order numbers", map)
+            }
+        }
+    }
+}
+
+/// Cross-checks the manifest against the data directory and repairs what drifted apart
+pub struct IntegrityService {
+    persistence_service: Arc<PersistenceService>,
+}
+
+impl IntegrityService {
+    pub fn new(persistence_service: Arc<PersistenceService>) -> Self {
+        Self {
+            persistence_service,
+        }
+    }
+
+    /// Every issue in the library, manifest entries first, then files on disk.
+    ///
+    /// Every image the manifest refers to is decoded, so this can take a while on large
+    /// libraries; run it off the UI thread.
+    pub fn check(&self) -> Result<Vec<IntegrityIssue>, IntegrityServiceError> {
+        let data_dir = self.persistence_service.get_data_dir();
+        let manifest = self.persistence_service.load_manifest()?;
+        let mut issues = Vec::new();
+
+        let mut map_names: Vec<&String> = manifest.images.keys().collect();
+        map_names.sort();
+        for map in &map_names {
+            let images = &manifest.images[*map];
+            for meta in images {
+                for step in meta.steps() {
+                    let path = object_store::image_path(&data_dir, map, &step.filename);
+                    if !path.is_file() {
+                        issues.push(IntegrityIssue::MissingFile {
+                            image_id: meta.id.clone(),
+                            map: map.to_string(),
+                            filename: step.filename.clone(),
+                        });
+                    } else if let Err(e) = image::open(&path) {
+                        issues.push(IntegrityIssue::UndecodableImage {
+                            image_id: meta.id.clone(),
+                            map: map.to_string(),
+                            filename: step.filename.clone(),
+                            error: e.to_string(),
+                        });
+                    }
+                }
+            }
+            if !has_sequential_order(images) {
+                issues.push(IntegrityIssue::BadOrder {
+                    map: map.to_string(),
+                });
+            }
+        }
+
+        // Every other visible directory in the data dir holds a map's images, listed or not
+        let mut map_dirs: Vec<(String, PathBuf)> = fs::read_dir(&data_dir)?
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                (!name.starts_with('.') && name != object_store::OBJECTS_DIR)
+                    .then(|| (name, entry.path()))
+            })
+            .collect();
+        map_dirs.sort();
+        for (map, map_dir) in map_dirs {
+            let referenced: HashSet<String> = manifest
+                .images
+                .get(&map)
+                .into_iter()
+                .flatten()
+                .flat_map(ImageMeta::steps)
+                .map(|step| step.filename)
+                .collect();
+            issues.extend(Self::check_map_dir(&map, &map_dir, &referenced)?);
+        }
+
+        // Images are written to a temporary file in the store before they're stored
+        let objects_dir = data_dir.join(object_store::OBJECTS_DIR);
+        if objects_dir.is_dir() {
+            let mut leftovers: Vec<PathBuf> = fs::read_dir(&objects_dir)?
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && file_name(path).starts_with(".tmp"))
+                .collect();
+            leftovers.sort();
+            issues.extend(
+                leftovers
+                    .into_iter()
+                    .map(|path| IntegrityIssue::LeftoverTempFile { path }),
+            );
+        }
+        Ok(issues)
+    }
+
+    /// Orphans and leftovers in one map directory and its `.thumbnails`
+    fn check_map_dir(
+        map: &str,
+        map_dir: &Path,
+        referenced: &HashSet<String>,
+    ) -> Result<Vec<IntegrityIssue>, IntegrityServiceError> {
+        let mut issues = Vec::new();
+        let mut filenames: Vec<String> = fs::read_dir(map_dir)?
+            .flatten()
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        filenames.sort();
+        for filename in filenames {
+            if filename.starts_with("tmp_for_conversion.") {
+                issues.push(IntegrityIssue::LeftoverTempFile {
+                    path: map_dir.join(&filename),
+                });
+            } else if is_image_file(&filename) && !referenced.contains(&filename) {
+                issues.push(IntegrityIssue::OrphanFile {
+                    map: map.to_string(),
+                    filename,
+                });
+            }
+        }
+
+        // Thumbnails are named after their image's stem, older ones with a `_<size>` suffix
+        let thumb_dir = map_dir.join(".thumbnails");
+        if thumb_dir.is_dir() {
+            let stems: HashSet<String> = referenced
+                .iter()
+                .map(|filename| file_stem(filename).to_string())
+                .collect();
+            let mut thumbnails: Vec<PathBuf> = fs::read_dir(&thumb_dir)?
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .collect();
+            thumbnails.sort();
+            for path in thumbnails {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let unsized_stem = stem
+                    .rsplit_once('_')
+                    .filter(|(_, size)| {
+                        !size.is_empty() && size.bytes().all(|b| b.is_ascii_digit())
+                    })
+                    .map_or(stem.as_ref(), |(base, _)| base);
+                if !stems.contains(stem.as_ref()) && !stems.contains(unsized_stem) {
+                    issues.push(IntegrityIssue::StaleThumbnail { path });
+                }
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Apply `action` to `issue` and return the manifest as saved afterwards.
+    ///
+    /// `RepairAction::Relink` takes an image anywhere on disk and copies it into the
+    /// object store. Relinking or adopting an image from a map's directory moves it there.
+    pub fn repair(
+        &self,
+        issue: &IntegrityIssue,
+        action: &RepairAction,
+    ) -> Result<ImageManifest, IntegrityServiceError> {
+        let data_dir = self.persistence_service.get_data_dir();
+        let mut manifest = self.persistence_service.load_manifest()?;
+
+        match (issue, action) {
+            (
+                IntegrityIssue::MissingFile {
+                    image_id,
+                    map,
+                    filename,
+                }
+                | IntegrityIssue::UndecodableImage {
+                    image_id,
+                    map,
+                    filename,
+                    ..
+                },
+                RepairAction::Relink(source),
+            ) => {
+                let new_filename = Self::store(&data_dir, map, source)?;
+                let meta = find_image_mut(&mut manifest, map, image_id)?;
+                if meta.filename == *filename {
+                    meta.filename = new_filename;
+                } else if let Some(step) = meta
+                    .extra_images
+                    .iter_mut()
+                    .find(|step| step.filename == *filename)
+                {
+                    step.filename = new_filename;
+                } else {
+                    return Err(IntegrityServiceError::NotFound(format!(
+                        "{} is no longer part of the lineup",
+                        filename
+                    )));
+                }
+            }
+            (
+                IntegrityIssue::MissingFile {
+                    image_id,
+                    map,
+                    filename,
+                }
+                | IntegrityIssue::UndecodableImage {
+                    image_id,
+                    map,
+                    filename,
+                    ..
+                },
+                RepairAction::Delete,
+            ) => {
+                let meta = find_image_mut(&mut manifest, map, image_id)?;
+                if meta.filename == *filename && meta.extra_images.is_empty() {
+                    // Without a step to take over, the lineup is deleted like any other
+                    let lineup = meta.clone();
+                    return self.trash_lineup(manifest, lineup);
+                }
+                if meta.filename == *filename {
+                    // The next step takes over as the lineup's first image
+                    let next = meta.extra_images.remove(0);
+                    meta.filename = next.filename;
+                    meta.role = next.role;
+                } else {
+                    meta.extra_images.retain(|step| step.filename != *filename);
+                }
+                // A stored image may be shared, so it's left for garbage collection
+                let path = data_dir.join(map).join(filename);
+                if !object_store::is_blob_name(filename) && path.exists() {
+                    fs::remove_file(&path)?;
+                }
+            }
+            (IntegrityIssue::OrphanFile { map, filename }, RepairAction::Adopt) => {
+                let stored = Self::store(&data_dir, map, &data_dir.join(map).join(filename))?;
+                let adopted = ImageMeta {
+                    id: new_image_id(),
+                    filename: stored,
+                    map: map.clone(),
+                    position: ADOPTED_POSITION.to_string(),
+                    ..Default::default()
+                };
+                manifest = manifest.clone_and_add(adopted, map);
+                manifest.maps.entry(map.clone()).or_insert_with(|| MapMeta {
+                    last_accessed: SystemTime::now(),
+                });
+            }
+            (IntegrityIssue::OrphanFile { map, filename }, RepairAction::Delete) => {
+                fs::remove_file(data_dir.join(map).join(filename))?;
+                return Ok(manifest);
+            }
+            (
+                IntegrityIssue::LeftoverTempFile { path } | IntegrityIssue::StaleThumbnail { path },
+                RepairAction::Delete,
+            ) => {
+                fs::remove_file(path)?;
+                return Ok(manifest);
+            }
+            (IntegrityIssue::BadOrder { map }, RepairAction::Renumber) => {
+                if let Some(images) = manifest.images.get_mut(map) {
+                    // Keep the current order as far as it goes; ties stay in manifest order
+                    images.sort_by_key(|img| img.order);
+                    for (index, image) in images.iter_mut().enumerate() {
+                        image.order = index;
+                    }
+                }
+            }
+            _ => {
+                return Err(IntegrityServiceError::InvalidRepair(format!(
+                    "{:?} doesn't fix {:?}",
+                    action, issue
+                )));
+            }
+        }
+
+        self.persistence_service.save_manifest(&manifest)?;
+        Ok(manifest)
+    }
+
+    /// Move a lineup to the trash and save the manifest without it, the way
+    /// `ImageService::delete_images` does. Its strat steps go with it, and so does its map
+    /// if it was the last lineup there. An image file still in the map's directory moves
+    /// into the store, so restoring the lineup brings it back as it was.
+    fn trash_lineup(
+        &self,
+        mut manifest: ImageManifest,
+        lineup: ImageMeta,
+    ) -> Result<ImageManifest, IntegrityServiceError> {
+        let data_dir = self.persistence_service.get_data_dir();
+        manifest.remove_image(&lineup);
+        let trash_before = self.persistence_service.load_trash();
+        let mut trash = trash_before.clone();
+        trash.add(lineup.clone(), SystemTime::now());
+        self.persistence_service.save_trash(&trash)?;
+        if let Err(e) = self.persistence_service.save_manifest(&manifest) {
+            if let Err(trash_err) = self.persistence_service.save_trash(&trash_before) {
+                log::error!(
+                    "Failed to take the lineup back out of the trash: {}",
+                    trash_err
+                );
+            }
+            return Err(e.into());
+        }
+
+        // The lineup is gone already; a file that can't be moved shows up as an orphan
+        for step in lineup.steps() {
+            if !object_store::is_blob_name(&step.filename)
+                && data_dir.join(&lineup.map).join(&step.filename).exists()
+                && let Err(e) =
+                    object_store::stash_legacy_image(&data_dir, &lineup.map, &step.filename)
+            {
+                log::warn!("Failed to move {} into the store: {}", step.filename, e);
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Copy `source` into the object store and return its blob name. A file from the map's
+    /// directory is removed once stored, so it doesn't show up as an orphan again.
+    fn store(data_dir: &Path, map: &str, source: &Path) -> Result<String, IntegrityServiceError> {
+        let extension = source.extension().map_or_else(
+            || "webp".to_string(),
+            |ext| ext.to_string_lossy().into_owned(),
+        );
+        let blob = object_store::copy_into_store(data_dir, source, &extension)?;
+        if source.parent() == Some(data_dir.join(map).as_path()) {
+            fs::remove_file(source)?;
+        }
+        Ok(blob)
+    }
+}
+
+/// Whether the lineups' `order` values are exactly 0, 1, 2, ... in some arrangement
+fn has_sequential_order(images: &[ImageMeta]) -> bool {
+    let mut orders: Vec<usize> = images.iter().map(|img| img.order).collect();
+    orders.sort_unstable();
+    orders
+        .iter()
+        .enumerate()
+        .all(|(index, &order)| index == order)
+}
+
+fn find_image_mut<'a>(
+    manifest: &'a mut ImageManifest,
+    map: &str,
+    image_id: &str,
+) -> Result<&'a mut ImageMeta, IntegrityServiceError> {
+    manifest
+        .images
+        .get_mut(map)
+        .and_then(|images| images.iter_mut().find(|img| img.id == image_id))
+        .ok_or_else(|| IntegrityServiceError::NotFound(format!("lineup {}", image_id)))
+}
+
+fn is_image_file(filename: &str) -> bool {
+    Path::new(filename).extension().is_some_and(|ext| {
+        IMAGE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str())
+    })
+}
+
+fn file_stem(filename: &str) -> &str {
+    Path::new(filename)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(filename)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{ImageRole, LineupImage, Strat};
+    use crate::tests_common::{create_dummy_image_file, setup_persistence_test_env};
+
+    fn lineup(id: &str, filename: &str, order: usize) -> ImageMeta {
+        ImageMeta {
+            id: id.to_string(),
+            filename: filename.to_string(),
+            map: "de_mirage".to_string(),
+            position: id.to_string(),
+            order,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_reports_every_kind_of_drift() {
+        let env = setup_persistence_test_env();
+        let data_dir = env.data_dir_path.clone();
+        let map_dir = data_dir.join("de_mirage");
+        fs::create_dir_all(map_dir.join(".thumbnails")).unwrap();
+
+        create_dummy_image_file(&map_dir, "window.png", 4, 4);
+        create_dummy_image_file(&map_dir, "orphan.png", 4, 4);
+        fs::write(map_dir.join("broken.png"), b"not an image").unwrap();
+        fs::write(map_dir.join("tmp_for_conversion.png"), b"").unwrap();
+        fs::write(map_dir.join("notes.txt"), b"ignored").unwrap();
+        fs::write(map_dir.join(".thumbnails").join("window_200.webp"), b"").unwrap();
+        fs::write(map_dir.join(".thumbnails").join("gone.webp"), b"").unwrap();
+        // Hidden directories aren't maps
+        fs::create_dir_all(data_dir.join(".radars")).unwrap();
+        fs::write(data_dir.join(".radars").join("de_mirage.png"), b"").unwrap();
+        // Neither is the object store, where unused images are left for garbage collection
+        let objects_dir = data_dir.join(object_store::OBJECTS_DIR);
+        fs::create_dir_all(objects_dir.join("ab")).unwrap();
+        fs::write(
+            objects_dir
+                .join("ab")
+                .join(format!("{}.png", "c".repeat(62))),
+            b"",
+        )
+        .unwrap();
+        fs::write(objects_dir.join(".tmpA1b2C3.webp"), b"").unwrap();
+
+        let mut window = lineup("window", "window.png", 0);
+        window.extra_images.push(LineupImage {
+            filename: "aim.png".to_string(),
+            role: ImageRole::Aim,
+        });
+        let mut manifest = ImageManifest::default();
+        manifest.images.insert(
+            "de_mirage".to_string(),
+            vec![window, lineup("broken", "broken.png", 0)],
+        );
+        env.persistence_service.save_manifest(&manifest).unwrap();
+
+        let service = IntegrityService::new(Arc::clone(&env.persistence_service));
+        let issues = service.check().unwrap();
+        assert_eq!(
+            issues,
+            vec![
+                IntegrityIssue::MissingFile {
+                    image_id: "window".to_string(),
+                    map: "de_mirage".to_string(),
+                    filename: "aim.png".to_string(),
+                },
+                IntegrityIssue::UndecodableImage {
+                    image_id: "broken".to_string(),
+                    map: "de_mirage".to_string(),
+                    filename: "broken.png".to_string(),
+                    error: match &issues[1] {
+                        IntegrityIssue::UndecodableImage { error, .. } => error.clone(),
+                        other => panic!("Expected an undecodable image, got {:?}", other),
+                    },
+                },
+                IntegrityIssue::BadOrder {
+                    map: "de_mirage".to_string(),
+                },
+                IntegrityIssue::OrphanFile {
+                    map: "de_mirage".to_string(),
+                    filename: "orphan.png".to_string(),
+                },
+                IntegrityIssue::LeftoverTempFile {
+                    path: map_dir.join("tmp_for_conversion.png"),
+                },
+                IntegrityIssue::StaleThumbnail {
+                    path: map_dir.join(".thumbnails").join("gone.webp"),
+                },
+                IntegrityIssue::LeftoverTempFile {
+                    path: objects_dir.join(".tmpA1b2C3.webp"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repairs() {
+        let env = setup_persistence_test_env();
+        let data_dir = env.data_dir_path.clone();
+        let map_dir = data_dir.join("de_mirage");
+        fs::create_dir_all(&map_dir).unwrap();
+        create_dummy_image_file(&map_dir, "window.png", 4, 4);
+        create_dummy_image_file(&map_dir, "orphan.png", 4, 4);
+        let outside = tempfile::tempdir().unwrap();
+        let replacement = create_dummy_image_file(outside.path(), "window.png", 4, 4);
+
+        let mut stairs = lineup("stairs", "stairs.png", 3);
+        stairs.extra_images.push(LineupImage {
+            filename: "window.png".to_string(),
+            role: ImageRole::Landing,
+        });
+        let mut manifest = ImageManifest::default();
+        manifest.images.insert(
+            "de_mirage".to_string(),
+            vec![lineup("jungle", "jungle.png", 3), stairs],
+        );
+        env.persistence_service.save_manifest(&manifest).unwrap();
+        let service = IntegrityService::new(Arc::clone(&env.persistence_service));
+
+        // Relinking to an image outside the library copies it into the store
+        let missing_jungle = IntegrityIssue::MissingFile {
+            image_id: "jungle".to_string(),
+            map: "de_mirage".to_string(),
+            filename: "jungle.png".to_string(),
+        };
+        let repaired = service
+            .repair(&missing_jungle, &RepairAction::Relink(replacement))
+            .unwrap();
+        let jungle = repaired.find_image("jungle").unwrap();
+        assert!(object_store::is_blob_name(&jungle.filename));
+        assert!(
+            object_store::image_path(&env.data_dir_path, "de_mirage", &jungle.filename).is_file()
+        );
+
+        // Deleting a missing first step promotes the next one
+        let missing_stairs = IntegrityIssue::MissingFile {
+            image_id: "stairs".to_string(),
+            map: "de_mirage".to_string(),
+            filename: "stairs.png".to_string(),
+        };
+        let repaired = service
+            .repair(&missing_stairs, &RepairAction::Delete)
+            .unwrap();
+        let stairs = repaired.find_image("stairs").unwrap();
+        assert_eq!(stairs.filename, "window.png");
+        assert_eq!(stairs.role, ImageRole::Landing);
+        assert!(stairs.extra_images.is_empty());
+
+        let orphan = IntegrityIssue::OrphanFile {
+            map: "de_mirage".to_string(),
+            filename: "orphan.png".to_string(),
+        };
+        // Adopting moves the orphan into the store
+        let repaired = service.repair(&orphan, &RepairAction::Adopt).unwrap();
+        let adopted = repaired.images["de_mirage"]
+            .iter()
+            .find(|img| img.position == ADOPTED_POSITION)
+            .unwrap();
+        assert!(object_store::is_blob_name(&adopted.filename));
+        assert!(!map_dir.join("orphan.png").exists());
+        let adopted_id = adopted.id.clone();
+
+        let bad_order = IntegrityIssue::BadOrder {
+            map: "de_mirage".to_string(),
+        };
+        assert!(matches!(
+            service.repair(&bad_order, &RepairAction::Delete),
+            Err(IntegrityServiceError::InvalidRepair(_))
+        ));
+        let repaired = service.repair(&bad_order, &RepairAction::Renumber).unwrap();
+        // The adopted lineup was added with order 2, before the two lineups sharing 3
+        let order: Vec<(&str, usize)> = repaired.images["de_mirage"]
+            .iter()
+            .map(|img| (img.id.as_str(), img.order))
+            .collect();
+        assert_eq!(
+            order,
+            vec![(adopted_id.as_str(), 0), ("jungle", 1), ("stairs", 2)]
+        );
+
+        assert_eq!(service.check().unwrap(), Vec::new());
+        assert_eq!(env.persistence_service.load_manifest().unwrap(), repaired);
+    }
+
+    #[test]
+    fn test_deleting_a_broken_lineup_moves_it_to_the_trash() {
+        let env = setup_persistence_test_env();
+        let map_dir = env.data_dir_path.join("de_nuke");
+        fs::create_dir_all(&map_dir).unwrap();
+        fs::write(map_dir.join("broken.png"), b"not an image").unwrap();
+
+        let mut broken = lineup("broken", "broken.png", 0);
+        broken.map = "de_nuke".to_string();
+        let mut strat = Strat::new("Outside", "de_nuke");
+        strat.add_lineup("broken");
+        let mut manifest = ImageManifest::default();
+        manifest = manifest.clone_and_add(broken, "de_nuke");
+        manifest.maps.insert(
+            "de_nuke".to_string(),
+            MapMeta {
+                last_accessed: SystemTime::now(),
+            },
+        );
+        manifest.strats.push(strat);
+        env.persistence_service.save_manifest(&manifest).unwrap();
+        let service = IntegrityService::new(Arc::clone(&env.persistence_service));
+
+        let issue = IntegrityIssue::UndecodableImage {
+            image_id: "broken".to_string(),
+            map: "de_nuke".to_string(),
+            filename: "broken.png".to_string(),
+            error: String::new(),
+        };
+        let repaired = service.repair(&issue, &RepairAction::Delete).unwrap();
+
+        // The lineup leaves its strat, and its map leaves with it
+        assert!(repaired.find_image("broken").is_none());
+        assert!(repaired.strats[0].steps.is_empty());
+        assert!(!repaired.images.contains_key("de_nuke"));
+        assert!(!repaired.maps.contains_key("de_nuke"));
+        assert_eq!(env.persistence_service.load_manifest().unwrap(), repaired);
+        // It can be restored from the trash, with its file kept in the store
+        let trash = env.persistence_service.load_trash();
+        assert_eq!(trash.lineups.len(), 1);
+        assert_eq!(trash.lineups[0].image.id, "broken");
+        assert!(!map_dir.join("broken.png").exists());
+    }
+}
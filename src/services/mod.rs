@@ -2,7 +2,9 @@
 
 pub mod export_service;
 pub mod image_service;
+pub mod integrity_service;
 pub mod persistence_service;
+pub mod practice_config_service;
 pub mod thumbnail_service;
 pub mod updater;
 
@@ -1,16 +1,23 @@
 // src/services/persistence_service.rs
 use std::fs; // For future use, e.g. create_dir_all
 use std::io; // For io::Error
+use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 // For Path type hint, though join works with PathBuf
-use crate::persistence::{ImageManifest, ImageMeta, MapMeta, NadeType}; // To return ImageManifest and use its components
+use crate::persistence::{
+    ImageManifest, ImageMeta, ImageRole, LineupImage, MapConfig, MapMeta, NadeType, Setpos, Side,
+    Strat, ThrowTechnique, Trash, new_image_id,
+}; // To return ImageManifest and use its components
 use chrono::Utc;
 use serde_json;
 use std::time::SystemTime; // For deserialization // For timestamp in copy_image_to_data
 // crate::thumbnail is no longer needed for these, but might be for generate_all_thumbnails later
 // For now, let's remove it and add back if necessary. We will need image ops though.
+use crate::migrations;
+use crate::object_store::{self, GarbageReport};
 use crate::services::thumbnail_service::{ThumbnailServiceError, ThumbnailServiceTrait}; // Added for thumbnail generation call and error type
+use std::collections::HashSet;
 use std::sync::{Arc, Mutex}; // Added for Arc and Mutex
 // image::imageops::FilterType and image::ImageFormat are no longer needed here as thumbnail generation moved
 
@@ -19,6 +26,7 @@ pub enum PersistenceServiceError {
     IoError(std::io::Error),
     InvalidInput(String),
     SerializationError(String),
+    CorruptManifest(String), // manifest.json exists but isn't a valid manifest
     ThumbnailGenerationFailed(ThumbnailServiceError),
     ThumbnailDeletionFailed(ThumbnailServiceError), // New variant, expects ThumbnailServiceError
 }
@@ -31,6 +39,9 @@ impl std::fmt::Display for PersistenceServiceError {
             PersistenceServiceError::SerializationError(msg) => {
                 write!(f, "Serialization error: {}", msg)
             }
+            PersistenceServiceError::CorruptManifest(msg) => {
+                write!(f, "manifest.json is corrupt: {}", msg)
+            }
             PersistenceServiceError::ThumbnailGenerationFailed(err) => {
                 write!(f, "Thumbnail generation failed: {}", err)
             }
@@ -58,9 +69,56 @@ impl From<std::io::Error> for PersistenceServiceError {
     }
 }
 
+/// How many earlier generations of `manifest.json` are kept as `manifest.json.bak.N`
+pub const MANIFEST_BACKUPS: usize = 5;
+
+/// Folder of the trash bin, inside the data directory
+pub const TRASH_DIR: &str = ".trash";
+
+/// An earlier generation of the manifest, kept by `save_manifest`
+#[derive(Debug, Clone)]
+pub struct ManifestBackup {
+    pub generation: usize, // 1 is the most recent
+    pub saved_at: Option<SystemTime>,
+    pub lineup_count: Option<usize>, // `None` if the backup can't be read
+}
+
+/// What can replace a manifest that failed to load, for the user to choose from
+#[derive(Debug, Clone)]
+pub struct ManifestRecovery {
+    pub error: String,                // Why the manifest failed to load
+    pub quarantined: Option<PathBuf>, // Where the damaged file was moved
+    pub backup: Option<(ManifestBackup, ImageManifest)>, // The newest readable backup
+    pub salvaged: ImageManifest,      // The entries that still parse in the damaged file
+}
+
+/// Which manifest to continue with after a failed load
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ManifestRecoveryChoice {
+    Backup,
+    Salvaged,
+    Empty,
+}
+
+impl ManifestRecovery {
+    /// The manifest for `choice`, or an empty one if that option isn't available
+    pub fn manifest(&self, choice: ManifestRecoveryChoice) -> ImageManifest {
+        match choice {
+            ManifestRecoveryChoice::Backup => self
+                .backup
+                .as_ref()
+                .map(|(_, manifest)| manifest.clone())
+                .unwrap_or_default(),
+            ManifestRecoveryChoice::Salvaged => self.salvaged.clone(),
+            ManifestRecoveryChoice::Empty => ImageManifest::default(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PersistenceService {
     data_dir: PathBuf,
+    save_lock: Mutex<()>, // Saves from different threads take turns
 }
 
 impl PersistenceService {
@@ -69,7 +127,10 @@ impl PersistenceService {
         if !data_dir.exists() {
             fs::create_dir_all(&data_dir)?;
         }
-        Ok(Self { data_dir })
+        Ok(Self {
+            data_dir,
+            save_lock: Mutex::new(()),
+        })
     }
 
     // Get the data directory path
@@ -77,31 +138,75 @@ impl PersistenceService {
         self.data_dir.clone()
     }
 
-    // Methods for load_manifest, save_manifest, copy_image_to_storage, etc., will be added here.
-    pub fn load_manifest(&self) -> ImageManifest {
+    /// Load `manifest.json`, or an empty manifest if there is none yet.
+    ///
+    /// A manifest that can't be read or parsed is an error rather than an empty library,
+    /// so the next save can't overwrite it; see `recover_manifest`. A manifest from an
+    /// older version is migrated and saved, after keeping the original as
+    /// `manifest.json.pre-migration-v<schema>`.
+    pub fn load_manifest(&self) -> Result<ImageManifest, PersistenceServiceError> {
         let manifest_path = self.data_dir.join("manifest.json");
-        if manifest_path.exists() {
-            match fs::read_to_string(&manifest_path) {
-                Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
-                    eprintln!(
-                        "Failed to parse manifest.json: {}. Returning default manifest.",
-                        e
-                    );
-                    ImageManifest::default()
-                }),
-                Err(e) => {
-                    eprintln!(
-                        "Failed to read manifest.json: {}. Returning default manifest.",
-                        e
-                    );
-                    ImageManifest::default()
-                }
+        if !manifest_path.exists() {
+            return Ok(ImageManifest::default());
+        }
+        let json = fs::read_to_string(&manifest_path)?;
+        let mut manifest: ImageManifest = serde_json::from_str(&json)
+            .map_err(|e| PersistenceServiceError::CorruptManifest(e.to_string()))?;
+
+        let schema_version = manifest.schema_version;
+        if migrations::migrate(&mut manifest, &self.data_dir) {
+            // If the backup or the save fails, the migration runs again on the next load
+            let backup_path = self
+                .data_dir
+                .join(format!("manifest.json.pre-migration-v{}", schema_version));
+            if let Err(e) = fs::write(&backup_path, &json)
+                .map_err(PersistenceServiceError::from)
+                .and_then(|()| self.save_manifest(&manifest))
+            {
+                log::error!("Failed to save the migrated manifest: {}", e);
             }
-        } else {
-            ImageManifest::default()
         }
+        Ok(manifest)
     }
 
+    /// Collect everything that can replace a manifest that failed to load with `error`.
+    ///
+    /// The damaged file is moved aside to `manifest.json.corrupt-<timestamp>` first, so
+    /// whichever option the user picks, the original stays on disk. The newest readable
+    /// backup and whatever lineups and strats still parse in the damaged file are offered.
+    pub fn recover_manifest(&self, error: &PersistenceServiceError) -> ManifestRecovery {
+        let manifest_path = self.data_dir.join("manifest.json");
+        let salvaged = fs::read(&manifest_path)
+            .map(|bytes| salvage_manifest(&String::from_utf8_lossy(&bytes)))
+            .unwrap_or_default();
+
+        let quarantine_path = self.data_dir.join(format!(
+            "manifest.json.corrupt-{}",
+            Utc::now().format("%Y%m%d%H%M%S%3f")
+        ));
+        let quarantined = match fs::rename(&manifest_path, &quarantine_path) {
+            Ok(()) => Some(quarantine_path),
+            Err(e) => {
+                log::error!("Failed to move the corrupt manifest aside: {}", e);
+                None
+            }
+        };
+
+        let backup = self.list_manifest_backups().into_iter().find_map(|backup| {
+            let manifest = self.read_manifest_backup(backup.generation).ok()?;
+            Some((backup, manifest))
+        });
+        ManifestRecovery {
+            error: error.to_string(),
+            quarantined,
+            backup,
+            salvaged,
+        }
+    }
+
+    /// Save the manifest without ever leaving a half-written `manifest.json`: it's written
+    /// to a temporary file, synced to disk, and renamed into place. The previous
+    /// generations are kept as `manifest.json.bak.1` (newest) to `.bak.MANIFEST_BACKUPS`.
     pub fn save_manifest(&self, manifest: &ImageManifest) -> Result<(), PersistenceServiceError> {
         let manifest_path = self.data_dir.join("manifest.json");
         let json = serde_json::to_string_pretty(manifest).map_err(|e| {
@@ -110,7 +215,243 @@ impl PersistenceService {
                 e
             ))
         })?;
-        Ok(fs::write(manifest_path, json)?)
+
+        // A poisoned lock only means another save panicked; the files are still consistent
+        let _guard = self
+            .save_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // An unchanged manifest would only push a distinct generation out of the backups
+        if fs::read(&manifest_path).is_ok_and(|current| current == json.as_bytes()) {
+            return Ok(());
+        }
+        let mut temp_file = tempfile::NamedTempFile::new_in(&self.data_dir)?;
+        temp_file.write_all(json.as_bytes())?;
+        temp_file.as_file().sync_all()?;
+
+        if manifest_path.exists() {
+            self.rotate_manifest_backups()?;
+            fs::copy(&manifest_path, self.manifest_backup_path(1))?;
+        }
+        temp_file
+            .persist(&manifest_path)
+            .map_err(|e| PersistenceServiceError::IoError(e.error))?;
+        Ok(())
+    }
+
+    fn manifest_backup_path(&self, generation: usize) -> PathBuf {
+        self.data_dir
+            .join(format!("manifest.json.bak.{}", generation))
+    }
+
+    /// Shift every backup one generation back, dropping the oldest
+    fn rotate_manifest_backups(&self) -> io::Result<()> {
+        for generation in (1..MANIFEST_BACKUPS).rev() {
+            let path = self.manifest_backup_path(generation);
+            if path.exists() {
+                fs::rename(&path, self.manifest_backup_path(generation + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The manifest backups on disk, newest first
+    pub fn list_manifest_backups(&self) -> Vec<ManifestBackup> {
+        (1..=MANIFEST_BACKUPS)
+            .filter_map(|generation| {
+                let path = self.manifest_backup_path(generation);
+                let metadata = fs::metadata(&path).ok()?;
+                let lineup_count = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|json| serde_json::from_str::<ImageManifest>(&json).ok())
+                    .map(|manifest| manifest.images.values().map(Vec::len).sum());
+                Some(ManifestBackup {
+                    generation,
+                    saved_at: metadata.modified().ok(),
+                    lineup_count,
+                })
+            })
+            .collect()
+    }
+
+    /// Make backup `generation` the current manifest and return it. The manifest it
+    /// replaces becomes the newest backup, so a restore can itself be undone.
+    pub fn restore_manifest_backup(
+        &self,
+        generation: usize,
+    ) -> Result<ImageManifest, PersistenceServiceError> {
+        let mut manifest = self.read_manifest_backup(generation)?;
+        // Only now that it's restored may its migration move files
+        migrations::migrate(&mut manifest, &self.data_dir);
+        self.save_manifest(&manifest)?;
+        Ok(manifest)
+    }
+
+    fn read_manifest_backup(
+        &self,
+        generation: usize,
+    ) -> Result<ImageManifest, PersistenceServiceError> {
+        let json = fs::read_to_string(self.manifest_backup_path(generation))?;
+        let mut manifest: ImageManifest = serde_json::from_str(&json).map_err(|e| {
+            PersistenceServiceError::SerializationError(format!(
+                "Backup {} is not a valid manifest: {}",
+                generation, e
+            ))
+        })?;
+        // Backups are listed and offered without being restored, so nothing moves yet
+        migrations::migrate_in_memory(&mut manifest);
+        Ok(manifest)
+    }
+
+    /// Remove the stored images no manifest refers to any more. Backups and pre-migration
+    /// copies count as references, so every backup can still be restored.
+    ///
+    /// Only run it while nothing is being stored: an image is stored before the manifest
+    /// that refers to it is saved. The app runs it at startup.
+    pub fn collect_garbage(&self) -> Result<GarbageReport, PersistenceServiceError> {
+        let mut roots = vec![self.load_manifest()?];
+        let saved_copies = fs::read_dir(&self.data_dir)?.flatten().filter(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.starts_with("manifest.json.bak.")
+                || name.starts_with("manifest.json.pre-migration-")
+        });
+        for entry in saved_copies {
+            // Read as saved; migrating would move files around
+            match fs::read_to_string(entry.path())
+                .map_err(|e| e.to_string())
+                .and_then(|json| {
+                    serde_json::from_str::<ImageManifest>(&json).map_err(|e| e.to_string())
+                }) {
+                Ok(manifest) => roots.push(manifest),
+                // A copy that doesn't parse can't be restored either
+                Err(e) => log::warn!("Skipping {}: {}", entry.path().display(), e),
+            }
+        }
+        // Deleted lineups can still be restored from the trash
+        roots.push(self.load_trash().as_manifest());
+        let report = object_store::collect_garbage(&self.data_dir, &roots)?;
+        if report.removed > 0 {
+            log::info!(
+                "Removed {} unused images ({} bytes)",
+                report.removed,
+                report.freed_bytes
+            );
+        }
+        Ok(report)
+    }
+
+    /// Load the user's map list from `maps.json`, or the default list if there is none yet.
+    pub fn load_map_config(&self) -> MapConfig {
+        let config_path = self.data_dir.join("maps.json");
+        if !config_path.exists() {
+            return MapConfig::default();
+        }
+        match fs::read_to_string(&config_path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::error!("Failed to parse maps.json: {}. Using default map list.", e);
+                MapConfig::default()
+            }),
+            Err(e) => {
+                log::error!("Failed to read maps.json: {}. Using default map list.", e);
+                MapConfig::default()
+            }
+        }
+    }
+
+    pub fn save_map_config(&self, config: &MapConfig) -> Result<(), PersistenceServiceError> {
+        let config_path = self.data_dir.join("maps.json");
+        let json = serde_json::to_string_pretty(config).map_err(|e| {
+            PersistenceServiceError::SerializationError(format!(
+                "Failed to serialize map list: {}",
+                e
+            ))
+        })?;
+        Ok(fs::write(config_path, json)?)
+    }
+
+    /// Load the trash bin from `.trash/trash.json`, or an empty one if there is none yet.
+    pub fn load_trash(&self) -> Trash {
+        let trash_path = self.data_dir.join(TRASH_DIR).join("trash.json");
+        if !trash_path.exists() {
+            return Trash::default();
+        }
+        match fs::read_to_string(&trash_path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::error!(
+                    "Failed to parse trash.json: {}. Starting with an empty trash.",
+                    e
+                );
+                Trash::default()
+            }),
+            Err(e) => {
+                log::error!(
+                    "Failed to read trash.json: {}. Starting with an empty trash.",
+                    e
+                );
+                Trash::default()
+            }
+        }
+    }
+
+    /// Save the trash bin. Like the manifest, it's written to a temporary file first.
+    pub fn save_trash(&self, trash: &Trash) -> Result<(), PersistenceServiceError> {
+        let trash_dir = self.data_dir.join(TRASH_DIR);
+        fs::create_dir_all(&trash_dir)?;
+        let json = serde_json::to_string_pretty(trash).map_err(|e| {
+            PersistenceServiceError::SerializationError(format!("Failed to serialize trash: {}", e))
+        })?;
+        let mut temp_file = tempfile::NamedTempFile::new_in(&trash_dir)?;
+        temp_file.write_all(json.as_bytes())?;
+        temp_file.as_file().sync_all()?;
+        temp_file
+            .persist(trash_dir.join("trash.json"))
+            .map_err(|e| PersistenceServiceError::IoError(e.error))?;
+        Ok(())
+    }
+
+    /// Full path of a radar image stored with `store_radar_image`.
+    pub fn radar_image_path(&self, filename: &str) -> PathBuf {
+        self.data_dir.join(".radars").join(filename)
+    }
+
+    /// Copy a radar image for `map` into the `.radars` directory, replacing any previous one.
+    /// Returns the stored filename, to be kept in the map's `RadarOverview`.
+    pub fn store_radar_image(
+        &self,
+        src: &Path,
+        map: &str,
+    ) -> Result<String, PersistenceServiceError> {
+        if map.is_empty() {
+            return Err(PersistenceServiceError::InvalidInput(
+                "Map name cannot be empty.".to_string(),
+            ));
+        }
+        // Make sure the file is an image we can display before keeping it
+        image::open(src).map_err(|e| {
+            PersistenceServiceError::InvalidInput(format!(
+                "'{}' is not a supported image: {}",
+                src.display(),
+                e
+            ))
+        })?;
+        let extension = src
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_else(|| "png".to_string());
+
+        let radar_dir = self.data_dir.join(".radars");
+        fs::create_dir_all(&radar_dir)?;
+        // Drop older radars for this map, which may have a different extension
+        for entry in fs::read_dir(&radar_dir)?.flatten() {
+            let path = entry.path();
+            if path.file_stem().is_some_and(|stem| stem == map) {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        let filename = format!("{}.{}", map, extension);
+        fs::copy(src, radar_dir.join(&filename))?;
+        Ok(filename)
     }
 
     fn ensure_map_dir(&self, map: &str) -> Result<PathBuf, PersistenceServiceError> {
@@ -119,112 +460,118 @@ impl PersistenceService {
         Ok(map_dir)
     }
 
-    pub fn copy_image_to_data(
+    /// Convert `src` to WebP and add it to the object store. Returns the stored path and
+    /// its blob name; the manifest is not touched. An image that's already stored isn't
+    /// stored twice.
+    fn store_image_file(
         &self,
         src: &Path,
         map: &str,
-        nade_type: NadeType,
-        notes: String,
-        position: String,
         thumbnail_service: &Arc<Mutex<dyn ThumbnailServiceTrait>>,
     ) -> Result<(PathBuf, String), PersistenceServiceError> {
-        if map.trim().is_empty() {
+        self.ensure_map_dir(map)?;
+        if src.file_name().is_none() {
             return Err(PersistenceServiceError::InvalidInput(
-                "Map name cannot be empty.".to_string(),
+                "Invalid source path".to_string(),
             ));
         }
-        let map_dir = self.ensure_map_dir(map)?;
-
-        // Temporarily copy the original image to use for WebP conversion
-        // Preserve the file extension to ensure image format can be determined
-        let original_extension = src
-            .extension()
-            .map(|ext| ext.to_string_lossy().into_owned())
-            .unwrap_or_else(|| "tmp".to_string());
-        let tmp_path = map_dir.join(format!("tmp_for_conversion.{}", original_extension));
-        fs::copy(src, &tmp_path).map_err(|e| {
-            log::error!("Failed to copy source image to temp location: {}", e);
-            PersistenceServiceError::IoError(e)
-        })?;
+        // Fail with the source's own error rather than the converter's
+        fs::metadata(src)?;
 
-        let original_filename = src.file_name().ok_or_else(|| {
-            PersistenceServiceError::InvalidInput("Invalid source path".to_string())
-        })?;
-        let stem = Path::new(original_filename)
-            .file_stem()
-            .ok_or_else(|| {
-                PersistenceServiceError::InvalidInput("Could not extract file stem".to_string())
-            })?
-            .to_string_lossy();
-
-        // Generate a unique filename with timestamp and .webp extension
-        let timestamp = Utc::now().format("%Y%m%d%H%M%S%3f").to_string();
-        let unique_webp_filename = format!("{}_{}_{}.webp", stem, timestamp, map.replace(" ", "_"));
-
-        // Direct path for the WebP file in the main map directory, not in .thumbnails
-        let webp_dest_path = map_dir.join(&unique_webp_filename);
+        // Convert next to the store, so the result can be renamed into it
+        let webp_file = object_store::temp_file(&self.data_dir, "webp")?;
 
         // Acquire lock on thumbnail service with better error handling
         let thumbnail_service_locked = match thumbnail_service.lock() {
             Ok(service) => service,
             Err(e) => {
                 log::error!("Failed to acquire lock on thumbnail service: {}", e);
-                // Clean up the temporary image since we can't proceed
-                if let Err(remove_err) = fs::remove_file(&tmp_path) {
-                    log::error!(
-                        "Additionally, failed to cleanup temporary file {} after lock error: {}",
-                        tmp_path.display(),
-                        remove_err
-                    );
-                }
                 return Err(PersistenceServiceError::InvalidInput(format!(
                     "Internal error: Failed to access thumbnail service: {}",
                     e
                 )));
             }
         };
+        if let Err(e) = thumbnail_service_locked.convert_to_webp_at_path(src, webp_file.path()) {
+            log::error!("Failed to convert image to WebP: {:?}", e);
+            return Err(PersistenceServiceError::ThumbnailGenerationFailed(e));
+        }
+        drop(thumbnail_service_locked);
 
-        // Convert directly to WebP in the main directory instead of .thumbnails
-        let webp_result =
-            thumbnail_service_locked.convert_to_webp_at_path(&tmp_path, &webp_dest_path);
+        let blob = object_store::store_temp_file(&self.data_dir, webp_file, "webp")?;
+        let webp_dest_path = object_store::image_path(&self.data_dir, map, &blob);
+        log::info!("WebP image stored at: {}", webp_dest_path.display());
+        Ok((webp_dest_path, blob))
+    }
 
-        // Clean up temporary file regardless of conversion result
-        if let Err(remove_err) = fs::remove_file(&tmp_path) {
-            log::error!("Failed to clean up temporary file: {}", remove_err);
-            // Continue with the conversion result, don't fail just because cleanup failed
+    /// Store every image of a new lineup and add it to the manifest.
+    /// The first source becomes the lineup's `filename`; the rest become `extra_images`, in order.
+    #[allow(clippy::too_many_arguments)]
+    pub fn copy_image_to_data(
+        &self,
+        sources: &[(PathBuf, ImageRole)],
+        map: &str,
+        nade_type: NadeType,
+        side: Side,
+        technique: ThrowTechnique,
+        tags: Vec<String>,
+        setpos: Option<Setpos>,
+        notes: String,
+        position: String,
+        thumbnail_service: &Arc<Mutex<dyn ThumbnailServiceTrait>>,
+    ) -> Result<(PathBuf, ImageMeta), PersistenceServiceError> {
+        if map.trim().is_empty() {
+            return Err(PersistenceServiceError::InvalidInput(
+                "Map name cannot be empty.".to_string(),
+            ));
+        }
+        if sources.is_empty() {
+            return Err(PersistenceServiceError::InvalidInput(
+                "A lineup needs at least one image.".to_string(),
+            ));
         }
 
-        // Check WebP conversion result
-        match webp_result {
-            Ok(_) => {
-                log::info!(
-                    "WebP image successfully created at: {}",
-                    webp_dest_path.display()
-                );
-            }
-            Err(e) => {
-                log::error!("Failed to convert image to WebP: {:?}", e);
-                return Err(PersistenceServiceError::ThumbnailGenerationFailed(e));
-            }
+        let mut stored: Vec<(PathBuf, LineupImage)> = Vec::with_capacity(sources.len());
+        for (src, role) in sources {
+            // On failure, the steps stored so far are unreferenced, so garbage collection
+            // removes them
+            let (path, filename) = self.store_image_file(src, map, thumbnail_service)?;
+            stored.push((
+                path,
+                LineupImage {
+                    filename,
+                    role: *role,
+                },
+            ));
         }
+        let (webp_dest_path, first) = stored.remove(0);
 
-        // Successfully created WebP image, now update manifest
-        let mut manifest = self.load_manifest();
+        // Successfully created the WebP images, now update manifest
+        let mut manifest = self.load_manifest()?;
 
         let image_meta = ImageMeta {
-            filename: unique_webp_filename.clone(), // Use the WebP filename in the manifest
+            id: new_image_id(),
+            filename: first.filename, // Use the WebP filename in the manifest
             map: map.to_string(),
             nade_type,
             notes,
             position,
-            order: 0, // Will be updated when added to manifest
+            side,
+            technique,
+            role: first.role,
+            extra_images: stored.into_iter().map(|(_, image)| image).collect(),
+            tags,
+            throw_pos: setpos.map(|s| s.pos),
+            view_angles: setpos.and_then(|s| s.angles),
+            landing_pos: None, // Set later from the edit modal
+            order: 0,          // Will be updated when added to manifest
         };
 
         manifest
             .images
             .entry(map.to_string())
             .or_default()
-            .push(image_meta);
+            .push(image_meta.clone());
 
         // Update map metadata, specifically last_accessed time
         manifest
@@ -237,83 +584,139 @@ impl PersistenceService {
 
         self.save_manifest(&manifest)?;
 
-        Ok((webp_dest_path, unique_webp_filename))
+        Ok((webp_dest_path, image_meta))
     }
 
-    pub fn delete_image_and_thumbnails(
+    /// Delete the thumbnails of every step of a lineup and move its image files from before
+    /// the object store into it. Leaves the manifest alone.
+    pub fn remove_image_files(
         &self,
-        map_name: &str,
-        image_filename: &str,
+        image_meta: &ImageMeta,
         thumbnail_service: &Arc<Mutex<dyn ThumbnailServiceTrait>>,
     ) -> Result<(), PersistenceServiceError> {
-        let image_path_in_data_dir = self.data_dir.join(map_name).join(image_filename);
-
-        // 1. Delegate thumbnail deletion (cache and disk) to ThumbnailService
-        //    This needs the base data_dir, map_name, and the original image's filename.
-        // Attempt to delete thumbnails from disk and clear from cache via ThumbnailService
-        // Errors here are logged by ThumbnailService but do not stop main image deletion.
-        thumbnail_service
-            .lock()
-            .unwrap()
-            .remove_thumbnails_for_image(image_filename, map_name, &self.data_dir)
-            .map_err(|io_err| {
-                // io_err is std::io::Error
-                log::error!(
-                    "PersistenceService: Failed to remove thumbnails for image {} in map {}: {}",
-                    image_filename,
-                    map_name,
-                    &io_err // Log by reference as io_err will be moved
-                );
-                PersistenceServiceError::ThumbnailDeletionFailed(io_err) // Use the correct variant
-            })?;
-
-        // 2. Delete main image file
-        //    If this fails, we return the error immediately.
-        fs::remove_file(&image_path_in_data_dir).map_err(|e| {
-            log::error!(
-                "PersistenceService: Failed to delete main image file {}: {}",
-                image_path_in_data_dir.display(),
-                e
-            );
-            e
-        })?;
-        log::info!(
-            "PersistenceService: Deleted main image file: {}",
-            image_path_in_data_dir.display()
-        );
-
-        // Now, update the manifest to remove the image entry
-        let mut manifest = self.load_manifest();
-        let mut map_became_empty = false;
+        let map_name = image_meta.map.as_str();
+
+        for step in image_meta.steps() {
+            let step_filename = step.filename.as_str();
+            let image_path_in_data_dir = self.data_dir.join(map_name).join(step_filename);
+
+            // 1. Delegate thumbnail deletion (cache and disk) to ThumbnailService
+            //    This needs the base data_dir, map_name, and the original image's filename.
+            // Attempt to delete thumbnails from disk and clear from cache via ThumbnailService
+            // Errors here are logged by ThumbnailService but do not stop main image deletion.
+            thumbnail_service
+                .lock()
+                .unwrap()
+                .remove_thumbnails_for_image(step_filename, map_name, &self.data_dir)
+                .map_err(|io_err| {
+                    // io_err is std::io::Error
+                    log::error!(
+                        "PersistenceService: Failed to remove thumbnails for image {} in map {}: {}",
+                        step_filename,
+                        map_name,
+                        &io_err // Log by reference as io_err will be moved
+                    );
+                    PersistenceServiceError::ThumbnailDeletionFailed(io_err) // Use the correct variant
+                })?;
 
-        if let Some(images_in_map) = manifest.images.get_mut(map_name) {
-            let original_len = images_in_map.len();
-            images_in_map.retain(|img| img.filename != image_filename);
-            if images_in_map.is_empty() && original_len > 0 {
-                map_became_empty = true;
+            // 2. Keep the image file until garbage collection, so the delete can be undone.
+            //    Stored images may be shared with other lineups and backups anyway.
+            if object_store::is_blob_name(step_filename) {
+                continue;
             }
-        }
-
-        // If the map's image list is now empty, remove the map itself from both images and maps collections.
-        if map_became_empty {
-            manifest.images.remove(map_name);
-            manifest.maps.remove(map_name); // Also remove associated map metadata
+            //    Images from before the store move into it. If this fails, we return the
+            //    error immediately.
+            object_store::stash_legacy_image(&self.data_dir, map_name, step_filename).map_err(
+                |e| {
+                    log::error!(
+                        "PersistenceService: Failed to move image file {} into the store: {}",
+                        image_path_in_data_dir.display(),
+                        e
+                    );
+                    e
+                },
+            )?;
             log::info!(
-                "PersistenceService: Map '{}' became empty and was removed from manifest after deleting image '{}'.",
-                map_name,
-                image_filename
+                "PersistenceService: Moved deleted image file into the store: {}",
+                image_path_in_data_dir.display()
             );
         }
+        Ok(())
+    }
+}
 
-        self.save_manifest(&manifest)?;
-        log::info!(
-            "PersistenceService: Updated manifest after deleting image '{}' from map '{}'.",
-            image_filename,
-            map_name
-        );
+/// Pull the lineups and strats that still parse out of a damaged manifest, including
+/// one that was cut off mid-write. Every complete `{...}` object is tried as a lineup,
+/// then as a strat; the objects nested inside an accepted one are skipped.
+fn salvage_manifest(text: &str) -> ImageManifest {
+    // Pair up the braces outside of strings
+    let mut open = Vec::new();
+    let mut objects = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, byte) in text.bytes().enumerate() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' => open.push(index),
+            b'}' => {
+                if let Some(start) = open.pop() {
+                    objects.push((start, index));
+                }
+            }
+            _ => {}
+        }
+    }
+    // Outermost objects first
+    objects.sort_unstable();
 
-        Ok(())
+    // The entries may be from any version, so every migration runs on them
+    let mut manifest = ImageManifest {
+        schema_version: 0,
+        ..Default::default()
+    };
+    let mut accepted_until = 0;
+    for (start, end) in objects {
+        if start < accepted_until {
+            continue;
+        }
+        let object = &text[start..=end];
+        if let Ok(meta) = serde_json::from_str::<ImageMeta>(object)
+            && !meta.filename.is_empty()
+            && !meta.map.is_empty()
+        {
+            manifest
+                .images
+                .entry(meta.map.clone())
+                .or_default()
+                .push(meta);
+            accepted_until = end + 1;
+        } else if let Ok(strat) = serde_json::from_str::<Strat>(object) {
+            manifest.strats.push(strat);
+            accepted_until = end + 1;
+        }
     }
+    // Strats can only keep the lineups that made it
+    let image_ids: HashSet<String> = manifest
+        .images
+        .values()
+        .flatten()
+        .map(|meta| meta.id.clone())
+        .collect();
+    for strat in &mut manifest.strats {
+        strat
+            .steps
+            .retain(|step| image_ids.contains(&step.image_id));
+    }
+    manifest
 }
 
 #[cfg(test)]
@@ -321,16 +724,18 @@ mod tests {
     use super::*; // Make parent module's items available
     use std::fs::{self, File};
     use std::io::{self, Write};
-    use std::path::{Path, PathBuf};
+    use std::path::PathBuf;
     use std::sync::{Arc, Mutex};
     use tempfile::NamedTempFile; // NamedTempFile for dummy files
 
-    use crate::persistence::{ImageManifest, ImageMeta, MapMeta, NadeType};
+    use crate::persistence::{
+        ImageManifest, ImageMeta, ImageRole, MapConfig, MapMeta, NadeType, RadarOverview, WorldPos,
+    };
     use crate::services::thumbnail_service::{
-        SerializableImageError, SerializableIoError, ThumbnailServiceError, ThumbnailServiceTrait,
+        SerializableImageError, ThumbnailServiceError, ThumbnailServiceTrait,
     };
     // Import the new common setup utilities and MockThumbnailService from tests_common
-    use crate::tests_common::setup_persistence_test_env;
+    use crate::tests_common::{create_dummy_image_file, setup_persistence_test_env};
 
     fn create_dummy_source_file() -> io::Result<NamedTempFile> {
         let mut file = NamedTempFile::new()?;
@@ -398,7 +803,7 @@ mod tests {
     fn test_load_manifest_new_service_returns_default() {
         let env = setup_persistence_test_env();
         let service = env.persistence_service;
-        let manifest = service.load_manifest();
+        let manifest = service.load_manifest().unwrap();
         assert_eq!(
             manifest,
             ImageManifest::default(),
@@ -420,12 +825,14 @@ mod tests {
             .maps
             .insert(test_map_name.clone(), test_map_meta.clone());
         let test_image = ImageMeta {
+            id: "test-image-id".to_string(),
             filename: "test_image.jpg".to_string(),
             map: test_map_name.clone(),
             nade_type: NadeType::default(),
             notes: "Test notes".to_string(),
             position: "A Site".to_string(),
             order: 0,
+            ..Default::default()
         };
         expected_manifest
             .images
@@ -433,7 +840,7 @@ mod tests {
         let json_data = serde_json::to_string_pretty(&expected_manifest)
             .expect("Failed to serialize test manifest");
         fs::write(&manifest_path, json_data).expect("Failed to write test manifest.json");
-        let loaded_manifest = service.load_manifest();
+        let loaded_manifest = service.load_manifest().unwrap();
         assert_eq!(
             loaded_manifest, expected_manifest,
             "Loaded manifest should match the written one"
@@ -441,17 +848,169 @@ mod tests {
     }
 
     #[test]
-    fn test_load_manifest_corrupt_json_returns_default() {
+    fn test_load_manifest_corrupt_json_is_an_error() {
         let env = setup_persistence_test_env();
         let service = env.persistence_service;
         let manifest_path = env.data_dir_path.join("manifest.json");
         fs::write(&manifest_path, "{corrupt_json_data:}")
             .expect("Failed to write corrupt manifest.json");
-        let manifest = service.load_manifest();
+        assert!(
+            matches!(
+                service.load_manifest(),
+                Err(PersistenceServiceError::CorruptManifest(_))
+            ),
+            "Corrupt JSON must not load as an empty manifest"
+        );
+        assert!(manifest_path.exists(), "Loading must not touch the file");
+    }
+
+    #[test]
+    fn test_load_manifest_migrates_and_keeps_the_original() {
+        let env = setup_persistence_test_env();
+        let service = env.persistence_service;
+        let manifest_path = env.data_dir_path.join("manifest.json");
+        let original = include_str!("../../tests/fixtures/manifests/v1.2.json");
+        fs::write(&manifest_path, original).unwrap();
+
+        let manifest = service.load_manifest().unwrap();
+        assert_eq!(manifest.schema_version, migrations::MANIFEST_SCHEMA_VERSION);
+        assert!(
+            manifest
+                .images
+                .values()
+                .flatten()
+                .all(|img| !img.id.is_empty())
+        );
+        let backup_path = env.data_dir_path.join("manifest.json.pre-migration-v0");
+        assert_eq!(fs::read_to_string(&backup_path).unwrap(), original);
+
+        // The migrated manifest was saved, so the next load has nothing to do
+        fs::remove_file(&backup_path).unwrap();
+        assert_eq!(service.load_manifest().unwrap(), manifest);
+        assert!(!backup_path.exists());
+    }
+
+    #[test]
+    fn test_recover_manifest_quarantines_and_salvages() {
+        let env = setup_persistence_test_env();
+        let service = env.persistence_service;
+        let manifest_path = env.data_dir_path.join("manifest.json");
+
+        let lineup = |id: &str, position: &str| ImageMeta {
+            id: id.to_string(),
+            filename: format!("{}.webp", id),
+            map: "de_mirage".to_string(),
+            position: position.to_string(),
+            notes: "Aim at the {antenna}, then \"jump\"".to_string(),
+            throw_pos: Some(crate::persistence::WorldPos::default()),
+            ..Default::default()
+        };
+        let mut manifest = ImageManifest::default();
+        manifest.images.insert(
+            "de_mirage".to_string(),
+            vec![
+                lineup("a", "Window"),
+                lineup("b", "Jungle"),
+                lineup("c", "Stairs"),
+            ],
+        );
+        let mut strat = Strat::new("A execute", "de_mirage");
+        strat.add_lineup("a");
+        strat.add_lineup("c");
+        manifest.strats.push(strat);
+        service.save_manifest(&manifest).unwrap();
+        // The next save keeps the first one as a backup
+        let mut newer = manifest.clone();
+        newer.images.get_mut("de_mirage").unwrap().pop();
+        service.save_manifest(&newer).unwrap();
+
+        // The strats survive, one lineup no longer fits the schema and the file was cut
+        // off inside the last one
+        let images = &manifest.images["de_mirage"];
+        let mut invalid = serde_json::to_value(&images[1]).unwrap();
+        invalid["nade_type"] = "Brick".into();
+        let truncated = serde_json::to_string_pretty(&images[2]).unwrap();
+        let damaged = format!(
+            "{{\"strats\": {},\n\"images\": {{\"de_mirage\": [{}, {}, {}",
+            serde_json::to_string_pretty(&manifest.strats).unwrap(),
+            serde_json::to_string_pretty(&images[0]).unwrap(),
+            invalid,
+            &truncated[..truncated.len() / 2]
+        );
+        fs::write(&manifest_path, &damaged).unwrap();
+
+        let error = service.load_manifest().unwrap_err();
+        let recovery = service.recover_manifest(&error);
+        assert!(!manifest_path.exists(), "The damaged file is moved aside");
+        let quarantined = recovery.quarantined.as_ref().unwrap();
+        assert_eq!(fs::read_to_string(quarantined).unwrap(), damaged);
+
+        let (backup, backup_manifest) = recovery.backup.as_ref().unwrap();
+        assert_eq!(backup.generation, 1);
+        assert_eq!(backup_manifest, &manifest);
+        assert_eq!(recovery.manifest(ManifestRecoveryChoice::Backup), manifest);
+
+        let salvaged = recovery.manifest(ManifestRecoveryChoice::Salvaged);
+        assert_eq!(salvaged.images["de_mirage"], vec![lineup("a", "Window")]);
+        assert_eq!(salvaged.strats.len(), 1);
+        // The strat only keeps the lineup that was salvaged
+        assert_eq!(salvaged.strats[0].steps.len(), 1);
+        assert_eq!(salvaged.strats[0].steps[0].image_id, "a");
         assert_eq!(
-            manifest,
-            ImageManifest::default(),
-            "Should return default manifest for corrupt JSON"
+            recovery.manifest(ManifestRecoveryChoice::Empty),
+            ImageManifest::default()
+        );
+    }
+
+    #[test]
+    fn test_assign_missing_ids_backfills_legacy_manifest() {
+        let env = setup_persistence_test_env();
+        let service = env.persistence_service;
+        let manifest_path = env.data_dir_path.join("manifest.json");
+        // Manifest written before ImageMeta had an `id` field
+        let legacy_json = r#"{
+            "images": {
+                "de_mirage": [
+                    {"filename": "a.webp", "map": "de_mirage", "nade_type": "Smoke", "notes": "", "position": "Window", "order": 0},
+                    {"filename": "b.webp", "map": "de_mirage", "nade_type": "Flash", "notes": "", "position": "Jungle", "order": 1}
+                ]
+            },
+            "maps": {}
+        }"#;
+        fs::write(&manifest_path, legacy_json).expect("Failed to write legacy manifest.json");
+
+        // Loading runs this as a migration, so apply it to the manifest as written
+        let mut manifest: ImageManifest = serde_json::from_str(legacy_json).unwrap();
+        assert!(
+            manifest.assign_missing_ids(),
+            "Legacy images should be assigned ids"
+        );
+        let images = &manifest.images["de_mirage"];
+        assert!(images.iter().all(|img| !img.id.is_empty()));
+        assert_ne!(images[0].id, images[1].id, "Assigned ids should be unique");
+        assert_eq!(
+            manifest
+                .find_image(&images[1].id)
+                .map(|img| img.filename.as_str()),
+            Some("b.webp")
+        );
+
+        let ids_before: Vec<String> = images.iter().map(|img| img.id.clone()).collect();
+        assert!(
+            !manifest.assign_missing_ids(),
+            "Second pass should not change anything"
+        );
+        let ids_after: Vec<String> = manifest.images["de_mirage"]
+            .iter()
+            .map(|img| img.id.clone())
+            .collect();
+        assert_eq!(ids_before, ids_after, "Existing ids must be stable");
+
+        let loaded = service.load_manifest().unwrap();
+        assert!(
+            loaded.images["de_mirage"]
+                .iter()
+                .all(|img| !img.id.is_empty())
         );
     }
 
@@ -469,12 +1028,14 @@ mod tests {
             .maps
             .insert(test_map_name.clone(), test_map_meta.clone());
         let test_image = ImageMeta {
+            id: "saved-image-id".to_string(),
             filename: "saved_image.jpg".to_string(),
             map: test_map_name.clone(),
             nade_type: NadeType::default(),
             notes: "Saved notes".to_string(),
             position: "B Site".to_string(),
             order: 0,
+            ..Default::default()
         };
         manifest_to_save
             .images
@@ -490,13 +1051,127 @@ mod tests {
             "manifest.json should be created by save_manifest at {:?}",
             manifest_path
         );
-        let loaded_manifest_after_save = service.load_manifest();
+        let loaded_manifest_after_save = service.load_manifest().unwrap();
         assert_eq!(
             loaded_manifest_after_save, manifest_to_save,
             "Loaded manifest should match the saved one"
         );
     }
 
+    #[test]
+    fn test_save_manifest_keeps_rotating_backups() {
+        let env = setup_persistence_test_env();
+        let service = env.persistence_service;
+        let manifest_with = |count: usize| {
+            let mut manifest = ImageManifest::default();
+            let images = (0..count)
+                .map(|i| ImageMeta {
+                    id: format!("lineup-{}", i),
+                    filename: format!("lineup_{}.webp", i),
+                    map: "de_nuke".to_string(),
+                    order: i,
+                    ..Default::default()
+                })
+                .collect();
+            manifest.images.insert("de_nuke".to_string(), images);
+            manifest
+        };
+
+        // Saving generations 1..=7 keeps the previous MANIFEST_BACKUPS of them
+        for count in 1..=7 {
+            service.save_manifest(&manifest_with(count)).unwrap();
+        }
+        let counts: Vec<Option<usize>> = service
+            .list_manifest_backups()
+            .iter()
+            .map(|backup| backup.lineup_count)
+            .collect();
+        assert_eq!(counts, vec![Some(6), Some(5), Some(4), Some(3), Some(2)]);
+        assert!(
+            !env.data_dir_path
+                .join(format!("manifest.json.bak.{}", MANIFEST_BACKUPS + 1))
+                .exists()
+        );
+
+        // Saving the same manifest again doesn't push a generation out
+        service.save_manifest(&manifest_with(7)).unwrap();
+        assert_eq!(service.list_manifest_backups()[0].lineup_count, Some(6));
+
+        // Restoring makes the backup current and keeps the replaced manifest as a backup
+        let restored = service.restore_manifest_backup(3).unwrap();
+        assert_eq!(restored, manifest_with(4));
+        assert_eq!(service.load_manifest().unwrap(), manifest_with(4));
+        assert_eq!(service.list_manifest_backups()[0].lineup_count, Some(7));
+
+        // Only the manifest and its backups are left behind, however the saves interleave
+        let handles: Vec<_> = (1..=8)
+            .map(|count| {
+                let service = Arc::clone(&service);
+                let manifest = manifest_with(count);
+                std::thread::spawn(move || service.save_manifest(&manifest).unwrap())
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let loaded: ImageManifest = serde_json::from_str(
+            &fs::read_to_string(env.data_dir_path.join("manifest.json")).unwrap(),
+        )
+        .expect("manifest.json must never be half-written");
+        assert!(!loaded.images["de_nuke"].is_empty());
+        let stray_files: Vec<_> = fs::read_dir(&env.data_dir_path)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| !name.starts_with("manifest.json"))
+            .collect();
+        assert!(stray_files.is_empty(), "{:?}", stray_files);
+    }
+
+    #[test]
+    fn test_backups_from_before_the_store_only_move_files_once_restored() {
+        let env = setup_persistence_test_env();
+        let service = env.persistence_service;
+        let map_dir = env.data_dir_path.join("de_dust2");
+        fs::create_dir_all(&map_dir).unwrap();
+        let legacy_path = create_dummy_image_file(&map_dir, "smoke.webp", 4, 4);
+
+        service.save_manifest(&ImageManifest::default()).unwrap();
+        let mut old = ImageManifest {
+            schema_version: 2,
+            ..Default::default()
+        };
+        old.images.insert(
+            "de_dust2".to_string(),
+            vec![ImageMeta {
+                id: "smoke".to_string(),
+                filename: "smoke.webp".to_string(),
+                map: "de_dust2".to_string(),
+                ..Default::default()
+            }],
+        );
+        fs::write(
+            service.manifest_backup_path(1),
+            serde_json::to_string(&old).unwrap(),
+        )
+        .unwrap();
+
+        // Listing and reading the backup leave the image where it is
+        assert_eq!(service.list_manifest_backups()[0].lineup_count, Some(1));
+        let backup = service.read_manifest_backup(1).unwrap();
+        assert_eq!(backup.schema_version, 2);
+        assert_eq!(backup.images["de_dust2"][0].filename, "smoke.webp");
+        assert!(legacy_path.exists());
+
+        // Restoring it finishes the migration
+        let restored = service.restore_manifest_backup(1).unwrap();
+        assert_eq!(restored.schema_version, migrations::MANIFEST_SCHEMA_VERSION);
+        let filename = &restored.images["de_dust2"][0].filename;
+        assert!(object_store::is_blob_name(filename));
+        assert!(object_store::image_path(&env.data_dir_path, "de_dust2", filename).exists());
+        assert!(!legacy_path.exists());
+    }
+
     #[test]
     fn test_copy_image_to_data_success() {
         let env = setup_persistence_test_env();
@@ -512,15 +1187,24 @@ mod tests {
             *mock_ts.generate_should_fail.lock().unwrap() = false;
         }
         let result = service.copy_image_to_data(
-            source_file_path,
+            &[(source_file_path.to_path_buf(), ImageRole::Stand)],
             map_name,
             NadeType::default(),
+            Side::default(),
+            ThrowTechnique::default(),
+            Vec::new(),
+            None,
             String::new(),
             String::new(),
             &(thumbnail_service_arc.clone() as Arc<Mutex<dyn ThumbnailServiceTrait>>),
         );
         assert!(result.is_ok(), "copy_image_to_data failed: {:?}", result);
-        let (dest_path, unique_filename) = result.unwrap();
+        let (dest_path, image_meta) = result.unwrap();
+        let unique_filename = image_meta.filename.clone();
+        assert!(
+            !image_meta.id.is_empty(),
+            "Copied image should be assigned an id"
+        );
 
         // Verify the destination path exists and is a WebP file
         assert!(
@@ -534,8 +1218,14 @@ mod tests {
             unique_filename
         );
 
-        // Verify the destination path is in the main directory (not in .thumbnails)
-        let expected_dest_path = env.data_dir_path.join(map_name).join(&unique_filename);
+        // Verify the image went into the object store under its content hash
+        assert!(object_store::is_blob_name(&unique_filename));
+        assert_eq!(
+            unique_filename,
+            object_store::blob_name(&object_store::sha256_file(&dest_path).unwrap(), "webp")
+        );
+        let expected_dest_path =
+            object_store::image_path(&env.data_dir_path, map_name, &unique_filename);
         assert_eq!(dest_path, expected_dest_path, "Destination path mismatch");
 
         // Verify the original non-WebP file doesn't exist
@@ -544,7 +1234,7 @@ mod tests {
         assert!(!original_path.exists(), "Original file should be removed");
 
         // Verify manifest entries
-        let manifest = service.load_manifest();
+        let manifest = service.load_manifest().unwrap();
         assert!(
             manifest.images.get(map_name).is_some(),
             "Map should exist in manifest"
@@ -559,12 +1249,84 @@ mod tests {
             unique_filename,
             "Filename in manifest should reference WebP file"
         );
+        assert_eq!(
+            manifest.images.get(map_name).unwrap()[0].id,
+            image_meta.id,
+            "Id in manifest should match the returned ImageMeta"
+        );
         assert!(
             manifest.maps.get(map_name).is_some(),
             "Map metadata should exist in manifest"
         );
     }
 
+    #[test]
+    fn test_copy_image_to_data_multiple_images() {
+        let env = setup_persistence_test_env();
+        let service = env.persistence_service;
+        let thumbnail_service: Arc<Mutex<dyn ThumbnailServiceTrait>> =
+            env.mock_thumbnail_service.clone();
+
+        let stand_file = create_dummy_source_file().expect("Failed to create dummy source file");
+        let aim_file = create_dummy_source_file().expect("Failed to create dummy source file");
+        let map_name = "test_map_multi_image";
+
+        let (_dest_path, image_meta) = service
+            .copy_image_to_data(
+                &[
+                    (stand_file.path().to_path_buf(), ImageRole::Stand),
+                    (aim_file.path().to_path_buf(), ImageRole::Aim),
+                ],
+                map_name,
+                NadeType::Smoke,
+                Side::default(),
+                ThrowTechnique::default(),
+                Vec::new(),
+                None,
+                String::new(),
+                String::new(),
+                &thumbnail_service,
+            )
+            .expect("copy_image_to_data failed for multiple images");
+
+        let steps = image_meta.steps();
+        assert_eq!(
+            steps.len(),
+            2,
+            "Both images should become steps of one lineup"
+        );
+        assert_eq!(steps[0].role, ImageRole::Stand);
+        assert_eq!(steps[1].role, ImageRole::Aim);
+        // The mock converts every image to the same pixels, so both steps share one file
+        assert_eq!(steps[0].filename, steps[1].filename);
+        let stored_path =
+            object_store::image_path(&env.data_dir_path, map_name, &steps[0].filename);
+        assert!(stored_path.exists());
+        assert_eq!(
+            service.load_manifest().unwrap().images[map_name],
+            vec![image_meta.clone()],
+            "Lineup should be a single manifest entry"
+        );
+
+        // Deleting the lineup leaves the file to garbage collection, which keeps it for
+        // the backups that still refer to it
+        let mut manifest = service.load_manifest().unwrap();
+        manifest.remove_image(&image_meta);
+        service.save_manifest(&manifest).unwrap();
+        service
+            .remove_image_files(&image_meta, &thumbnail_service)
+            .expect("remove_image_files failed");
+        assert!(stored_path.exists());
+        assert_eq!(service.collect_garbage().unwrap().removed, 0);
+        assert!(stored_path.exists());
+
+        for backup in service.list_manifest_backups() {
+            fs::remove_file(service.manifest_backup_path(backup.generation)).unwrap();
+        }
+        assert_eq!(service.collect_garbage().unwrap().removed, 1);
+        assert!(!stored_path.exists());
+    }
+
     #[test]
     fn test_copy_image_to_data_thumbnail_generation_fails() {
         let env = setup_persistence_test_env();
@@ -590,9 +1352,13 @@ mod tests {
             *mock_ts.generation_error_type.lock().unwrap() = Some(specific_error);
         }
         let result = service.copy_image_to_data(
-            source_file_path,
+            &[(source_file_path.to_path_buf(), ImageRole::Stand)],
             map_name,
             NadeType::default(),
+            Side::default(),
+            ThrowTechnique::default(),
+            Vec::new(),
+            None,
             String::new(),
             String::new(),
             &(thumbnail_service_arc.clone() as Arc<Mutex<dyn ThumbnailServiceTrait>>),
@@ -618,7 +1384,7 @@ mod tests {
             "Map directory {:?} should be empty or not exist if copy failed early",
             map_dir
         );
-        let manifest = service.load_manifest();
+        let manifest = service.load_manifest().unwrap();
         assert!(
             manifest.images.get(map_name).is_none()
                 || manifest.images.get(map_name).unwrap().is_empty(),
@@ -635,9 +1401,13 @@ mod tests {
         let non_existent_path = PathBuf::from("path/to/non_existent_image.jpg");
         let map_name = "test_map_src_not_found";
         let result = service.copy_image_to_data(
-            &non_existent_path,
+            &[(non_existent_path.to_path_buf(), ImageRole::Stand)],
             map_name,
             NadeType::default(),
+            Side::default(),
+            ThrowTechnique::default(),
+            Vec::new(),
+            None,
             String::new(),
             String::new(),
             &(thumbnail_service_arc.clone() as Arc<Mutex<dyn ThumbnailServiceTrait>>),
@@ -665,9 +1435,13 @@ mod tests {
         let source_file_path = source_file_temp.path();
         let map_name_empty = ""; // Invalid map name
         let result = service.copy_image_to_data(
-            source_file_path,
+            &[(source_file_path.to_path_buf(), ImageRole::Stand)],
             map_name_empty,
             NadeType::default(),
+            Side::default(),
+            ThrowTechnique::default(),
+            Vec::new(),
+            None,
             String::new(),
             String::new(),
             &(thumbnail_service_arc.clone() as Arc<Mutex<dyn ThumbnailServiceTrait>>),
@@ -695,9 +1469,13 @@ mod tests {
             create_dummy_source_file().expect("Failed to create dummy source file");
         let source_file_path = source_file_temp.path();
         let result = service.copy_image_to_data(
-            source_file_path,
+            &[(source_file_path.to_path_buf(), ImageRole::Stand)],
             map_name,
             NadeType::default(),
+            Side::default(),
+            ThrowTechnique::default(),
+            Vec::new(),
+            None,
             String::new(),
             String::new(),
             &(thumbnail_service_arc.clone() as Arc<Mutex<dyn ThumbnailServiceTrait>>),
@@ -724,219 +1502,172 @@ mod tests {
     }
 
     #[test]
-    fn test_delete_image_and_thumbnails_success() {
+    fn test_trash_keeps_images_until_purged() {
         let env = setup_persistence_test_env();
         let service = env.persistence_service;
-        let thumbnail_service_arc = env.mock_thumbnail_service;
-
-        let source_file_temp =
-            create_dummy_source_file().expect("Failed to create dummy source file for delete test");
-        let source_file_path = source_file_temp.path();
-        let map_name = "test_map_delete_success";
-        {
-            let mock_ts = thumbnail_service_arc.lock().unwrap();
-            *mock_ts.generate_should_fail.lock().unwrap() = false;
-            *mock_ts.remove_should_fail.lock().unwrap() = false;
+        assert_eq!(service.load_trash(), Trash::default());
+
+        let source = create_dummy_source_file().unwrap();
+        let blob =
+            object_store::copy_into_store(&env.data_dir_path, source.path(), "webp").unwrap();
+        let now = SystemTime::now();
+        let month_ago = now - std::time::Duration::from_secs(40 * 86_400);
+        let mut trash = Trash::default();
+        for (id, deleted_at) in [("old", month_ago), ("recent", now)] {
+            let image = ImageMeta {
+                id: id.to_string(),
+                filename: blob.clone(),
+                map: "de_nuke".to_string(),
+                ..Default::default()
+            };
+            trash.add(image, deleted_at);
         }
-        let (copied_image_path, unique_filename) = service
-            .copy_image_to_data(
-                source_file_path,
-                map_name,
-                NadeType::default(),
-                String::new(),
-                String::new(),
-                &(thumbnail_service_arc.clone() as Arc<Mutex<dyn ThumbnailServiceTrait>>),
-            )
-            .expect("Setup: copy_image_to_data failed for delete test");
+        service.save_trash(&trash).unwrap();
+        assert_eq!(service.load_trash(), trash);
+
+        // Nothing in the library uses the image, but the trash does
+        let stored_path = object_store::image_path(&env.data_dir_path, "de_nuke", &blob);
+        assert_eq!(service.collect_garbage().unwrap().removed, 0);
+        assert!(stored_path.exists());
+
+        let mut kept_forever = trash.clone();
+        kept_forever.retention_days = 0;
+        assert_eq!(kept_forever.purge_expired(now), 0);
+        assert_eq!(trash.purge_expired(now), 1);
+        assert_eq!(trash.lineups[0].image.id, "recent");
+
+        trash.take("recent").unwrap();
+        service.save_trash(&trash).unwrap();
+        assert_eq!(service.collect_garbage().unwrap().removed, 1);
+        assert!(!stored_path.exists());
+    }
+
+    #[test]
+    fn test_map_config_defaults_round_trip_and_discovery() {
+        let env = setup_persistence_test_env();
+        let service = env.persistence_service;
+
+        // Without a maps.json the default pool is used
+        let mut config = service.load_map_config();
+        assert_eq!(config, MapConfig::default());
+        assert!(config.find("de_mirage").is_some());
+
+        // Custom maps, labels, hiding and ordering survive a save/load
+        config
+            .add_map("de_scrim_b", Some(123456))
+            .expect("add_map failed");
         assert!(
-            copied_image_path.exists(),
-            "Setup: Copied image should exist"
-        );
-        let manifest_before_delete = service.load_manifest();
-        assert_eq!(
-            manifest_before_delete
-                .images
-                .get(map_name)
-                .map_or(0, |v| v.len()),
-            1,
-            "Image should be in manifest before delete"
-        );
-        let delete_result = service.delete_image_and_thumbnails(
-            map_name,
-            &unique_filename,
-            &(thumbnail_service_arc.clone() as Arc<Mutex<dyn ThumbnailServiceTrait>>),
+            config.add_map("de_scrim_b", None).is_err(),
+            "Duplicates are rejected"
         );
         assert!(
-            delete_result.is_ok(),
-            "delete_image_and_thumbnails failed: {:?}",
-            delete_result
+            config.add_map("  ", None).is_err(),
+            "Blank names are rejected"
         );
         assert!(
-            !copied_image_path.exists(),
-            "Copied image should be deleted"
+            config.add_map("../evil", None).is_err(),
+            "Paths are rejected"
         );
-        let data_map_path = env.data_dir_path.join(map_name);
-        let thumb_dir = data_map_path.join(".thumbnails");
-
-        // For full-size WebP, the naming format is just the file stem with .webp extension
-        let expected_thumb_path = thumb_dir.join(format!(
-            "{}.webp",
-            Path::new(&unique_filename)
-                .file_stem()
-                .unwrap()
-                .to_str()
-                .unwrap()
-        ));
+        let last = config.maps.len() - 1;
+        config.move_map(last, 0);
+        config.maps[0].display_name = Some("Scrim B".to_string());
+        config.maps[1].hidden = true;
+        service
+            .save_map_config(&config)
+            .expect("save_map_config failed");
+
+        let reloaded = service.load_map_config();
+        assert_eq!(reloaded, config);
+        assert_eq!(reloaded.maps[0].workshop_id, Some(123456));
+        assert_eq!(reloaded.label_for("de_scrim_b"), "Scrim B");
+        assert_eq!(reloaded.label_for("de_unknown"), "de_unknown");
         assert!(
-            !expected_thumb_path.exists(),
-            "Expected thumbnail {:?} to be deleted",
-            expected_thumb_path
+            reloaded
+                .visible_maps()
+                .all(|entry| entry.name != "de_ancient"),
+            "Hidden maps are left out of the selector"
         );
 
-        let manifest_after_delete = service.load_manifest();
-        assert!(
-            manifest_after_delete.images.get(map_name).is_none()
-                || manifest_after_delete
-                    .images
-                    .get(map_name)
-                    .unwrap()
-                    .is_empty(),
-            "Image should be removed from manifest after delete, or map entry removed"
-        );
-
-        // If the map became empty, the map entry itself should be removed from the maps collection too
-        if manifest_after_delete.images.get(map_name).is_none() {
-            assert!(
-                manifest_after_delete.maps.get(map_name).is_none(),
-                "Map metadata should be removed if map becomes empty"
-            );
-        }
-    }
-
-    #[test]
-    fn test_delete_image_and_thumbnails_image_not_found() {
-        let env = setup_persistence_test_env();
-        let service = env.persistence_service;
-        let thumbnail_service_arc = env.mock_thumbnail_service;
-        let map_name = "test_map_delete_not_found";
-        // Setup: copy an image first to ensure the map exists and manifest might have it.
-        let source_file_temp =
-            create_dummy_source_file().expect("Failed to create dummy source file for setup");
-        let source_file_path = source_file_temp.path();
-        let (_copied_image_path, _unique_filename) = service
-            .copy_image_to_data(
-                source_file_path,
-                map_name,
-                NadeType::default(),
-                String::new(),
-                String::new(),
-                &(thumbnail_service_arc.clone() as Arc<Mutex<dyn ThumbnailServiceTrait>>),
-            )
-            .expect("Setup: copy_image_to_data failed for delete test");
-        let non_existent_filename = "non_existent_image.jpg";
-        let result = service.delete_image_and_thumbnails(
-            map_name,
-            non_existent_filename,
-            &(thumbnail_service_arc.clone() as Arc<Mutex<dyn ThumbnailServiceTrait>>),
+        // Maps with lineups in the manifest are discovered; empty ones are not
+        let mut manifest = ImageManifest::default();
+        manifest.images.insert(
+            "cs_office".to_string(),
+            vec![ImageMeta {
+                map: "cs_office".to_string(),
+                ..Default::default()
+            }],
         );
+        manifest.images.insert("de_empty".to_string(), Vec::new());
+        manifest.images.insert("de_mirage".to_string(), Vec::new());
+        let mut discovered = reloaded.clone();
+        assert!(discovered.discover_maps(&manifest));
+        assert_eq!(discovered.maps.last().unwrap().name, "cs_office");
+        assert_eq!(discovered.maps.len(), reloaded.maps.len() + 1);
         assert!(
-            result.is_err(),
-            "Expected delete to fail for non-existent image"
+            !discovered.discover_maps(&manifest),
+            "Discovery is idempotent"
         );
-        match result.err().unwrap() {
-            PersistenceServiceError::IoError(io_err) => {
-                assert_eq!(io_err.kind(), std::io::ErrorKind::NotFound);
-            }
-            other_err => panic!("Expected IoError(NotFound), got {:?}", other_err),
-        }
     }
 
     #[test]
-    fn test_delete_image_and_thumbnails_fails_if_thumbnail_service_fails() {
+    fn test_store_radar_image_and_overview_parameters() {
         let env = setup_persistence_test_env();
         let service = env.persistence_service;
-        let thumbnail_service_arc = env.mock_thumbnail_service;
+        let source_dir = tempfile::tempdir().expect("Failed to create source dir");
+
+        let first = create_dummy_image_file(source_dir.path(), "radar.png", 64, 64);
+        let filename = service
+            .store_radar_image(&first, "de_mirage")
+            .expect("store_radar_image failed");
+        assert_eq!(filename, "de_mirage.png");
+        assert!(service.radar_image_path(&filename).exists());
+
+        // A replacement with another extension removes the old file
+        let second = source_dir.path().join("radar.jpg");
+        image::RgbImage::new(64, 64)
+            .save(&second)
+            .expect("Failed to write jpg radar");
+        let replaced = service
+            .store_radar_image(&second, "de_mirage")
+            .expect("store_radar_image failed for replacement");
+        assert_eq!(replaced, "de_mirage.jpg");
+        assert!(service.radar_image_path(&replaced).exists());
+        assert!(!service.radar_image_path(&filename).exists());
+
+        // Files that aren't images are rejected
+        let not_an_image = create_dummy_source_file().expect("Failed to create dummy file");
+        assert!(matches!(
+            service.store_radar_image(not_an_image.path(), "de_mirage"),
+            Err(PersistenceServiceError::InvalidInput(_))
+        ));
 
-        let source_file_temp =
-            create_dummy_source_file().expect("Failed to create dummy source file for delete test");
-        let source_file_path = source_file_temp.path();
-        let map_name = "test_map_delete_thumb_fail";
-        {
-            let mock_ts = thumbnail_service_arc.lock().unwrap();
-            *mock_ts.generate_should_fail.lock().unwrap() = false;
-        }
-        let (copied_image_path, unique_filename) = service
-            .copy_image_to_data(
-                source_file_path,
-                map_name,
-                NadeType::default(),
-                String::new(),
-                String::new(),
-                &(thumbnail_service_arc.clone() as Arc<Mutex<dyn ThumbnailServiceTrait>>),
-            )
-            .expect("Setup: copy_image_to_data failed for delete test");
-        assert!(
-            copied_image_path.exists(),
-            "Setup: Copied image should exist"
-        );
-        {
-            let mock_ts = thumbnail_service_arc.lock().unwrap();
-            *mock_ts.remove_should_fail.lock().unwrap() = true;
-            let error_to_set = ThumbnailServiceError::FileRemoval(
-                PathBuf::from("mock_path_for_remove_error.webp"), // Dummy path for the error
-                SerializableIoError {
-                    kind: std::io::ErrorKind::PermissionDenied,
-                    message: "Mock remove: Permission denied".to_string(),
-                },
-            );
-            *mock_ts.removal_error_type.lock().unwrap() = Some(error_to_set);
-        }
-        let delete_result = service.delete_image_and_thumbnails(
-            map_name,
-            &unique_filename,
-            &(thumbnail_service_arc.clone() as Arc<Mutex<dyn ThumbnailServiceTrait>>),
-        );
-        assert!(
-            delete_result.is_err(),
-            "delete_image_and_thumbnails should fail when thumbnail service fails. Result: {:?}",
-            delete_result
-        );
-        let actual_err = delete_result.err().unwrap(); // Extract error once
-        match actual_err {
-            PersistenceServiceError::ThumbnailDeletionFailed(ref ts_error) => {
-                // Changed io_err to ts_error for clarity
-                match ts_error {
-                    // ts_error is &ThumbnailServiceError
-                    ThumbnailServiceError::FileRemoval(_path, serializable_err) => {
-                        assert_eq!(
-                            serializable_err.kind,
-                            std::io::ErrorKind::PermissionDenied,
-                            "Unexpected IO error kind in SerializableIoError"
-                        );
-                        assert!(
-                            serializable_err
-                                .message
-                                .contains("Mock remove: Permission denied"),
-                            "Error message mismatch. Got: {}",
-                            serializable_err.message
-                        );
-                    }
-                    other_ts_err => panic!(
-                        "Expected ThumbnailServiceError::FileRemoval, got {:?}",
-                        other_ts_err
-                    ),
-                }
+        // Overview parameters as written in the game's overview files
+        let overview = r#"
+            "de_mirage"
+            {
+                "material"  "overviews/de_mirage"
+                "pos_x"     "-3230"
+                "pos_y"     "1713"
+                "scale"     "5.00"
+                "rotate"    "0"
             }
-            ref other_err => panic!(
-                "Expected PersistenceServiceError::ThumbnailDeletionFailed, but got {:?}. Full error: {:?}",
-                other_err, actual_err
-            ),
-        }
-        assert!(
-            copied_image_path.exists(),
-            "Main image should NOT be deleted if thumbnail service fails. Path: {:?}. Delete result: {:?}",
-            copied_image_path,
-            actual_err
-        );
+        "#;
+        let (pos_x, pos_y, scale) =
+            RadarOverview::parse_overview(overview).expect("parse_overview failed");
+        assert_eq!((pos_x, pos_y, scale), (-3230.0, 1713.0, 5.0));
+        assert!(RadarOverview::parse_overview("\"pos_x\" \"10\"").is_none());
+
+        let radar = RadarOverview {
+            image: Some(replaced),
+            pos_x,
+            pos_y,
+            scale,
+        };
+        let (radar_x, radar_y) = radar.world_to_radar(WorldPos {
+            x: -3230.0 + 500.0,
+            y: 1713.0 - 1000.0,
+            z: 0.0,
+        });
+        assert_eq!((radar_x, radar_y), (100.0, 200.0));
     }
 }
@@ -0,0 +1,250 @@
+// src/services/practice_config_service.rs
+use crate::persistence::ImageMeta;
+use std::fs;
+use std::path::Path;
+
+/// Server cvars and commands that set up an offline practice server: cheats for
+/// `setpos`, no bots or round timer, free grenades and trajectory previews.
+const PRACTICE_COMMANDS: &[&str] = &[
+    "sv_cheats 1",
+    "bot_kick",
+    "mp_warmup_end",
+    "mp_limitteams 0",
+    "mp_autoteambalance 0",
+    "mp_freezetime 0",
+    "mp_roundtime 60",
+    "mp_roundtime_defuse 60",
+    "mp_buytime 9999",
+    "mp_buy_anywhere 1",
+    "mp_startmoney 60000",
+    "mp_maxmoney 60000",
+    "sv_infinite_ammo 1",
+    "ammo_grenade_limit_total 5",
+    "sv_grenade_trajectory_prac_pipreview 1",
+    "sv_grenade_trajectory_prac_trailtime 15",
+    "sv_showimpacts 1",
+    "sv_showimpacts_time 10",
+    "mp_restartgame 1",
+];
+
+#[derive(Debug)]
+pub enum PracticeConfigServiceError {
+    IoError(std::io::Error),
+    NoTeleportLineups, // None of the lineups has a stored throw position
+}
+
+impl std::fmt::Display for PracticeConfigServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PracticeConfigServiceError::IoError(err) => write!(f, "I/O error: {}", err),
+            PracticeConfigServiceError::NoTeleportLineups => write!(
+                f,
+                "None of the selected lineups has a throw position. Paste their getpos output in the edit modal first."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PracticeConfigServiceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PracticeConfigServiceError::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PracticeConfigServiceError {
+    fn from(err: std::io::Error) -> Self {
+        PracticeConfigServiceError::IoError(err)
+    }
+}
+
+/// Builds CS2 practice `.cfg` files from stored lineups
+#[derive(Debug, Default)]
+pub struct PracticeConfigService;
+
+impl PracticeConfigService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Generate the practice config text for `lineups` on `map_label`.
+    ///
+    /// Every lineup with a throw position gets an alias `nx1`, `nx2`, ... that teleports
+    /// to it and echoes what it is; `nx_list` prints them all. Lineups without a throw
+    /// position are listed in a comment so it's clear why they're missing.
+    pub fn generate(&self, map_label: &str, lineups: &[&ImageMeta]) -> String {
+        let mut cfg = String::new();
+        cfg.push_str(&format!("// Nadex practice config for {}\n", map_label));
+        cfg.push_str(&format!(
+            "// Generated {}. Run with: exec <this file's name>\n\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M")
+        ));
+
+        cfg.push_str("// --- Practice settings ---\n");
+        for command in PRACTICE_COMMANDS {
+            cfg.push_str(command);
+            cfg.push('\n');
+        }
+        cfg.push_str("// Hide the HUD except kill feed for clean lineup screenshots\n");
+        cfg.push_str("// cl_draw_only_deathnotices 1\n\n");
+
+        cfg.push_str("// --- Lineups ---\n");
+        let mut list_lines = Vec::new();
+        let mut skipped = Vec::new();
+        for meta in lineups {
+            let Some(setpos) = meta.setpos() else {
+                skipped.push(console_text(&meta.position));
+                continue;
+            };
+            let alias = format!("nx{}", list_lines.len() + 1);
+            let description = format!(
+                "{} - {} ({:?})",
+                alias,
+                console_text(&meta.position),
+                meta.nade_type
+            );
+            if !meta.notes.trim().is_empty() {
+                cfg.push_str(&format!("// {}\n", console_text(&meta.notes)));
+            }
+            cfg.push_str(&format!(
+                "alias \"{}\" \"{};echo {}\"\n",
+                alias,
+                setpos.command(),
+                description
+            ));
+            list_lines.push(description);
+        }
+
+        let list = list_lines
+            .iter()
+            .map(|line| format!("echo {}", line))
+            .collect::<Vec<_>>()
+            .join(";");
+        cfg.push_str(&format!(
+            "alias \"nx_list\" \"echo Nadex lineups for {};{}\"\n",
+            console_text(map_label),
+            list
+        ));
+        if !skipped.is_empty() {
+            cfg.push_str("// Skipped (no throw position): ");
+            cfg.push_str(&skipped.join(", "));
+            cfg.push('\n');
+        }
+        cfg.push_str("\nnx_list\n");
+        cfg
+    }
+
+    /// Write the practice config for `lineups` to `path`.
+    ///
+    /// Returns how many lineups got a teleport alias.
+    pub fn write_config(
+        &self,
+        path: &Path,
+        map_label: &str,
+        lineups: &[&ImageMeta],
+    ) -> Result<usize, PracticeConfigServiceError> {
+        let teleport_count = lineups
+            .iter()
+            .filter(|meta| meta.throw_pos.is_some())
+            .count();
+        if teleport_count == 0 {
+            return Err(PracticeConfigServiceError::NoTeleportLineups);
+        }
+        fs::write(path, self.generate(map_label, lineups))?;
+        Ok(teleport_count)
+    }
+}
+
+/// Make free text safe inside a quoted console command: quotes and `;` would end the
+/// alias early, and a line break would end the command.
+fn console_text(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '"' | ';' => '\'',
+            '\n' | '\r' => ' ',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{NadeType, ViewAngles, WorldPos};
+
+    fn lineup(position: &str, throw_pos: Option<WorldPos>) -> ImageMeta {
+        ImageMeta {
+            id: position.to_string(),
+            filename: format!("{}.webp", position),
+            map: "de_mirage".to_string(),
+            nade_type: NadeType::Smoke,
+            position: position.to_string(),
+            throw_pos,
+            view_angles: Some(ViewAngles {
+                pitch: -12.5,
+                yaw: 90.0,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_practice_config_aliases_and_skips() {
+        let service = PracticeConfigService::new();
+        let mut window = lineup(
+            "Window \"smoke\"; from T spawn",
+            Some(WorldPos {
+                x: 1216.0,
+                y: -160.5,
+                z: -103.75,
+            }),
+        );
+        window.notes = "Jump throw\nalign with the antenna".to_string();
+        let no_coordinates = lineup("Stairs", None);
+        let cfg = service.generate("Mirage", &[&window, &no_coordinates]);
+
+        assert!(cfg.contains("sv_cheats 1\n"));
+        assert!(cfg.contains("sv_infinite_ammo 1\n"));
+        assert!(cfg.contains("sv_grenade_trajectory_prac_pipreview 1\n"));
+        assert!(cfg.contains("// Jump throw align with the antenna\n"));
+        assert!(cfg.contains(
+            "alias \"nx1\" \"setpos 1216 -160.5 -103.75;setang -12.5 90 0;echo nx1 - Window 'smoke'' from T spawn (Smoke)\"\n"
+        ));
+        assert!(!cfg.contains("nx2"));
+        assert!(cfg.contains("// Skipped (no throw position): Stairs\n"));
+        assert!(cfg.ends_with("\nnx_list\n"));
+
+        // Every alias body stays on one line with balanced quotes
+        for line in cfg.lines().filter(|line| line.starts_with("alias")) {
+            assert_eq!(line.matches('"').count(), 4, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_write_practice_config() {
+        let service = PracticeConfigService::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("nadex_de_mirage.cfg");
+
+        let no_coordinates = lineup("Stairs", None);
+        assert!(matches!(
+            service.write_config(&path, "Mirage", &[&no_coordinates]),
+            Err(PracticeConfigServiceError::NoTeleportLineups)
+        ));
+        assert!(!path.exists());
+
+        let jungle = lineup("Jungle", Some(WorldPos::default()));
+        let written = service
+            .write_config(&path, "Mirage", &[&jungle, &no_coordinates])
+            .unwrap();
+        assert_eq!(written, 1);
+        let cfg = fs::read_to_string(&path).unwrap();
+        assert!(cfg.contains(
+            "alias \"nx1\" \"setpos 0 0 0;setang -12.5 90 0;echo nx1 - Jungle (Smoke)\"\n"
+        ));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::app_actions::AppAction;
+use crate::app_state::AppState;
+use chrono::{DateTime, Local};
+use eframe::egui;
+
+/// Renders the backup window, listing the manifest generations kept by
+/// `PersistenceService::save_manifest`, newest first.
+///
+/// Restoring asks for confirmation inline before pushing `AppAction::RestoreManifestBackup`.
+/// Closing the window pushes `AppAction::CloseBackups`.
+pub fn show_backups(
+    app_state: &mut AppState,
+    ctx: &egui::Context,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let mut open = app_state.show_backups;
+    let backups = app_state.persistence_service.list_manifest_backups();
+    // The backup waiting for restore confirmation, if any
+    let confirm_id = egui::Id::new("backup_confirm_restore");
+    let mut confirm_restore: Option<usize> = ctx.data_mut(|d| d.get_temp(confirm_id));
+
+    egui::Window::new("Library Backups")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            if backups.is_empty() {
+                ui.label("No backups yet. One is kept every time the library changes.");
+                return;
+            }
+            ui.label("Each change to the library keeps the previous version. Restoring one keeps the current version as a backup too.");
+            ui.add_space(5.0);
+
+            egui::Grid::new("backup_grid")
+                .num_columns(3)
+                .spacing([10.0, 6.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    for backup in &backups {
+                        let saved_at = backup.saved_at.map_or_else(
+                            || "Unknown time".to_string(),
+                            |time| DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M:%S").to_string(),
+                        );
+                        ui.label(saved_at);
+                        match backup.lineup_count {
+                            Some(count) => ui.label(format!("{} lineups", count)),
+                            None => ui.colored_label(egui::Color32::RED, "Damaged"),
+                        };
+                        ui.horizontal(|ui_h| {
+                            if confirm_restore == Some(backup.generation) {
+                                ui_h.label("Replace the current library?");
+                                if ui_h.button("Yes").clicked() {
+                                    action_queue.push(AppAction::RestoreManifestBackup(backup.generation));
+                                    confirm_restore = None;
+                                }
+                                if ui_h.button("No").clicked() {
+                                    confirm_restore = None;
+                                }
+                            } else if ui_h
+                                .add_enabled(backup.lineup_count.is_some(), egui::Button::new("Restore"))
+                                .clicked()
+                            {
+                                confirm_restore = Some(backup.generation);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+        });
+
+    ctx.data_mut(|d| match confirm_restore {
+        Some(generation) if open => d.insert_temp(confirm_id, generation),
+        _ => {
+            d.remove::<usize>(confirm_id);
+        }
+    });
+    if !open {
+        action_queue.push(AppAction::CloseBackups);
+    }
+}
@@ -24,7 +24,7 @@ pub fn show_delete_confirmation_modal(
                 "Are you sure you want to delete '{}'?",
                 image_to_delete.filename
             ));
-            ui.label("This action cannot be undone.");
+            ui.label("The lineup moves to the trash, where it can be restored.");
             ui.add_space(10.0);
             ui.horizontal(|ui| {
                 if ui.button("Delete").clicked() {
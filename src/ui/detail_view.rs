@@ -101,6 +101,25 @@ pub fn show_detail_modal(
                         // Inner layout to keep separator, image, and grid left-aligned with each other
                         ui.with_layout(egui::Layout::top_down(egui::Align::Min), |ui_sub| {
                             ui_sub.separator();
+
+                            // Step selector for lineups made of several images
+                            let steps = selected_image_meta.steps();
+                            if steps.len() > 1 {
+                                ui_sub.horizontal(|ui_steps| {
+                                    for (index, step) in steps.iter().enumerate() {
+                                        let label = format!("{}. {:?}", index + 1, step.role);
+                                        if ui_steps
+                                            .selectable_label(app_state.detail_step == index, label)
+                                            .clicked()
+                                            && app_state.detail_step != index
+                                        {
+                                            action_queue
+                                                .push(AppAction::DetailModalSelectStep(index));
+                                        }
+                                    }
+                                });
+                            }
+
                             if let Some(texture) = detail_view_texture_handle {
                                 let img_widget = egui::Image::new(egui::load::SizedTexture::new(
                                     texture.id(),
@@ -125,6 +144,32 @@ pub fn show_detail_modal(
                                     ui.label(format!("{:?}", selected_image_meta.nade_type));
                                     ui.end_row();
 
+                                    ui.strong("Side:");
+                                    ui.label(format!("{:?}", selected_image_meta.side));
+                                    ui.end_row();
+
+                                    let technique = &selected_image_meta.technique;
+                                    ui.strong("Technique:");
+                                    ui.label(format!(
+                                        "{:?}, {:?} click",
+                                        technique.movement, technique.click
+                                    ));
+                                    ui.end_row();
+
+                                    ui.strong("Jump-throw Bind:");
+                                    ui.label(if technique.jump_throw_bind {
+                                        "Required"
+                                    } else {
+                                        "Not needed"
+                                    });
+                                    ui.end_row();
+
+                                    if !selected_image_meta.tags.is_empty() {
+                                        ui.strong("Tags:");
+                                        ui.label(selected_image_meta.tags.join(", "));
+                                        ui.end_row();
+                                    }
+
                                     ui.strong("Position:");
                                     ui.label(if selected_image_meta.position.is_empty() {
                                         "[No Position]"
@@ -140,6 +185,24 @@ pub fn show_detail_modal(
                                     };
                                     ui.add(egui::Label::new(notes_text).wrap());
                                     ui.end_row();
+
+                                    if let Some(setpos) = selected_image_meta.setpos() {
+                                        let command = setpos.command();
+                                        ui.strong("Teleport:");
+                                        ui.horizontal(|ui_h| {
+                                            ui_h.monospace(&command);
+                                            if ui_h
+                                                .button("Copy setpos")
+                                                .on_hover_text(
+                                                    "Copy the console command that teleports to the throw position",
+                                                )
+                                                .clicked()
+                                            {
+                                                ui_h.ctx().copy_text(command.clone());
+                                            }
+                                        });
+                                        ui.end_row();
+                                    }
                                 });
                         }); // End of ui_sub (left_aligned_sub_block)
 
@@ -154,7 +217,7 @@ pub fn show_detail_modal(
                                     .clicked()
                                 {
                                     action_queue.push(AppAction::DetailModalRequestEdit(
-                                        selected_image_meta.clone(),
+                                        selected_image_meta.id.clone(),
                                     ));
                                 }
                                 ui.add_space(10.0); // Space between buttons
@@ -164,7 +227,7 @@ pub fn show_detail_modal(
                                     .clicked()
                                 {
                                     action_queue.push(AppAction::DetailModalRequestDelete(
-                                        selected_image_meta.clone(),
+                                        selected_image_meta.id.clone(),
                                     ));
                                 }
                             });
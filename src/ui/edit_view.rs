@@ -2,31 +2,99 @@ use crate::app_actions::AppAction; // Added AppAction for the queue
 use crate::app_state::AppState;
 use crate::persistence::ImageMeta;
 use crate::persistence::NadeType;
+use crate::persistence::Side;
+use crate::persistence::{Movement, ThrowClick, ThrowTechnique, ViewAngles, WorldPos};
+use crate::ui::getpos_input_view::show_getpos_input;
+use crate::ui::tag_editor_view::show_tag_editor;
 use egui::{Align2, ComboBox, Context, Id, TextEdit, Vec2, Window};
 use strum::IntoEnumIterator;
 
 /// Data structure for the edit form.
 #[derive(Clone, Debug)]
 pub struct EditFormData {
-    pub filename: String,
+    pub id: String,
+    pub filename: String, // Shown in the window title only; images are matched by `id`
+    pub map: String,      // A different map moves the lineup there on save
+    pub keep_copy: bool,  // With a different map, copy the lineup there instead of moving it
     pub nade_type: NadeType,
+    pub side: Side,
+    pub technique: ThrowTechnique,
+    pub tags: Vec<String>,
     pub position: String,
     pub notes: String,
+    pub throw_pos: Option<WorldPos>,
+    pub landing_pos: Option<WorldPos>,
+    pub view_angles: Option<ViewAngles>,
 }
 
 impl EditFormData {
     pub fn from_meta(meta: &ImageMeta) -> Self {
         Self {
+            id: meta.id.clone(),
             filename: meta.filename.clone(),
+            map: meta.map.clone(),
+            keep_copy: false,
             nade_type: meta.nade_type,
+            side: meta.side,
+            technique: meta.technique,
+            tags: meta.tags.clone(),
             position: meta.position.clone(),
             notes: meta.notes.clone(),
+            throw_pos: meta.throw_pos,
+            landing_pos: meta.landing_pos,
+            view_angles: meta.view_angles,
         }
     }
 }
 
 // EditModalAction enum removed as part of refactor
 
+/// Optional world coordinates: a checkbox to set or clear them, plus x/y/z fields.
+fn show_world_pos_editor(ui: &mut egui::Ui, pos: &mut Option<WorldPos>) {
+    ui.horizontal(|ui_h| {
+        let mut is_set = pos.is_some();
+        if ui_h.checkbox(&mut is_set, "").changed() {
+            *pos = is_set.then(WorldPos::default);
+        }
+        if let Some(pos) = pos {
+            for (label, value) in [("x", &mut pos.x), ("y", &mut pos.y), ("z", &mut pos.z)] {
+                ui_h.label(label);
+                ui_h.add(egui::DragValue::new(value).speed(1.0).max_decimals(2));
+            }
+        } else {
+            ui_h.weak("Not set (needed for the radar)");
+        }
+    });
+}
+
+/// Optional view angles: a checkbox to set or clear them, plus pitch/yaw fields.
+fn show_view_angles_editor(ui: &mut egui::Ui, angles: &mut Option<ViewAngles>) {
+    ui.horizontal(|ui_h| {
+        let mut is_set = angles.is_some();
+        if ui_h.checkbox(&mut is_set, "").changed() {
+            *angles = is_set.then(ViewAngles::default);
+        }
+        if let Some(angles) = angles {
+            ui_h.label("pitch");
+            ui_h.add(
+                egui::DragValue::new(&mut angles.pitch)
+                    .speed(0.1)
+                    .range(-89.0..=89.0)
+                    .max_decimals(2),
+            );
+            ui_h.label("yaw");
+            ui_h.add(
+                egui::DragValue::new(&mut angles.yaw)
+                    .speed(0.1)
+                    .range(-180.0..=180.0)
+                    .max_decimals(2),
+            );
+        } else {
+            ui_h.weak("Not set");
+        }
+    });
+}
+
 /// Renders the edit modal for an image.
 ///
 /// Pushes `AppAction::EditModalSave` or `AppAction::EditModalCancel` to the action queue.
@@ -57,7 +125,24 @@ pub fn show_edit_modal(app_state: &mut AppState, ctx: &Context, action_queue: &m
 
     // Clone filename for the window ID and title to avoid borrow checker issues with app_state.edit_form_data inside the closure.
     let filename_for_title = app_state.edit_form_data.as_ref().unwrap().filename.clone();
-    let window_id = Id::new("edit_image_modal_window").with(&filename_for_title);
+    let known_tags: Vec<String> = app_state.image_manifest.tag_counts().into_keys().collect();
+    // Visible maps, plus the lineup's own map in case it's hidden
+    let original_map = app_state
+        .editing_image_meta
+        .as_ref()
+        .map(|meta| meta.map.clone())
+        .unwrap_or_default();
+    let mut maps: Vec<(String, String)> = app_state
+        .map_config
+        .visible_maps()
+        .map(|entry| (entry.name.clone(), entry.label().to_string()))
+        .collect();
+    if !maps.iter().any(|(name, _)| *name == original_map) {
+        let label = app_state.map_config.label_for(&original_map).to_string();
+        maps.insert(0, (original_map.clone(), label));
+    }
+    let window_id =
+        Id::new("edit_image_modal_window").with(&app_state.edit_form_data.as_ref().unwrap().id);
 
     Window::new(format!("Edit: {}", filename_for_title))
         .id(window_id)
@@ -80,6 +165,30 @@ pub fn show_edit_modal(app_state: &mut AppState, ctx: &Context, action_queue: &m
                     .spacing([10.0, 10.0])
                     .striped(true)
                     .show(ui, |ui| {
+                        ui.label("Map:");
+                        ui.vertical(|ui_v| {
+                            let selected_label = maps
+                                .iter()
+                                .find(|(name, _)| *name == form_data.map)
+                                .map_or(form_data.map.as_str(), |(_, label)| label.as_str());
+                            ComboBox::new("map_combo_edit", "")
+                                .selected_text(selected_label)
+                                .show_ui(ui_v, |ui_combo| {
+                                    for (name, label) in &maps {
+                                        ui_combo.selectable_value(
+                                            &mut form_data.map,
+                                            name.clone(),
+                                            label,
+                                        );
+                                    }
+                                });
+                            if form_data.map != original_map {
+                                ui_v.checkbox(&mut form_data.keep_copy, "Copy instead of moving")
+                                    .on_hover_text("Keep the lineup on its current map too");
+                            }
+                        });
+                        ui.end_row();
+
                         ui.label("Nade Type:");
                         ComboBox::new("nade_type_combo_edit", "") // Unique ID source, empty label
                             .selected_text(format!("{:?}", form_data.nade_type))
@@ -94,6 +203,52 @@ pub fn show_edit_modal(app_state: &mut AppState, ctx: &Context, action_queue: &m
                             });
                         ui.end_row();
 
+                        ui.label("Side:");
+                        ComboBox::new("side_combo_edit", "")
+                            .selected_text(format!("{:?}", form_data.side))
+                            .show_ui(ui, |ui| {
+                                for side in Side::iter() {
+                                    ui.selectable_value(
+                                        &mut form_data.side,
+                                        side,
+                                        format!("{:?}", side),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Movement:");
+                        ComboBox::new("movement_combo_edit", "")
+                            .selected_text(format!("{:?}", form_data.technique.movement))
+                            .show_ui(ui, |ui| {
+                                for movement in Movement::iter() {
+                                    ui.selectable_value(
+                                        &mut form_data.technique.movement,
+                                        movement,
+                                        format!("{:?}", movement),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Click:");
+                        ComboBox::new("click_combo_edit", "")
+                            .selected_text(format!("{:?}", form_data.technique.click))
+                            .show_ui(ui, |ui| {
+                                for click in ThrowClick::iter() {
+                                    ui.selectable_value(
+                                        &mut form_data.technique.click,
+                                        click,
+                                        format!("{:?}", click),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Jump-throw Bind:");
+                        ui.checkbox(&mut form_data.technique.jump_throw_bind, "Required");
+                        ui.end_row();
+
                         ui.label("Position:");
                         ui.add(
                             TextEdit::singleline(&mut form_data.position)
@@ -101,6 +256,10 @@ pub fn show_edit_modal(app_state: &mut AppState, ctx: &Context, action_queue: &m
                         );
                         ui.end_row();
 
+                        ui.label("Tags:");
+                        show_tag_editor(ui, "edit", &mut form_data.tags, &known_tags);
+                        ui.end_row();
+
                         ui.label("Notes:");
                         ui.add(
                             TextEdit::multiline(&mut form_data.notes)
@@ -108,6 +267,25 @@ pub fn show_edit_modal(app_state: &mut AppState, ctx: &Context, action_queue: &m
                                 .hint_text("Brief description or lineup"),
                         );
                         ui.end_row();
+
+                        ui.label("getpos:");
+                        if let Some(setpos) = show_getpos_input(ui, "edit") {
+                            form_data.throw_pos = Some(setpos.pos);
+                            form_data.view_angles = setpos.angles;
+                        }
+                        ui.end_row();
+
+                        ui.label("Throw Position:");
+                        show_world_pos_editor(ui, &mut form_data.throw_pos);
+                        ui.end_row();
+
+                        ui.label("View Angles:");
+                        show_view_angles_editor(ui, &mut form_data.view_angles);
+                        ui.end_row();
+
+                        ui.label("Landing Spot:");
+                        show_world_pos_editor(ui, &mut form_data.landing_pos);
+                        ui.end_row();
                     });
 
                 ui.add_space(20.0);
@@ -0,0 +1,53 @@
+use crate::persistence::Setpos;
+use egui::{Id, Ui};
+
+/// Renders a field for pasting the output of the console `getpos` command
+/// (Enter or "Apply" to confirm).
+///
+/// Returns the parsed position when the paste is applied. Unparseable input shows an
+/// error instead. The pending text is kept in egui's temporary memory under `id_salt`.
+pub fn show_getpos_input(ui: &mut Ui, id_salt: &str) -> Option<Setpos> {
+    let input_id = Id::new("getpos_input").with(id_salt);
+    let error_id = Id::new("getpos_input_error").with(id_salt);
+    let mut input: String = ui.data_mut(|d| d.get_temp(input_id).unwrap_or_default());
+    let mut error: Option<String> = ui.data_mut(|d| d.get_temp(error_id));
+    let mut parsed = None;
+
+    ui.vertical(|ui| {
+        ui.horizontal(|ui_h| {
+            let response = ui_h.add(
+                egui::TextEdit::singleline(&mut input)
+                    .hint_text("Paste getpos output: setpos x y z;setang pitch yaw 0")
+                    .desired_width(260.0),
+            );
+            let submitted =
+                response.lost_focus() && ui_h.input(|i| i.key_pressed(egui::Key::Enter));
+            if (ui_h.button("Apply").clicked() || submitted) && !input.trim().is_empty() {
+                match Setpos::parse(&input) {
+                    Some(setpos) => {
+                        parsed = Some(setpos);
+                        input.clear();
+                        error = None;
+                    }
+                    None => {
+                        error = Some("No \"setpos x y z\" found in the pasted text.".to_string());
+                    }
+                }
+            }
+        });
+        if let Some(error) = &error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+    });
+
+    ui.data_mut(|d| {
+        d.insert_temp(input_id, input);
+        match error {
+            Some(error) => d.insert_temp(error_id, error),
+            None => {
+                d.remove::<String>(error_id);
+            }
+        }
+    });
+    parsed
+}
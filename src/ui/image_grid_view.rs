@@ -1,10 +1,68 @@
 use crate::app_state::AppState;
+use crate::object_store;
 use crate::persistence::{ImageMeta, NadeType};
 use egui::{CornerRadius, Sense, Ui, Vec2};
 
 use crate::app_actions::AppAction; // Added import
 use crate::services::thumbnail_service::ThumbnailServiceTrait;
+use crate::ui::strat_editor_view::StratLineupDrag;
 use log::info;
+use std::collections::HashSet;
+
+/// Colour used for a nade type's icon in the grid and its pins on the radar.
+pub fn nade_type_color(nade_type: NadeType) -> egui::Color32 {
+    match nade_type {
+        NadeType::Smoke => egui::Color32::DARK_GRAY,
+        NadeType::Flash => egui::Color32::WHITE,
+        NadeType::Molotov => egui::Color32::from_rgb(255, 69, 0),
+        NadeType::Grenade => egui::Color32::from_rgb(34, 139, 34),
+    }
+}
+
+/// Colour of the outline around selected lineups and of the selection band
+const SELECTION_COLOR: egui::Color32 = egui::Color32::from_rgb(0, 150, 255);
+
+/// The selection after Ctrl/Shift-clicking the lineup at `index` of `images`, and the lineup
+/// later Shift-clicks extend from. Ctrl toggles the lineup, Shift selects the range from the
+/// anchor to it, and Ctrl+Shift adds that range to the selection.
+fn clicked_selection(
+    selected: &HashSet<String>,
+    anchor: Option<&str>,
+    images: &[&ImageMeta],
+    index: usize,
+    modifiers: egui::Modifiers,
+) -> (Vec<String>, Option<String>) {
+    let clicked_id = images[index].id.clone();
+    let mut image_ids: Vec<String> = if modifiers.command {
+        images
+            .iter()
+            .filter(|meta| selected.contains(&meta.id))
+            .map(|meta| meta.id.clone())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let anchor_index = anchor.and_then(|anchor| images.iter().position(|meta| meta.id == anchor));
+    match anchor_index {
+        Some(anchor_index) if modifiers.shift => {
+            for meta in &images[anchor_index.min(index)..=anchor_index.max(index)] {
+                if !image_ids.contains(&meta.id) {
+                    image_ids.push(meta.id.clone());
+                }
+            }
+            (image_ids, anchor.map(str::to_string))
+        }
+        // A Shift-click without an anchor on screen starts from the clicked lineup
+        _ => {
+            if let Some(pos) = image_ids.iter().position(|id| *id == clicked_id) {
+                image_ids.remove(pos);
+            } else {
+                image_ids.push(clicked_id.clone());
+            }
+            (image_ids, Some(clicked_id))
+        }
+    }
+}
 
 /// Renders the main image grid.
 #[allow(clippy::too_many_lines)] // This function is inherently long due to UI logic
@@ -12,15 +70,19 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
     // Display image grid for app.current_map
     let data_dir_clone = app.data_dir.clone();
 
-    // Use pre-filtered and sorted images from app.current_map_images
-    // The nade_type filter is applied on top of this.
-    let filtered_images: Vec<&ImageMeta> = app
-        .current_map_images
-        .iter()
-        .filter(|meta| {
-            app.selected_nade_type.is_none() || app.selected_nade_type == Some(meta.nade_type)
-        })
-        .collect();
+    // Use pre-filtered and sorted images from app.current_map_images, or the ranked
+    // results from every map while a search is active, with the nade_type, side,
+    // throw technique and tag filters applied on top (see AppState::visible_images).
+    let searching = !app.search_query.trim().is_empty();
+    // Dragging reorders within a single map, so it is disabled for search results
+    let reorder_mode = app.reorder_mode && !searching;
+    // While the strat editor is open, lineups can be dragged into it
+    let strat_drag = app.editing_strat.is_some() && !reorder_mode;
+    // Ctrl/Shift-clicks and dragging a band over the grid select lineups for bulk actions,
+    // except while reordering, where clicks and drags move lineups
+    let selecting = !reorder_mode;
+    let band_id = egui::Id::new("grid_selection_band");
+    let filtered_images: Vec<&ImageMeta> = app.visible_images();
 
     let _available_width = ui.available_width();
 
@@ -42,13 +104,34 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
         let first_visible_row = (offset_y / row_height).floor().max(0.0) as usize;
         let last_visible_row = ((offset_y + viewport.height()) / row_height).ceil() as usize + 1;
 
+        // The selection band takes drags that no lineup takes itself. Its start is kept
+        // relative to the content, so scrolling during the drag doesn't move it.
+        let content_origin = ui.max_rect().min;
+        let band_response = selecting.then(|| ui.interact(ui.clip_rect(), band_id, Sense::drag()));
+        let band_rect = band_response.as_ref().and_then(|response| {
+            if response.drag_started()
+                && let Some(press_origin) = ui.input(|i| i.pointer.press_origin())
+            {
+                ui.data_mut(|d| d.insert_temp(band_id, press_origin - content_origin));
+            }
+            let start = ui.data(|d| d.get_temp::<Vec2>(band_id))?;
+            let pointer = ui.input(|i| i.pointer.interact_pos())?;
+            let band = egui::Rect::from_two_pos(content_origin + start, pointer);
+            // The band gets every press too; one that barely moved is a click on a lineup
+            ((response.dragged() || response.drag_stopped()) && band.size().max_elem() > 6.0)
+                .then_some(band)
+        });
+        let mut tile_rects: Vec<(usize, egui::Rect)> = Vec::with_capacity(filtered_images.len());
+
         grid.show(ui, |ui| {
             for (i, meta) in filtered_images.iter().enumerate() {
                 let current_meta_ref: &ImageMeta = meta;
 
-                let img_path_check = data_dir_clone
-                    .join(&current_meta_ref.map)
-                    .join(&current_meta_ref.filename);
+                let img_path_check = object_store::image_path(
+                    &data_dir_clone,
+                    &current_meta_ref.map,
+                    &current_meta_ref.filename,
+                );
                 if !img_path_check.exists() {
                     if i % num_columns != 0 && i != 0 { // Ensure not first item of a new row already
                         // This logic might be tricky, if an item is missing, how to fill grid?
@@ -56,7 +139,9 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
                     }
                     let display_width = app.grid_image_size;
                     let display_height = app.grid_image_size * 3.0_f32 / 4.0_f32;
-                    ui.allocate_space(egui::Vec2::new(display_width, display_height));
+                    let (_, rect_alloc) =
+                        ui.allocate_space(egui::Vec2::new(display_width, display_height));
+                    tile_rects.push((i, rect_alloc));
                     if (i + 1) % num_columns == 0 || i == filtered_images.len() - 1 {
                         ui.end_row();
                     }
@@ -71,16 +156,18 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
                         Vec2::new(display_width, display_height),
                         Sense::hover(),
                     );
+                    tile_rects.push((i, rect_alloc));
                     ui.painter().rect_filled(
                         rect_alloc,
                         CornerRadius::default(),
                         egui::Color32::from_gray(30), // Darker placeholder
                     );
                 } else {
-                    let img_path = app
-                        .data_dir
-                        .join(current_meta_ref.map.clone())
-                        .join(current_meta_ref.filename.clone());
+                    let img_path = object_store::image_path(
+                        &app.data_dir,
+                        &current_meta_ref.map,
+                        &current_meta_ref.filename,
+                    );
                     let webp_path = img_path.clone();
                     let thumb_path_key_str = webp_path.to_string_lossy().into_owned();
 
@@ -124,13 +211,14 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
                         // --- Image Rendering with Drag & Drop Support ---
 
                         // 1. Allocate space and sense interaction (hover, click, drag)
-                        let sense = if app.reorder_mode {
+                        let sense = if reorder_mode || strat_drag {
                             egui::Sense::click_and_drag()
                         } else {
                             egui::Sense::click()
                         };
                         let (rect, image_response) =
                             ui.allocate_exact_size(Vec2::new(display_width, display_height), sense);
+                        tile_rects.push((i, rect));
 
                         // 2. Determine the UV coordinates for the texture based on hover state
                         let uv_rect = if image_response.hovered() {
@@ -152,7 +240,7 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
                         let mut tint_color = egui::Color32::WHITE;
 
                         // Add visual feedback for drag & drop in reorder mode
-                        if app.reorder_mode {
+                        if reorder_mode {
                             // Check if this image is being dragged
                             let is_being_dragged = ui.memory(|mem| {
                                 mem.data
@@ -208,7 +296,7 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
                             .image(texture_handle.id(), rect, uv_rect, tint_color);
 
                         // 4. Handle click/drag events based on mode
-                        if app.reorder_mode {
+                        if reorder_mode {
                             // In reorder mode, handle drag & drop
                             if image_response.drag_started() {
                                 // Store the drag source index
@@ -237,9 +325,24 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
                             }
                         } else {
                             // Normal mode, handle clicks for detail view
-                            if image_response.clicked() {
+                            if strat_drag && image_response.drag_started() {
+                                image_response.dnd_set_drag_payload(StratLineupDrag(
+                                    current_meta_ref.id.clone(),
+                                ));
+                            }
+                            let modifiers = ui.input(|i| i.modifiers);
+                            if image_response.clicked() && (modifiers.command || modifiers.shift) {
+                                let (image_ids, anchor) = clicked_selection(
+                                    &app.selected_images,
+                                    app.selection_anchor.as_deref(),
+                                    &filtered_images,
+                                    i,
+                                    modifiers,
+                                );
+                                action_queue.push(AppAction::SelectImages { image_ids, anchor });
+                            } else if image_response.clicked() {
                                 action_queue.push(AppAction::ImageGridImageClicked(
-                                    current_meta_ref.clone(),
+                                    current_meta_ref.id.clone(),
                                 ));
                             }
                         }
@@ -263,12 +366,7 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
 
                         let icon_center_y = top_bar_rect.min.y + bar_height / 2.0_f32;
                         let icon_center_x = top_bar_rect.min.x + text_padding + icon_radius;
-                        let icon_color = match current_meta_ref.nade_type {
-                            NadeType::Smoke => egui::Color32::DARK_GRAY,
-                            NadeType::Flash => egui::Color32::WHITE,
-                            NadeType::Molotov => egui::Color32::from_rgb(255, 69, 0),
-                            NadeType::Grenade => egui::Color32::from_rgb(34, 139, 34),
-                        };
+                        let icon_color = nade_type_color(current_meta_ref.nade_type);
                         painter.circle_filled(
                             egui::pos2(icon_center_x, icon_center_y),
                             icon_radius,
@@ -280,11 +378,19 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
                             egui::Stroke::new(1.0_f32, egui::Color32::BLACK),
                         );
 
-                        let position_text_str = if current_meta_ref.position.is_empty() {
+                        let mut position_text_str = if current_meta_ref.position.is_empty() {
                             "[No Position]".to_string()
                         } else {
                             current_meta_ref.position.clone()
                         };
+                        if searching {
+                            // Results come from every map, so say which one
+                            position_text_str = format!(
+                                "{} · {}",
+                                app.map_config.label_for(&current_meta_ref.map),
+                                position_text_str
+                            );
+                        }
                         let text_color = egui::Color32::WHITE;
                         let font_id_overlay = egui::FontId::proportional(font_size_overlay);
                         let text_galley = painter.layout_no_wrap(
@@ -324,6 +430,24 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
                             font_id_overlay,
                             text_color,
                         );
+
+                        // Selected lineups, and the ones the selection band is over, get an outline
+                        if selecting
+                            && (app.selected_images.contains(&current_meta_ref.id)
+                                || band_rect.is_some_and(|band| band.intersects(image_rect)))
+                        {
+                            painter.rect_filled(
+                                image_rect,
+                                CornerRadius::ZERO,
+                                egui::Color32::from_rgba_unmultiplied(0, 150, 255, 40),
+                            );
+                            painter.rect_stroke(
+                                image_rect,
+                                CornerRadius::ZERO,
+                                egui::Stroke::new(3.0, SELECTION_COLOR),
+                                egui::StrokeKind::Inside,
+                            );
+                        }
                         loaded_thumbnail = true;
                     }
                     if !loaded_thumbnail {
@@ -333,6 +457,7 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
                             Vec2::new(display_width, display_height),
                             Sense::hover(),
                         );
+                        tile_rects.push((i, rect_alloc));
                         ui.painter().rect_filled(
                             rect_alloc,
                             CornerRadius::default(),
@@ -345,14 +470,84 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
                 }
             }
         });
+
+        if let (Some(response), Some(band)) = (&band_response, band_rect) {
+            ui.painter().rect(
+                band,
+                CornerRadius::ZERO,
+                egui::Color32::from_rgba_unmultiplied(0, 150, 255, 30),
+                egui::Stroke::new(1.0, SELECTION_COLOR),
+                egui::StrokeKind::Inside,
+            );
+            if response.drag_stopped() {
+                // Ctrl or Shift adds to the selection; a plain band replaces it
+                let modifiers = ui.input(|i| i.modifiers);
+                let mut image_ids: Vec<String> = if modifiers.command || modifiers.shift {
+                    app.selected_visible_images()
+                        .iter()
+                        .map(|meta| meta.id.clone())
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                let banded: Vec<&String> = tile_rects
+                    .iter()
+                    .filter(|(_, rect)| band.intersects(*rect))
+                    .map(|(idx, _)| &filtered_images[*idx].id)
+                    .collect();
+                for id in &banded {
+                    if !image_ids.contains(id) {
+                        image_ids.push((*id).clone());
+                    }
+                }
+                action_queue.push(AppAction::SelectImages {
+                    image_ids,
+                    anchor: banded.first().map(|id| (*id).clone()),
+                });
+            }
+        }
+        if band_response.is_some_and(|response| response.drag_stopped()) {
+            ui.data_mut(|d| d.remove::<Vec2>(band_id));
+        }
     });
 
     if filtered_images.is_empty() {
-        ui.label("[No images uploaded for this filter]");
+        if searching {
+            ui.label(format!(
+                "[No lineups match \"{}\"]",
+                app.search_query.trim()
+            ));
+        } else {
+            ui.label("[No images uploaded for this filter]");
+        }
+    }
+
+    // Name the lineup being dragged into the strat editor next to the cursor
+    if strat_drag
+        && let Some(payload) = egui::DragAndDrop::payload::<StratLineupDrag>(ui.ctx())
+        && let Some(cursor_pos) = ui.input(|i| i.pointer.interact_pos())
+        && let Some(dragged_image) = filtered_images.iter().find(|meta| meta.id == payload.0)
+    {
+        let painter = ui.ctx().layer_painter(egui::LayerId::new(
+            egui::Order::Tooltip,
+            egui::Id::new("strat_drag_preview"),
+        ));
+        let text_pos = cursor_pos + egui::Vec2::new(14.0, 14.0);
+        let galley = painter.layout_no_wrap(
+            format!("➕ {}", dragged_image.position),
+            egui::FontId::proportional(13.0),
+            egui::Color32::WHITE,
+        );
+        painter.rect_filled(
+            egui::Rect::from_min_size(text_pos, galley.size()).expand(4.0),
+            CornerRadius::same(4),
+            egui::Color32::from_rgba_unmultiplied(0, 0, 0, 200),
+        );
+        painter.galley(text_pos, galley, egui::Color32::WHITE);
     }
 
     // Draw drag preview if something is being dragged in reorder mode
-    if app.reorder_mode {
+    if reorder_mode {
         if let Some(drag_source_idx) =
             ui.memory(|mem| mem.data.get_temp::<usize>(egui::Id::new("drag_source")))
         {
@@ -375,9 +570,11 @@ pub fn show_image_grid(app: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<A
                         );
 
                         // Try to get the thumbnail for preview
-                        let webp_path = data_dir_clone
-                            .join(&dragged_image.map)
-                            .join(&dragged_image.filename);
+                        let webp_path = object_store::image_path(
+                            &data_dir_clone,
+                            &dragged_image.map,
+                            &dragged_image.filename,
+                        );
                         let thumb_path_key_str = webp_path.to_string_lossy().to_string();
 
                         if let Some((texture_handle, _)) = app
@@ -0,0 +1,165 @@
+use crate::app_actions::AppAction;
+use crate::app_state::AppState;
+use crate::services::integrity_service::{IntegrityIssue, RepairAction};
+use eframe::egui;
+use rfd::FileDialog;
+
+/// Renders the library check window for `AppState::integrity_report`, listing every
+/// mismatch between the manifest and the data directory with buttons to fix it.
+///
+/// Repairs push `AppAction::RepairIntegrityIssue`; deleting asks for confirmation inline
+/// first. Closing the window pushes `AppAction::CloseIntegrityCheck`.
+pub fn show_integrity_check(
+    app_state: &mut AppState,
+    ctx: &egui::Context,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let mut open = app_state.show_integrity_check;
+    // The issue waiting for delete confirmation, if any
+    let confirm_id = egui::Id::new("integrity_confirm_delete");
+    let mut confirm_delete: Option<IntegrityIssue> = ctx.data_mut(|d| d.get_temp(confirm_id));
+
+    egui::Window::new("Library Check")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            let issues = match &app_state.integrity_report {
+                None => {
+                    ui.horizontal(|ui_h| {
+                        ui_h.spinner();
+                        ui_h.label("Checking the library...");
+                    });
+                    return;
+                }
+                Some(Err(error)) => {
+                    ui.colored_label(egui::Color32::RED, format!("The check failed: {}", error));
+                    if ui.button("Check Again").clicked() {
+                        action_queue.push(AppAction::ShowIntegrityCheck);
+                    }
+                    return;
+                }
+                Some(Ok(issues)) => issues,
+            };
+            ui.horizontal(|ui_h| {
+                if issues.is_empty() {
+                    ui_h.label("No problems found. The library matches the files on disk.");
+                } else {
+                    ui_h.label(format!("{} problems found.", issues.len()));
+                }
+                if ui_h.button("Check Again").clicked() {
+                    action_queue.push(AppAction::ShowIntegrityCheck);
+                }
+            });
+            if issues.is_empty() {
+                return;
+            }
+            ui.separator();
+
+            egui::ScrollArea::vertical()
+                .max_height(400.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("integrity_issue_grid")
+                        .num_columns(2)
+                        .spacing([10.0, 6.0])
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for issue in issues {
+                                ui.label(issue.describe(&app_state.image_manifest));
+                                ui.horizontal(|ui_h| {
+                                    if confirm_delete.as_ref() == Some(issue) {
+                                        ui_h.label("Delete for good?");
+                                        if ui_h.button("Yes").clicked() {
+                                            action_queue.push(AppAction::RepairIntegrityIssue(
+                                                issue.clone(),
+                                                RepairAction::Delete,
+                                            ));
+                                            confirm_delete = None;
+                                        }
+                                        if ui_h.button("No").clicked() {
+                                            confirm_delete = None;
+                                        }
+                                        return;
+                                    }
+                                    show_repair_buttons(
+                                        ui_h,
+                                        issue,
+                                        &mut confirm_delete,
+                                        action_queue,
+                                    );
+                                });
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+
+    ctx.data_mut(|d| match confirm_delete {
+        Some(issue) if open => d.insert_temp(confirm_id, issue),
+        _ => {
+            d.remove::<IntegrityIssue>(confirm_id);
+        }
+    });
+    if !open {
+        action_queue.push(AppAction::CloseIntegrityCheck);
+    }
+}
+
+/// The repairs that apply to `issue`. Delete only asks for confirmation here.
+fn show_repair_buttons(
+    ui: &mut egui::Ui,
+    issue: &IntegrityIssue,
+    confirm_delete: &mut Option<IntegrityIssue>,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let repair = |action: RepairAction| AppAction::RepairIntegrityIssue(issue.clone(), action);
+    match issue {
+        IntegrityIssue::MissingFile { .. } | IntegrityIssue::UndecodableImage { .. } => {
+            if ui
+                .button("Re-link...")
+                .on_hover_text("Pick the image this lineup step should use")
+                .clicked()
+                && let Some(path) = FileDialog::new()
+                    .add_filter("Images", &["webp", "png", "jpg", "jpeg"])
+                    .pick_file()
+            {
+                action_queue.push(repair(RepairAction::Relink(path)));
+            }
+            if ui
+                .button("Remove")
+                .on_hover_text("Remove this step; a lineup without steps is removed")
+                .clicked()
+            {
+                *confirm_delete = Some(issue.clone());
+            }
+        }
+        IntegrityIssue::OrphanFile { .. } => {
+            if ui
+                .button("Adopt")
+                .on_hover_text("Add it as an untitled lineup")
+                .clicked()
+            {
+                action_queue.push(repair(RepairAction::Adopt));
+            }
+            if ui.button("Delete").clicked() {
+                *confirm_delete = Some(issue.clone());
+            }
+        }
+        IntegrityIssue::LeftoverTempFile { .. } | IntegrityIssue::StaleThumbnail { .. } => {
+            // Nothing refers to these, so no confirmation is needed
+            if ui.button("Delete").clicked() {
+                action_queue.push(repair(RepairAction::Delete));
+            }
+        }
+        IntegrityIssue::BadOrder { .. } => {
+            if ui
+                .button("Renumber")
+                .on_hover_text("Keep the current grid order and number it 1, 2, 3, ...")
+                .clicked()
+            {
+                action_queue.push(repair(RepairAction::Renumber));
+            }
+        }
+    }
+}
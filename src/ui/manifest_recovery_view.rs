@@ -0,0 +1,79 @@
+use crate::app_actions::AppAction;
+use crate::app_state::AppState;
+use crate::services::persistence_service::ManifestRecoveryChoice;
+use chrono::{DateTime, Local};
+use eframe::egui;
+
+/// Renders the library recovery dialog for `AppState::manifest_recovery`.
+///
+/// Shown when `manifest.json` failed to load. It blocks the rest of the app until the
+/// user picks the newest backup, the lineups salvaged from the damaged file, or an empty
+/// library, which pushes `AppAction::ResolveManifestRecovery`.
+pub fn show_manifest_recovery(
+    app_state: &mut AppState,
+    ctx: &egui::Context,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let Some(recovery) = &app_state.manifest_recovery else {
+        return;
+    };
+
+    egui::Modal::new(egui::Id::new("manifest_recovery_modal")).show(ctx, |ui| {
+        ui.set_max_width(460.0);
+        ui.heading("Library Recovery");
+        ui.label("Your lineup library couldn't be loaded, so it hasn't been opened. Nothing has been deleted.");
+        ui.colored_label(egui::Color32::RED, &recovery.error);
+        match &recovery.quarantined {
+            Some(path) => {
+                ui.label(format!("The damaged file was kept as {}.", path.display()));
+            }
+            None => {
+                ui.label("The damaged file couldn't be moved aside and will be replaced by your choice.");
+            }
+        }
+        ui.add_space(5.0);
+        ui.label("Continue with:");
+
+        if let Some((backup, manifest)) = &recovery.backup {
+            let saved_at = backup.saved_at.map_or_else(
+                || "an unknown time".to_string(),
+                |time| {
+                    DateTime::<Local>::from(time)
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string()
+                },
+            );
+            let count: usize = manifest.images.values().map(Vec::len).sum();
+            if ui
+                .button(format!(
+                    "Restore the backup from {} ({} lineups)",
+                    saved_at, count
+                ))
+                .clicked()
+            {
+                action_queue.push(AppAction::ResolveManifestRecovery(
+                    ManifestRecoveryChoice::Backup,
+                ));
+            }
+        }
+        let salvaged_count: usize = recovery.salvaged.images.values().map(Vec::len).sum();
+        if salvaged_count > 0
+            && ui
+                .button(format!(
+                    "Keep the {} lineups and {} strats still readable in the damaged file",
+                    salvaged_count,
+                    recovery.salvaged.strats.len()
+                ))
+                .clicked()
+        {
+            action_queue.push(AppAction::ResolveManifestRecovery(
+                ManifestRecoveryChoice::Salvaged,
+            ));
+        }
+        if ui.button("Start with an empty library").clicked() {
+            action_queue.push(AppAction::ResolveManifestRecovery(
+                ManifestRecoveryChoice::Empty,
+            ));
+        }
+    });
+}
@@ -0,0 +1,198 @@
+use crate::app_actions::AppAction;
+use crate::app_state::AppState;
+use eframe::egui;
+
+/// Renders the map manager window: reorder, hide, relabel and add maps.
+///
+/// Edits a copy of the map list and pushes `AppAction::UpdateMapConfig` when something
+/// changed. Labels only change what the UI shows; the map name stays the folder and
+/// manifest key. Closing the window pushes `AppAction::CloseMapManager`.
+pub fn show_map_manager(
+    app_state: &mut AppState,
+    ctx: &egui::Context,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let mut open = app_state.show_map_manager;
+    let mut map_config = app_state.map_config.clone();
+
+    egui::Window::new("Manage Maps")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label("Hidden maps keep their lineups and can be shown again at any time.");
+            ui.add_space(5.0);
+
+            let map_count = map_config.maps.len();
+            let mut move_request = None;
+            // Targets for moving a whole map's lineups, e.g. after uploading to the wrong map
+            let move_targets: Vec<(String, String)> = map_config
+                .maps
+                .iter()
+                .map(|entry| (entry.name.clone(), entry.label().to_string()))
+                .collect();
+            egui::ScrollArea::vertical()
+                .max_height(400.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("map_manager_grid")
+                        .num_columns(6)
+                        .spacing([10.0, 6.0])
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (index, entry) in map_config.maps.iter_mut().enumerate() {
+                                ui.horizontal(|ui_h| {
+                                    if ui_h
+                                        .add_enabled(index > 0, egui::Button::new("⬆").small())
+                                        .clicked()
+                                    {
+                                        move_request = Some((index, index - 1));
+                                    }
+                                    if ui_h
+                                        .add_enabled(
+                                            index + 1 < map_count,
+                                            egui::Button::new("⬇").small(),
+                                        )
+                                        .clicked()
+                                    {
+                                        move_request = Some((index, index + 1));
+                                    }
+                                });
+
+                                let mut visible = !entry.hidden;
+                                if ui.checkbox(&mut visible, "Show").changed() {
+                                    entry.hidden = !visible;
+                                }
+
+                                let lineup_count = app_state
+                                    .image_manifest
+                                    .images
+                                    .get(&entry.name)
+                                    .map_or(0, Vec::len);
+                                let mut name_text = format!("{} ({})", entry.name, lineup_count);
+                                if let Some(workshop_id) = entry.workshop_id {
+                                    name_text.push_str(&format!(" · workshop {}", workshop_id));
+                                }
+                                ui.label(name_text);
+
+                                // Pending label text lives in egui memory, keyed by the map
+                                let input_id = egui::Id::new("map_manager_label").with(&entry.name);
+                                let current_label = entry.display_name.clone().unwrap_or_default();
+                                let mut new_label: String = ui.data_mut(|d| {
+                                    d.get_temp(input_id)
+                                        .unwrap_or_else(|| current_label.clone())
+                                });
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut new_label)
+                                        .hint_text(&entry.name)
+                                        .desired_width(140.0),
+                                );
+                                if ui
+                                    .add_enabled(
+                                        new_label.trim() != current_label,
+                                        egui::Button::new("Rename"),
+                                    )
+                                    .on_hover_text("Leave empty to show the map name")
+                                    .clicked()
+                                {
+                                    let label = new_label.trim();
+                                    entry.display_name =
+                                        (!label.is_empty()).then(|| label.to_string());
+                                    ui.data_mut(|d| d.remove::<String>(input_id));
+                                } else {
+                                    ui.data_mut(|d| d.insert_temp(input_id, new_label));
+                                }
+
+                                if lineup_count > 0 {
+                                    ui.menu_button("Move lineups to", |ui_menu| {
+                                        for (name, label) in &move_targets {
+                                            if *name != entry.name
+                                                && ui_menu.button(label).clicked()
+                                            {
+                                                let image_ids = app_state.image_manifest.images
+                                                    [&entry.name]
+                                                    .iter()
+                                                    .map(|img| img.id.clone())
+                                                    .collect();
+                                                action_queue.push(AppAction::MoveImagesToMap {
+                                                    image_ids,
+                                                    map: name.clone(),
+                                                    copy: false,
+                                                });
+                                                ui_menu.close_menu();
+                                            }
+                                        }
+                                    });
+                                } else {
+                                    ui.label("");
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            if let Some((from_index, to_index)) = move_request {
+                map_config.move_map(from_index, to_index);
+            }
+
+            ui.separator();
+            ui.label("Add a map (e.g. a new active-duty, workshop or scrim map):");
+            let name_id = egui::Id::new("map_manager_new_name");
+            let workshop_id_id = egui::Id::new("map_manager_new_workshop_id");
+            let error_id = egui::Id::new("map_manager_add_error");
+            let mut new_name: String = ui.data_mut(|d| d.get_temp(name_id).unwrap_or_default());
+            let mut new_workshop_id: String =
+                ui.data_mut(|d| d.get_temp(workshop_id_id).unwrap_or_default());
+            let mut add_error: Option<String> = ui.data_mut(|d| d.get_temp(error_id));
+
+            ui.horizontal(|ui_h| {
+                ui_h.add(
+                    egui::TextEdit::singleline(&mut new_name)
+                        .hint_text("e.g., de_thera")
+                        .desired_width(140.0),
+                );
+                ui_h.add(
+                    egui::TextEdit::singleline(&mut new_workshop_id)
+                        .hint_text("Workshop ID (optional)")
+                        .desired_width(160.0),
+                );
+                if ui_h.button("Add").clicked() {
+                    let workshop_id = match new_workshop_id.trim() {
+                        "" => Ok(None),
+                        id => id
+                            .parse::<u64>()
+                            .map(Some)
+                            .map_err(|_| format!("'{}' is not a valid workshop id.", id)),
+                    };
+                    match workshop_id.and_then(|id| map_config.add_map(&new_name, id)) {
+                        Ok(()) => {
+                            new_name.clear();
+                            new_workshop_id.clear();
+                            add_error = None;
+                        }
+                        Err(e) => add_error = Some(e),
+                    }
+                }
+            });
+            if let Some(error) = &add_error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+
+            ui.data_mut(|d| {
+                d.insert_temp(name_id, new_name);
+                d.insert_temp(workshop_id_id, new_workshop_id);
+                match add_error {
+                    Some(error) => d.insert_temp(error_id, error),
+                    None => {
+                        d.remove::<String>(error_id);
+                    }
+                }
+            });
+        });
+
+    if map_config != app_state.map_config {
+        action_queue.push(AppAction::UpdateMapConfig(map_config));
+    }
+    if !open {
+        action_queue.push(AppAction::CloseMapManager);
+    }
+}
@@ -1,9 +1,23 @@
+pub mod backup_view;
 pub mod delete_confirmation_view;
 pub mod detail_view;
 pub mod edit_view;
+pub mod getpos_input_view;
 pub mod image_grid_view;
+pub mod integrity_view;
+pub mod manifest_recovery_view;
+pub mod map_manager_view;
+pub mod practice_config_view;
 pub mod progress_indicator_view;
+pub mod radar_view;
+pub mod selection_bar_view;
 pub mod sharing_view;
+pub mod strat_editor_view;
+pub mod strat_panel_view;
+pub mod strat_playback_view;
+pub mod tag_editor_view;
+pub mod tag_manager_view;
 pub mod top_bar_view;
+pub mod trash_view;
 pub mod update_dialog;
 pub mod upload_modal_view;
@@ -0,0 +1,129 @@
+use crate::app_actions::AppAction;
+use crate::app_state::AppState;
+use crate::services::practice_config_service::PracticeConfigService;
+use eframe::egui;
+use rfd::FileDialog;
+
+/// Renders the practice config window: pick lineups of the current map and save them as
+/// a CS2 `.cfg` with practice settings and one teleport alias per lineup.
+///
+/// The selection lives in `AppState::practice_config_selection`. Lineups without a throw
+/// position can't be teleported to, so they can't be picked. Closing the window pushes
+/// `AppAction::ClosePracticeConfig`.
+pub fn show_practice_config(
+    app_state: &mut AppState,
+    ctx: &egui::Context,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let Some(mut selection) = app_state.practice_config_selection.clone() else {
+        return;
+    };
+    let mut open = true;
+    let map_name = app_state.current_map.clone();
+    let map_label = app_state.map_config.label_for(&map_name).to_string();
+    let status_id = egui::Id::new("practice_config_status");
+
+    egui::Window::new(format!("Practice Config: {}", map_label))
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label("Each picked lineup gets a console alias (nx1, nx2, ...) that teleports to its throw position. Type nx_list to see them all.");
+            ui.add_space(5.0);
+
+            let teleport_ids: Vec<String> = app_state
+                .current_map_images
+                .iter()
+                .filter(|meta| meta.throw_pos.is_some())
+                .map(|meta| meta.id.clone())
+                .collect();
+            ui.horizontal(|ui_h| {
+                if ui_h.button("Select All").clicked() {
+                    selection = teleport_ids.clone();
+                }
+                if ui_h.button("Select None").clicked() {
+                    selection.clear();
+                }
+            });
+
+            egui::ScrollArea::vertical()
+                .max_height(350.0)
+                .show(ui, |ui| {
+                    if app_state.current_map_images.is_empty() {
+                        ui.weak("No lineups on this map yet.");
+                    }
+                    for meta in &app_state.current_map_images {
+                        let mut picked = selection.contains(&meta.id);
+                        let label = format!("{} ({:?})", meta.position, meta.nade_type);
+                        let response = ui.add_enabled(
+                            meta.throw_pos.is_some(),
+                            egui::Checkbox::new(&mut picked, label),
+                        );
+                        if meta.throw_pos.is_none() {
+                            response.on_disabled_hover_text(
+                                "No throw position. Paste getpos output in the edit modal.",
+                            );
+                        } else if response.changed() {
+                            if picked {
+                                selection.push(meta.id.clone());
+                            } else {
+                                selection.retain(|id| id != &meta.id);
+                            }
+                        }
+                    }
+                });
+
+            ui.separator();
+            // Keep the manifest order in the file, whatever order the lineups were picked in
+            let lineups: Vec<_> = app_state
+                .current_map_images
+                .iter()
+                .filter(|meta| selection.contains(&meta.id))
+                .collect();
+            if ui
+                .add_enabled(
+                    !lineups.is_empty(),
+                    egui::Button::new(format!("Save .cfg ({} lineups)...", lineups.len())),
+                )
+                .clicked()
+                && let Some(path) = FileDialog::new()
+                    .add_filter("CS2 Config", &["cfg"])
+                    .set_file_name(format!("nadex_{}.cfg", map_name))
+                    .save_file()
+            {
+                let status = match PracticeConfigService::new()
+                    .write_config(&path, &map_label, &lineups)
+                {
+                    Ok(count) => Ok(format!(
+                        "Saved {} lineups to {}. Copy it to the game's cfg folder and run: exec {}",
+                        count,
+                        path.display(),
+                        path.file_stem().unwrap_or_default().to_string_lossy()
+                    )),
+                    Err(e) => {
+                        log::error!("Failed to write practice config {:?}: {}", path, e);
+                        Err(format!("Failed to save practice config: {}", e))
+                    }
+                };
+                ui.data_mut(|d| d.insert_temp(status_id, status));
+            }
+
+            match ui.data(|d| d.get_temp::<Result<String, String>>(status_id)) {
+                Some(Ok(message)) => {
+                    ui.colored_label(egui::Color32::GREEN, message);
+                }
+                Some(Err(error)) => {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                None => {}
+            }
+        });
+
+    if open {
+        app_state.practice_config_selection = Some(selection);
+    } else {
+        ctx.data_mut(|d| d.remove::<Result<String, String>>(status_id));
+        action_queue.push(AppAction::ClosePracticeConfig);
+    }
+}
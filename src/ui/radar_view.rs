@@ -0,0 +1,256 @@
+use crate::app_actions::AppAction;
+use crate::app_state::AppState;
+use crate::persistence::{RadarOverview, WorldPos};
+use crate::ui::image_grid_view::nade_type_color;
+use egui::{Color32, Id, Rect, Sense, Stroke, Ui, Vec2};
+use rfd::FileDialog;
+
+/// Radars without an image are drawn as an empty square of this many pixels per side,
+/// the size of the game's own radar images.
+const DEFAULT_RADAR_SIZE: f32 = 1024.0;
+
+/// Renders the radar panel for the current map: the radar image with a pin for every
+/// visible lineup that has both throw and landing coordinates, plus the radar settings.
+///
+/// Clicking a pin pushes `AppAction::ImageGridImageClicked`, which opens the detail modal.
+pub fn show_radar_panel(app_state: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<AppAction>) {
+    let map_name = app_state.current_map.clone();
+    let Some(radar) = app_state
+        .map_config
+        .find(&map_name)
+        .map(|entry| entry.radar.clone())
+    else {
+        ui.label("Add this map in Manage Maps to set up its radar.");
+        return;
+    };
+
+    ui.heading(format!(
+        "Radar: {}",
+        app_state.map_config.label_for(&map_name)
+    ));
+    show_radar_settings(app_state, ui, &map_name, &radar, action_queue);
+    ui.separator();
+
+    // Load (or reuse) the radar texture
+    let mut texture = None;
+    if let Some(filename) = &radar.image {
+        let path = app_state.persistence_service.radar_image_path(filename);
+        let error_id = Id::new("radar_load_error").with(&path);
+        let cached = app_state
+            .radar_texture
+            .as_ref()
+            .filter(|(cached_path, _)| *cached_path == path)
+            .map(|(_, handle)| handle.clone());
+        if let Some(handle) = cached {
+            texture = Some(handle);
+        } else if let Some(error) = ui.data(|d| d.get_temp::<String>(error_id)) {
+            ui.colored_label(Color32::RED, error);
+        } else {
+            match image::open(&path) {
+                Ok(img) => {
+                    let color_image = egui::ColorImage::from_rgba_unmultiplied(
+                        [img.width() as usize, img.height() as usize],
+                        img.to_rgba8().as_flat_samples().as_slice(),
+                    );
+                    let handle = ui.ctx().load_texture(
+                        format!("radar_{}", filename),
+                        color_image,
+                        egui::TextureOptions::default(),
+                    );
+                    app_state.radar_texture = Some((path, handle.clone()));
+                    texture = Some(handle);
+                }
+                Err(e) => {
+                    log::error!("Failed to load radar image {:?}: {}", path, e);
+                    // Remember the failure so the file isn't re-read every frame
+                    let error = format!("Failed to load radar image: {}", e);
+                    ui.data_mut(|d| d.insert_temp(error_id, error));
+                }
+            }
+        }
+    } else {
+        ui.weak("No radar image yet. Pins are drawn on an empty radar.");
+    }
+
+    let (radar_width, radar_height) = texture
+        .as_ref()
+        .map_or((DEFAULT_RADAR_SIZE, DEFAULT_RADAR_SIZE), |handle| {
+            (handle.size()[0] as f32, handle.size()[1] as f32)
+        });
+    let side = ui.available_width().min(ui.available_height()).max(100.0);
+    let (rect, _) = ui.allocate_exact_size(Vec2::splat(side), Sense::hover());
+    let painter = ui.painter_at(rect);
+    match &texture {
+        Some(handle) => {
+            painter.image(
+                handle.id(),
+                rect,
+                Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                Color32::WHITE,
+            );
+        }
+        None => {
+            painter.rect_filled(rect, 0.0, Color32::from_gray(30));
+        }
+    }
+
+    let to_screen = |pos: WorldPos| {
+        let (radar_x, radar_y) = radar.world_to_radar(pos);
+        rect.min + Vec2::new(radar_x / radar_width, radar_y / radar_height) * rect.size()
+    };
+
+    let selected_id = app_state
+        .selected_image_for_detail
+        .as_ref()
+        .map(|meta| meta.id.clone());
+    let mut without_coordinates = 0;
+    for meta in app_state.visible_images() {
+        if meta.map != map_name {
+            continue; // Search results can come from other maps
+        }
+        let (Some(throw_pos), Some(landing_pos)) = (meta.throw_pos, meta.landing_pos) else {
+            without_coordinates += 1;
+            continue;
+        };
+        let throw_point = to_screen(throw_pos);
+        let landing_point = to_screen(landing_pos);
+        let color = nade_type_color(meta.nade_type);
+        let outline = if selected_id.as_ref() == Some(&meta.id) {
+            Stroke::new(2.0, Color32::YELLOW)
+        } else {
+            Stroke::new(1.0, Color32::BLACK)
+        };
+
+        painter.line_segment([throw_point, landing_point], Stroke::new(2.0, color));
+        painter.circle(throw_point, 4.0, color, outline);
+        painter.circle(landing_point, 7.0, color, outline);
+
+        for (point, what) in [(throw_point, "throw"), (landing_point, "landing")] {
+            let pin_response = ui
+                .interact(
+                    Rect::from_center_size(point, Vec2::splat(16.0)),
+                    Id::new("radar_pin").with(&meta.id).with(what),
+                    Sense::click(),
+                )
+                .on_hover_text(format!("{} ({})", meta.position, what));
+            if pin_response.clicked() {
+                action_queue.push(AppAction::ImageGridImageClicked(meta.id.clone()));
+            }
+        }
+    }
+
+    if without_coordinates > 0 {
+        ui.weak(format!(
+            "{} lineup(s) have no throw and landing coordinates yet. Add them in the edit modal.",
+            without_coordinates
+        ));
+    }
+}
+
+/// Radar image picker and overview parameters. Parameter edits are kept in egui memory
+/// until applied, so dragging a value doesn't rewrite `maps.json` every frame.
+fn show_radar_settings(
+    app_state: &AppState,
+    ui: &mut Ui,
+    map_name: &str,
+    radar: &RadarOverview,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let pending_id = Id::new("radar_settings_pending").with(map_name);
+    let error_id = Id::new("radar_settings_error").with(map_name);
+    let mut pending: RadarOverview =
+        ui.data_mut(|d| d.get_temp(pending_id).unwrap_or_else(|| radar.clone()));
+    let mut error: Option<String> = ui.data_mut(|d| d.get_temp(error_id));
+
+    egui::CollapsingHeader::new("Radar Settings")
+        .id_salt(("radar_settings", map_name))
+        .show(ui, |ui| {
+            ui.horizontal(|ui_h| {
+                if ui_h.button("Choose Radar Image...").clicked()
+                    && let Some(path) = FileDialog::new()
+                        .add_filter("Images", &["png", "jpg", "jpeg", "webp"])
+                        .pick_file()
+                {
+                    action_queue.push(AppAction::SetRadarImage {
+                        map_name: map_name.to_string(),
+                        path,
+                    });
+                }
+                if ui_h
+                    .button("Load Overview File...")
+                    .on_hover_text("Read pos_x, pos_y and scale from the game's overview .txt")
+                    .clicked()
+                    && let Some(path) = FileDialog::new()
+                        .add_filter("Overview", &["txt"])
+                        .pick_file()
+                {
+                    match std::fs::read_to_string(&path)
+                        .ok()
+                        .and_then(|text| RadarOverview::parse_overview(&text))
+                    {
+                        Some((pos_x, pos_y, scale)) => {
+                            pending.pos_x = pos_x;
+                            pending.pos_y = pos_y;
+                            pending.scale = scale;
+                            error = None;
+                        }
+                        None => {
+                            error = Some(format!(
+                                "No pos_x, pos_y and scale found in {}",
+                                path.display()
+                            ));
+                        }
+                    }
+                }
+            });
+
+            egui::Grid::new(("radar_settings_grid", map_name))
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("pos_x:");
+                    ui.add(egui::DragValue::new(&mut pending.pos_x).speed(1.0));
+                    ui.end_row();
+                    ui.label("pos_y:");
+                    ui.add(egui::DragValue::new(&mut pending.pos_y).speed(1.0));
+                    ui.end_row();
+                    ui.label("scale:");
+                    ui.add(
+                        egui::DragValue::new(&mut pending.scale)
+                            .speed(0.01)
+                            .range(0.01..=100.0),
+                    );
+                    ui.end_row();
+                });
+
+            if let Some(error) = &error {
+                ui.colored_label(Color32::RED, error);
+            }
+            let changed = pending.pos_x != radar.pos_x
+                || pending.pos_y != radar.pos_y
+                || pending.scale != radar.scale;
+            if ui
+                .add_enabled(changed, egui::Button::new("Apply"))
+                .clicked()
+            {
+                let mut map_config = app_state.map_config.clone();
+                if let Some(entry) = map_config.find_mut(map_name) {
+                    entry.radar.pos_x = pending.pos_x;
+                    entry.radar.pos_y = pending.pos_y;
+                    entry.radar.scale = pending.scale;
+                    action_queue.push(AppAction::UpdateMapConfig(map_config));
+                }
+            }
+        });
+
+    // The image is changed through an action, so keep it in sync with the saved config
+    pending.image = radar.image.clone();
+    ui.data_mut(|d| {
+        d.insert_temp(pending_id, pending);
+        match error {
+            Some(error) => d.insert_temp(error_id, error),
+            None => {
+                d.remove::<String>(error_id);
+            }
+        }
+    });
+}
@@ -0,0 +1,149 @@
+use crate::app_actions::AppAction;
+use crate::app_state::AppState;
+use crate::persistence::{NadeType, Side, normalize_tag};
+use crate::services::image_service::BulkEdit;
+use eframe::egui;
+use strum::IntoEnumIterator;
+
+/// Renders the bulk action bar over the grid while lineups are selected. Every action
+/// applies to `AppState::selected_visible_images`: setting the nade type or side, adding or
+/// removing a tag, moving them to another map, exporting them, or deleting them.
+///
+/// Deleting asks for confirmation inline before pushing `AppAction::DeleteImages`.
+pub fn show_selection_bar(
+    app_state: &AppState,
+    ui: &mut egui::Ui,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let selected = app_state.selected_visible_images();
+    if selected.is_empty() {
+        return;
+    }
+    let image_ids: Vec<String> = selected.iter().map(|meta| meta.id.clone()).collect();
+    let tag_id = egui::Id::new("selection_bar_tag");
+    let confirm_id = egui::Id::new("selection_bar_confirm_delete");
+    let mut tag: String = ui.data_mut(|d| d.get_temp(tag_id)).unwrap_or_default();
+    let mut confirm_delete: bool = ui.data_mut(|d| d.get_temp(confirm_id)).unwrap_or(false);
+
+    ui.horizontal_wrapped(|ui_h| {
+        ui_h.label(format!("{} selected", image_ids.len()));
+        ui_h.separator();
+
+        egui::ComboBox::from_id_salt("selection_bar_nade_type")
+            .selected_text("Set type")
+            .show_ui(ui_h, |ui_combo| {
+                for nade_type in NadeType::iter() {
+                    if ui_combo
+                        .selectable_label(false, format!("{:?}", nade_type))
+                        .clicked()
+                    {
+                        action_queue.push(AppAction::BulkEditImages {
+                            image_ids: image_ids.clone(),
+                            edit: BulkEdit::NadeType(nade_type),
+                        });
+                    }
+                }
+            });
+        egui::ComboBox::from_id_salt("selection_bar_side")
+            .selected_text("Set side")
+            .show_ui(ui_h, |ui_combo| {
+                for side in Side::iter() {
+                    if ui_combo
+                        .selectable_label(false, format!("{:?}", side))
+                        .clicked()
+                    {
+                        action_queue.push(AppAction::BulkEditImages {
+                            image_ids: image_ids.clone(),
+                            edit: BulkEdit::Side(side),
+                        });
+                    }
+                }
+            });
+
+        ui_h.add(
+            egui::TextEdit::singleline(&mut tag)
+                .hint_text("Tag")
+                .desired_width(100.0),
+        );
+        let has_tag = normalize_tag(&tag).is_some();
+        if ui_h
+            .add_enabled(has_tag, egui::Button::new("Add tag"))
+            .clicked()
+        {
+            action_queue.push(AppAction::BulkEditImages {
+                image_ids: image_ids.clone(),
+                edit: BulkEdit::AddTag(tag.clone()),
+            });
+            tag.clear();
+        }
+        if ui_h
+            .add_enabled(has_tag, egui::Button::new("Remove tag"))
+            .clicked()
+        {
+            action_queue.push(AppAction::BulkEditImages {
+                image_ids: image_ids.clone(),
+                edit: BulkEdit::RemoveTag(tag.clone()),
+            });
+            tag.clear();
+        }
+        ui_h.separator();
+
+        ui_h.menu_button("Move to map", |ui_menu| {
+            for entry in app_state.map_config.visible_maps() {
+                // Nothing to move when every selected lineup is on the map already
+                if selected.iter().all(|meta| meta.map == entry.name) {
+                    continue;
+                }
+                if ui_menu
+                    .button(app_state.map_config.label_for(&entry.name))
+                    .clicked()
+                {
+                    action_queue.push(AppAction::MoveImagesToMap {
+                        image_ids: image_ids.clone(),
+                        map: entry.name.clone(),
+                        copy: false,
+                    });
+                    ui_menu.close_menu();
+                }
+            }
+        });
+        if ui_h
+            .button("Export...")
+            .on_hover_text("Share the selected lineups as a .nadex package")
+            .clicked()
+        {
+            action_queue.push(AppAction::ExportSelection);
+        }
+        ui_h.separator();
+
+        if confirm_delete {
+            ui_h.label(format!("Move {} lineups to the trash?", image_ids.len()));
+            if ui_h.button("Yes").clicked() {
+                action_queue.push(AppAction::DeleteImages(image_ids.clone()));
+                confirm_delete = false;
+            }
+            if ui_h.button("No").clicked() {
+                confirm_delete = false;
+            }
+        } else if ui_h.button("Delete").clicked() {
+            confirm_delete = true;
+        }
+        ui_h.separator();
+
+        if ui_h
+            .button("Clear selection")
+            .on_hover_text("Esc")
+            .clicked()
+        {
+            action_queue.push(AppAction::SelectImages {
+                image_ids: Vec::new(),
+                anchor: None,
+            });
+        }
+    });
+
+    ui.data_mut(|d| {
+        d.insert_temp(tag_id, tag);
+        d.insert_temp(confirm_id, confirm_delete);
+    });
+}
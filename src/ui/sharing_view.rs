@@ -1,12 +1,56 @@
 // src/ui/sharing_view.rs
+use crate::app_actions::AppAction;
 use crate::app_state::AppState;
+use crate::services::export_service::{
+    ConflictResolution, ExportService, ExportServiceError, ImportPlan, ImportStatus,
+};
 use eframe::egui;
 use rfd::FileDialog;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
 use std::thread;
 
+/// Which part of the library the export button writes
+#[derive(Debug, Clone, PartialEq)]
+enum ExportScope {
+    Library,
+    CurrentMap,
+    VisibleLineups,  // The current map or search results, with the active filters
+    SelectedLineups, // The lineups selected in the grid
+    Strat(String),   // Strat id
+}
+
+/// An analyzed package waiting for the user to review it in the import preview
+struct PendingImport {
+    path: PathBuf,
+    plan: Arc<ImportPlan>,
+    resolutions: HashMap<String, ConflictResolution>, // Incoming lineup id -> choice
+}
+
+/// What the sharing view's background thread is doing
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TaskKind {
+    Export,
+    ReadPackage, // Reading and checking a package for the import preview
+    Import,
+}
+
+/// An export or import running in the background. It reports back through
+/// `AppAction::ExportProgress`/`ImportProgress` and `ExportCompleted`/`ImportCompleted`.
+struct BackgroundTask {
+    kind: TaskKind,
+    path: PathBuf,            // The package being written or read
+    progress: (usize, usize), // Files done, total files
+    cancel_flag: Arc<AtomicBool>,
+}
+
 pub struct SharingView {
-    export_in_progress: bool,
-    import_in_progress: bool,
+    export_scope: ExportScope,
+    author: String, // Name written into exported packages
+    pending_import: Option<PendingImport>,
+    task: Option<BackgroundTask>,
     last_status_message: Option<String>,
     last_status_is_error: bool,
 }
@@ -14,8 +58,10 @@ pub struct SharingView {
 impl Default for SharingView {
     fn default() -> Self {
         Self {
-            export_in_progress: false,
-            import_in_progress: false,
+            export_scope: ExportScope::Library,
+            author: String::new(),
+            pending_import: None,
+            task: None,
             last_status_message: None,
             last_status_is_error: false,
         }
@@ -37,17 +83,23 @@ impl SharingView {
                 ui.set_min_width(540.0);
                 ui.vertical(|ui| {
                     ui.heading("Export Nade Library");
-                    ui.label("Create a file with all your nade lineups to share with friends.");
+                    ui.label("Create a file with your nade lineups to share with friends.");
+                    ui.add_space(5.0);
+                    self.show_export_scope(ui, app_state);
+                    ui.horizontal(|ui_h| {
+                        ui_h.label("Your name:");
+                        ui_h.add(
+                            egui::TextEdit::singleline(&mut self.author)
+                                .hint_text("Optional, shown to whoever imports it"),
+                        );
+                    });
                     ui.add_space(5.0);
 
                     if ui
-                        .add_enabled(
-                            !self.export_in_progress,
-                            egui::Button::new("Export Library"),
-                        )
+                        .add_enabled(self.is_idle(), egui::Button::new("Export Library"))
                         .clicked()
                     {
-                        self.handle_export(app_state);
+                        self.handle_export(ui.ctx(), app_state);
                     }
                 });
             });
@@ -63,17 +115,16 @@ impl SharingView {
                     ui.add_space(5.0);
 
                     if ui
-                        .add_enabled(
-                            !self.import_in_progress,
-                            egui::Button::new("Import Library"),
-                        )
+                        .add_enabled(self.is_idle(), egui::Button::new("Import Library"))
                         .clicked()
                     {
-                        self.handle_import(app_state);
+                        self.handle_import(ui.ctx(), app_state);
                     }
                 });
             });
 
+            self.show_task_progress(ui);
+
             // Status message
             if let Some(message) = &self.last_status_message {
                 ui.add_space(10.0);
@@ -87,99 +138,459 @@ impl SharingView {
                 });
             }
         });
+
+        self.show_import_preview(ui.ctx(), app_state);
     }
 
-    fn handle_export(&mut self, app_state: &AppState) {
-        self.export_in_progress = true;
-        self.last_status_message = None;
+    /// Preselect the lineups selected in the grid as what to export, for the selection bar
+    pub fn export_selection(&mut self) {
+        self.export_scope = ExportScope::SelectedLineups;
+    }
 
-        // Ask the user where to save the file
-        if let Some(path) = FileDialog::new()
-            .add_filter("Nade Lineup Package", &["nadex"])
-            .set_file_name("my_nade_lineups.nadex")
-            .save_file()
+    /// Scope picker for the export: the whole library, the current map, what the grid
+    /// currently shows or has selected, or one of the current map's strats.
+    fn show_export_scope(&mut self, ui: &mut egui::Ui, app_state: &AppState) {
+        let map_label = app_state.map_config.label_for(&app_state.current_map);
+        let strats = app_state
+            .image_manifest
+            .strats_for_map(&app_state.current_map);
+        // A strat from another map (or a deleted one) can't stay selected
+        if let ExportScope::Strat(strat_id) = &self.export_scope
+            && !strats.iter().any(|strat| strat.id == *strat_id)
         {
-            // Create an instance of the export service
-            let export_service = crate::services::export_service::ExportService::new(
-                app_state.persistence_service.clone(),
-            );
-
-            // Perform the export
-            match export_service.export_library(&path, &app_state.data_dir) {
-                Ok(_) => {
-                    self.last_status_message = Some(format!(
-                        "Successfully exported nade library to {}",
-                        path.display()
-                    ));
-                    self.last_status_is_error = false;
-                }
-                Err(err) => {
-                    self.last_status_message = Some(format!("Export failed: {}", err));
-                    self.last_status_is_error = true;
-                }
+            self.export_scope = ExportScope::Library;
+        }
+        let selected_count = app_state.selected_visible_images().len();
+        if self.export_scope == ExportScope::SelectedLineups && selected_count == 0 {
+            self.export_scope = ExportScope::Library;
+        }
+
+        let scope_label = |scope: &ExportScope| match scope {
+            ExportScope::Library => "Whole library".to_string(),
+            ExportScope::CurrentMap => format!("All of {}", map_label),
+            ExportScope::VisibleLineups => format!(
+                "Lineups shown in the grid ({})",
+                app_state.visible_images().len()
+            ),
+            ExportScope::SelectedLineups => format!("Selected lineups ({})", selected_count),
+            ExportScope::Strat(strat_id) => strats
+                .iter()
+                .find(|strat| strat.id == *strat_id)
+                .map_or_else(String::new, |strat| format!("Strat: {}", strat.name)),
+        };
+
+        ui.horizontal(|ui_h| {
+            ui_h.label("Export:");
+            egui::ComboBox::from_id_salt("export_scope_combo")
+                .selected_text(scope_label(&self.export_scope))
+                .show_ui(ui_h, |ui_combo| {
+                    let mut scopes = vec![
+                        ExportScope::Library,
+                        ExportScope::CurrentMap,
+                        ExportScope::VisibleLineups,
+                    ];
+                    if selected_count > 0 {
+                        scopes.push(ExportScope::SelectedLineups);
+                    }
+                    scopes.extend(
+                        strats
+                            .iter()
+                            .map(|strat| ExportScope::Strat(strat.id.clone())),
+                    );
+                    for scope in scopes {
+                        let label = scope_label(&scope);
+                        ui_combo.selectable_value(&mut self.export_scope, scope, label);
+                    }
+                });
+        });
+    }
+
+    /// No export or import is running or waiting in the preview
+    fn is_idle(&self) -> bool {
+        self.task.is_none() && self.pending_import.is_none()
+    }
+
+    /// Progress bar and cancel button for the running export or import
+    fn show_task_progress(&mut self, ui: &mut egui::Ui) {
+        let Some(task) = &self.task else {
+            return;
+        };
+        ui.add_space(10.0);
+        let label = match task.kind {
+            TaskKind::Export => "Exporting",
+            TaskKind::ReadPackage => "Reading package",
+            TaskKind::Import => "Importing",
+        };
+        let (done, total) = task.progress;
+        let fraction = if total == 0 {
+            0.0
+        } else {
+            done as f32 / total as f32
+        };
+        ui.horizontal(|ui_h| {
+            ui_h.label(format!(
+                "{} {}...",
+                label,
+                task.path.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            let cancelling = task.cancel_flag.load(Ordering::Relaxed);
+            if ui_h
+                .add_enabled(!cancelling, egui::Button::new("Cancel"))
+                .clicked()
+            {
+                task.cancel_flag.store(true, Ordering::Relaxed);
             }
+        });
+        ui.add(
+            egui::ProgressBar::new(fraction)
+                .desired_width(540.0)
+                .text(format!("{} of {} files", done, total)),
+        );
+    }
+
+    /// Progress reported by the background thread through `AppAction::ExportProgress`
+    /// or `AppAction::ImportProgress`
+    pub fn set_progress(&mut self, done: usize, total: usize) {
+        if let Some(task) = &mut self.task {
+            task.progress = (done, total);
+        }
+    }
+
+    /// The export thread finished; `result` is the status message to show
+    pub fn finish_export(&mut self, result: Result<String, String>) {
+        self.task = None;
+        self.set_status(result);
+    }
+
+    /// The package has been read; open the import preview for it
+    pub fn open_import_preview(&mut self, plan: Arc<ImportPlan>) {
+        if let Some(task) = self.task.take() {
+            self.pending_import = Some(PendingImport {
+                path: task.path,
+                plan,
+                resolutions: HashMap::new(),
+            });
         }
+    }
+
+    /// Reading or applying the package finished. The caller refreshes the manifest.
+    pub fn finish_import(&mut self, result: Result<(), String>) {
+        let path = self.task.take().map(|task| task.path).unwrap_or_default();
+        self.set_status(
+            result.map(|()| format!("Successfully imported nade library from {}", path.display())),
+        );
+    }
 
-        self.export_in_progress = false;
+    fn set_status(&mut self, result: Result<String, String>) {
+        self.last_status_is_error = result.is_err();
+        self.last_status_message = Some(result.unwrap_or_else(|error| error));
     }
 
-    fn handle_import(&mut self, app_state: &mut AppState) {
-        self.import_in_progress = true;
+    /// Start a background task of `kind` on `path`, returning the service that
+    /// reports its progress and watches its cancel flag
+    fn start_task(
+        &mut self,
+        kind: TaskKind,
+        path: PathBuf,
+        ctx: &egui::Context,
+        app_state: &AppState,
+    ) -> ExportService {
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        self.task = Some(BackgroundTask {
+            kind,
+            path,
+            progress: (0, 0),
+            cancel_flag: Arc::clone(&cancel_flag),
+        });
         self.last_status_message = None;
 
-        // Set app_state flag to show progress indicator
-        app_state.is_processing_upload = true;
+        let sender = app_state.upload_result_sender.clone();
+        let ctx = ctx.clone();
+        ExportService::new(app_state.persistence_service.clone())
+            .with_cancel_flag(cancel_flag)
+            .with_progress(move |done, total| {
+                let action = match kind {
+                    TaskKind::Export => AppAction::ExportProgress { done, total },
+                    TaskKind::ReadPackage | TaskKind::Import => {
+                        AppAction::ImportProgress { done, total }
+                    }
+                };
+                let _ = sender.send(action);
+                ctx.request_repaint();
+            })
+    }
+
+    fn handle_export(&mut self, ctx: &egui::Context, app_state: &AppState) {
+        let file_name = match &self.export_scope {
+            ExportScope::Library => "my_nade_lineups.nadex".to_string(),
+            _ => format!("{}_lineups.nadex", app_state.current_map),
+        };
 
+        // Ask the user where to save the file
+        let Some(path) = FileDialog::new()
+            .add_filter("Nade Lineup Package", &["nadex"])
+            .set_file_name(file_name)
+            .save_file()
+        else {
+            return;
+        };
+
+        // Everything the thread needs from the app state, taken now
+        let scope = self.export_scope.clone();
+        let current_map = app_state.current_map.clone();
+        let visible_ids: Vec<String> = app_state
+            .visible_images()
+            .iter()
+            .map(|img| img.id.clone())
+            .collect();
+        let selected_ids: Vec<String> = app_state
+            .selected_visible_images()
+            .iter()
+            .map(|img| img.id.clone())
+            .collect();
+        let data_dir = app_state.data_dir.clone();
+        let export_service = self
+            .start_task(TaskKind::Export, path.clone(), ctx, app_state)
+            .with_author(&self.author);
+        let sender = app_state.upload_result_sender.clone();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let result = match &scope {
+                ExportScope::Library => export_service
+                    .export_library(&path, &data_dir)
+                    .map(|_| "nade library".to_string()),
+                ExportScope::CurrentMap => export_service
+                    .export_matching(&path, &data_dir, |img| img.map == current_map)
+                    .map(|count| format!("{} lineups", count)),
+                ExportScope::VisibleLineups => export_service
+                    .export_images(&path, &data_dir, &visible_ids)
+                    .map(|count| format!("{} lineups", count)),
+                ExportScope::SelectedLineups => export_service
+                    .export_images(&path, &data_dir, &selected_ids)
+                    .map(|count| format!("{} lineups", count)),
+                ExportScope::Strat(strat_id) => export_service
+                    .export_strat(&path, &data_dir, strat_id)
+                    .map(|count| format!("strat with {} lineups", count)),
+            };
+            let result = match result {
+                Ok(what) => Ok(format!(
+                    "Successfully exported {} to {}",
+                    what,
+                    path.display()
+                )),
+                Err(ExportServiceError::Cancelled) => Err("Export cancelled".to_string()),
+                Err(err) => Err(format!("Export failed: {}", err)),
+            };
+            send_completed(&sender, &ctx, AppAction::ExportCompleted(result));
+        });
+    }
+
+    fn handle_import(&mut self, ctx: &egui::Context, app_state: &AppState) {
         // Ask the user to select a file to import
-        if let Some(path) = FileDialog::new()
+        let Some(path) = FileDialog::new()
             .add_filter("Nade Lineup Package", &["nadex"])
             .pick_file()
-        {
-            // Clone what we need for the background thread
-            let path_clone = path.clone();
-            let persistence_service_clone = app_state.persistence_service.clone();
-
-            // Create a thread to handle the import operation
-            let handle = thread::spawn(move || {
-                // Create an instance of the export service
-                let export_service =
-                    crate::services::export_service::ExportService::new(persistence_service_clone);
-
-                // Perform the import
-                export_service.import_library(&path_clone)
-            });
+        else {
+            return;
+        };
 
-            // Wait for the import to complete
-            match handle.join() {
-                Ok(import_result) => {
-                    match import_result {
-                        Ok(updated_manifest) => {
-                            // Update the app state with the new manifest
-                            app_state.image_manifest = updated_manifest;
-                            app_state.filter_images_for_current_map();
-
-                            self.last_status_message = Some(format!(
-                                "Successfully imported nade library from {}",
-                                path.display()
-                            ));
-                            self.last_status_is_error = false;
-                        }
-                        Err(err) => {
-                            self.last_status_message = Some(format!("Import failed: {}", err));
-                            self.last_status_is_error = true;
-                        }
+        // Read and classify the package in a background thread; nothing is written
+        // until the user confirms the preview
+        let export_service = self.start_task(TaskKind::ReadPackage, path.clone(), ctx, app_state);
+        let sender = app_state.upload_result_sender.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let action = match export_service.analyze_import(&path) {
+                Ok(plan) => AppAction::ImportPreviewReady(Arc::new(plan)),
+                Err(err) => AppAction::ImportCompleted(Err(import_error_message(err))),
+            };
+            send_completed(&sender, &ctx, action);
+        });
+    }
+
+    /// The import preview: what a package adds, which lineups the library already has,
+    /// and a choice per conflicting lineup. Importing applies the plan with those choices.
+    fn show_import_preview(&mut self, ctx: &egui::Context, app_state: &AppState) {
+        let Some(pending) = &mut self.pending_import else {
+            return;
+        };
+        let mut open = true;
+        let mut confirmed = false;
+        let mut cancelled = false;
+
+        egui::Window::new("Import Preview")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let plan = &pending.plan;
+                let new_count = plan.count(|status| *status == ImportStatus::New);
+                let duplicate_count =
+                    plan.count(|status| matches!(status, ImportStatus::Duplicate { .. }));
+                let conflicts: Vec<_> = plan
+                    .items
+                    .iter()
+                    .filter(|item| matches!(item.status, ImportStatus::Conflict { .. }))
+                    .collect();
+
+                ui.label(format!(
+                    "Package: {}",
+                    pending
+                        .path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                ));
+                ui.label(format!("{} new lineups", new_count));
+                ui.label(format!(
+                    "{} lineups already in your library (skipped)",
+                    duplicate_count
+                ));
+                ui.label(format!("{} lineups differ from yours", conflicts.len()));
+                match &plan.header {
+                    Some(header) => {
+                        ui.weak(format!(
+                            "Made {}with Nadex {} on {}",
+                            header
+                                .author
+                                .as_ref()
+                                .map_or_else(String::new, |author| format!("by {} ", author)),
+                            header.app_version,
+                            header
+                                .created_at
+                                .with_timezone(&chrono::Local)
+                                .format("%Y-%m-%d %H:%M")
+                        ));
+                    }
+                    None => {
+                        ui.weak(
+                            "Made with an older Nadex version; images can't be checked for damage.",
+                        );
                     }
                 }
-                Err(_) => {
-                    self.last_status_message =
-                        Some("Import failed: Background process error".to_string());
-                    self.last_status_is_error = true;
+                if plan.skipped > 0 {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!(
+                            "{} lineups are missing their image and will be skipped",
+                            plan.skipped
+                        ),
+                    );
                 }
-            }
+                if !plan.new_strats.is_empty() {
+                    ui.label(format!("New strats: {}", plan.new_strats.join(", ")));
+                }
+
+                if !conflicts.is_empty() {
+                    ui.separator();
+                    ui.horizontal(|ui_h| {
+                        ui_h.label("All conflicts:");
+                        for (resolution, label) in RESOLUTION_LABELS {
+                            if ui_h.button(label).clicked() {
+                                for item in &conflicts {
+                                    pending
+                                        .resolutions
+                                        .insert(item.incoming.id.clone(), resolution);
+                                }
+                            }
+                        }
+                    });
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("import_conflicts_grid")
+                                .num_columns(3)
+                                .spacing([10.0, 4.0])
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for item in &conflicts {
+                                        let ImportStatus::Conflict {
+                                            existing_id,
+                                            differences,
+                                        } = &item.status
+                                        else {
+                                            continue;
+                                        };
+                                        let mine = app_state.image_manifest.find_image(existing_id);
+                                        let incoming = &item.incoming;
+                                        ui.label(format!(
+                                            "{} ({:?}, {})",
+                                            incoming.position,
+                                            incoming.nade_type,
+                                            app_state.map_config.label_for(&incoming.map)
+                                        ))
+                                        .on_hover_text(
+                                            format!(
+                                                "Your notes: {}\nTheir notes: {}",
+                                                mine.map_or("", |meta| meta.notes.as_str()),
+                                                incoming.notes
+                                            ),
+                                        );
+                                        ui.weak(format!("Differs in {}", differences.join(", ")));
+                                        let choice = pending
+                                            .resolutions
+                                            .entry(incoming.id.clone())
+                                            .or_default();
+                                        ui.horizontal(|ui_h| {
+                                            for (resolution, label) in RESOLUTION_LABELS {
+                                                ui_h.radio_value(choice, resolution, label);
+                                            }
+                                        });
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                }
+
+                ui.separator();
+                ui.horizontal(|ui_h| {
+                    if ui_h.button("Import").clicked() {
+                        confirmed = true;
+                    }
+                    if ui_h.button("Cancel").clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+
+        if confirmed {
+            let pending = self.pending_import.take().expect("Import preview is open");
+            let export_service = self.start_task(TaskKind::Import, pending.path, ctx, app_state);
+            let sender = app_state.upload_result_sender.clone();
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                let result = export_service
+                    .apply_import(&pending.plan, &pending.resolutions)
+                    .map(Arc::new)
+                    .map_err(import_error_message);
+                send_completed(&sender, &ctx, AppAction::ImportCompleted(result));
+            });
+        } else if cancelled || !open {
+            self.pending_import = None;
         }
+    }
+}
 
-        // Hide the progress indicator
-        app_state.is_processing_upload = false;
-        self.import_in_progress = false;
+/// Send a background task's final action and wake the UI up to handle it
+fn send_completed(sender: &mpsc::Sender<AppAction>, ctx: &egui::Context, action: AppAction) {
+    if let Err(e) = sender.send(action) {
+        log::error!("Failed to send sharing result to the main thread: {}", e);
     }
+    ctx.request_repaint();
 }
+
+fn import_error_message(err: ExportServiceError) -> String {
+    match err {
+        ExportServiceError::Cancelled => "Import cancelled".to_string(),
+        err => format!("Import failed: {}", err),
+    }
+}
+
+/// Conflict choices as offered in the import preview
+const RESOLUTION_LABELS: [(ConflictResolution, &str); 3] = [
+    (ConflictResolution::KeepMine, "Keep mine"),
+    (ConflictResolution::TakeTheirs, "Take theirs"),
+    (ConflictResolution::KeepBoth, "Keep both"),
+];
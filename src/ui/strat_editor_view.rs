@@ -0,0 +1,169 @@
+use crate::app_actions::AppAction;
+use crate::app_state::AppState;
+use crate::persistence::{ImageManifest, STRAT_PLAYERS, Strat};
+use crate::ui::strat_panel_view::player_color;
+use egui::{Align2, ComboBox, Context, Id, RichText, TextEdit, Ui, Window};
+
+/// Drag-and-drop payload for a grid lineup dragged into the strat editor (image id).
+#[derive(Debug, Clone)]
+pub struct StratLineupDrag(pub String);
+
+/// Renders the strat editor window for `AppState::editing_strat`.
+///
+/// Lineups are added by dragging them from the grid onto the lineup list. Each one gets
+/// a player role and a timing; lineups with the same timing are thrown together. Saving
+/// pushes `AppAction::SaveStrat`, closing pushes `AppAction::CloseStratEditor`.
+pub fn show_strat_editor(
+    app_state: &mut AppState,
+    ctx: &Context,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let Some(mut strat) = app_state.editing_strat.clone() else {
+        return;
+    };
+    let mut open = true;
+    let error_id = Id::new("strat_editor_error").with(&strat.id);
+    let mut error: Option<String> = ctx.data_mut(|d| d.get_temp(error_id));
+    let map_label = app_state.map_config.label_for(&strat.map).to_string();
+    let is_new = app_state.image_manifest.find_strat(&strat.id).is_none();
+    let title = if is_new { "New Strat" } else { "Edit Strat" };
+
+    Window::new(format!("{}: {}", title, map_label))
+        .id(Id::new("strat_editor_window").with(&strat.id))
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            egui::Grid::new("strat_editor_fields")
+                .num_columns(2)
+                .spacing([10.0, 6.0])
+                .show(ui, |ui| {
+                    ui.label("Name:");
+                    ui.add(TextEdit::singleline(&mut strat.name).hint_text("e.g., A execute"));
+                    ui.end_row();
+                    ui.label("Notes:");
+                    ui.add(
+                        TextEdit::multiline(&mut strat.notes)
+                            .hint_text("Calls, timings, who buys what...")
+                            .desired_rows(2),
+                    );
+                    ui.end_row();
+                });
+            ui.add_space(5.0);
+
+            ui.label(format!(
+                "Drag lineups from the {} grid onto the list below. Lineups with the same timing are thrown together.",
+                map_label
+            ));
+            let (_, dropped) = ui.dnd_drop_zone::<StratLineupDrag, ()>(
+                egui::Frame::group(ui.style()),
+                |ui| {
+                    ui.set_min_width(460.0);
+                    ui.set_min_height(60.0);
+                    show_strat_steps(ui, &mut strat, &app_state.image_manifest);
+                },
+            );
+
+            if let Some(payload) = dropped {
+                let image_id = &payload.0;
+                error = match app_state.image_manifest.find_image(image_id) {
+                    Some(meta) if meta.map != strat.map => Some(format!(
+                        "'{}' is on {}, not {}.",
+                        meta.position,
+                        app_state.map_config.label_for(&meta.map),
+                        map_label
+                    )),
+                    Some(meta) if !strat.add_lineup(image_id) => {
+                        Some(format!("'{}' is already in this strat.", meta.position))
+                    }
+                    Some(_) => None,
+                    None => Some("That lineup no longer exists.".to_string()),
+                };
+            }
+            if let Some(error) = &error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+
+            ui.add_space(5.0);
+            ui.horizontal(|ui_h| {
+                if ui_h
+                    .add_enabled(!strat.name.trim().is_empty(), egui::Button::new("Save"))
+                    .clicked()
+                {
+                    action_queue.push(AppAction::SaveStrat(strat.clone()));
+                }
+                if ui_h.button("Cancel").clicked() {
+                    action_queue.push(AppAction::CloseStratEditor);
+                }
+            });
+        });
+
+    ctx.data_mut(|d| match &error {
+        Some(error) if open => d.insert_temp(error_id, error.clone()),
+        _ => {
+            d.remove::<String>(error_id);
+        }
+    });
+    if open {
+        app_state.editing_strat = Some(strat);
+    } else {
+        action_queue.push(AppAction::CloseStratEditor);
+    }
+}
+
+/// The strat's lineups with their player and timing pickers, in the order they were added.
+fn show_strat_steps(ui: &mut Ui, strat: &mut Strat, manifest: &ImageManifest) {
+    if strat.steps.is_empty() {
+        ui.weak("Drop lineups here");
+        return;
+    }
+
+    let mut remove_index = None;
+    egui::Grid::new("strat_editor_steps")
+        .num_columns(4)
+        .spacing([10.0, 4.0])
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Lineup");
+            ui.strong("Player");
+            ui.strong("Timing");
+            ui.label("");
+            ui.end_row();
+
+            for (index, step) in strat.steps.iter_mut().enumerate() {
+                match manifest.find_image(&step.image_id) {
+                    Some(meta) => ui.label(format!("{} ({:?})", meta.position, meta.nade_type)),
+                    None => ui.colored_label(egui::Color32::RED, "[Missing lineup]"),
+                };
+                ComboBox::new(("strat_step_player", index), "")
+                    .selected_text(
+                        RichText::new(format!("Player {}", step.player))
+                            .color(player_color(step.player)),
+                    )
+                    .show_ui(ui, |ui| {
+                        for player in 1..=STRAT_PLAYERS {
+                            ui.selectable_value(
+                                &mut step.player,
+                                player,
+                                RichText::new(format!("Player {}", player))
+                                    .color(player_color(player)),
+                            );
+                        }
+                    });
+                ui.add(egui::DragValue::new(&mut step.order).range(1..=99))
+                    .on_hover_text("Throw order; equal timings are thrown together");
+                if ui
+                    .small_button("✖")
+                    .on_hover_text("Remove from strat")
+                    .clicked()
+                {
+                    remove_index = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+    if let Some(index) = remove_index {
+        strat.steps.remove(index);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::app_actions::AppAction;
+use crate::app_state::AppState;
+use egui::{Color32, Id, RichText, Ui};
+
+/// Colour for a strat player role, after the in-game teammate colours.
+pub fn player_color(player: u8) -> Color32 {
+    match player {
+        1 => Color32::from_rgb(70, 150, 255),
+        2 => Color32::from_rgb(60, 200, 90),
+        3 => Color32::from_rgb(240, 210, 40),
+        4 => Color32::from_rgb(255, 140, 30),
+        _ => Color32::from_rgb(180, 90, 230),
+    }
+}
+
+/// Renders the strat list for the current map, with buttons to play, edit, delete and
+/// create strats. Deleting asks for confirmation inline before pushing
+/// `AppAction::DeleteStrat`.
+pub fn show_strat_panel(app_state: &mut AppState, ui: &mut Ui, action_queue: &mut Vec<AppAction>) {
+    let map_name = app_state.current_map.clone();
+    ui.heading(format!(
+        "Strats: {}",
+        app_state.map_config.label_for(&map_name)
+    ));
+    if ui
+        .button("➕ New Strat")
+        .on_hover_text("Group lineups of this map into a team execute")
+        .clicked()
+    {
+        action_queue.push(AppAction::EditStrat(None));
+    }
+    ui.separator();
+
+    let strats = app_state.image_manifest.strats_for_map(&map_name);
+    if strats.is_empty() {
+        ui.weak("No strats for this map yet.");
+        return;
+    }
+
+    // The strat waiting for delete confirmation, if any
+    let confirm_id = Id::new("strat_panel_confirm_delete");
+    let mut confirm_delete: Option<String> = ui.data_mut(|d| d.get_temp(confirm_id));
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for strat in strats {
+            ui.group(|ui| {
+                ui.set_min_width(ui.available_width());
+                ui.label(RichText::new(&strat.name).strong());
+                ui.horizontal_wrapped(|ui_h| {
+                    ui_h.weak(format!("{} lineups", strat.steps.len()));
+                    let mut players: Vec<u8> = strat.steps.iter().map(|step| step.player).collect();
+                    players.sort_unstable();
+                    players.dedup();
+                    for player in players {
+                        ui_h.colored_label(player_color(player), format!("P{}", player));
+                    }
+                });
+                if !strat.notes.is_empty() {
+                    ui.weak(&strat.notes);
+                }
+
+                ui.horizontal(|ui_h| {
+                    if confirm_delete.as_ref() == Some(&strat.id) {
+                        ui_h.label("Delete this strat?");
+                        if ui_h.button("Yes").clicked() {
+                            action_queue.push(AppAction::DeleteStrat(strat.id.clone()));
+                            confirm_delete = None;
+                        }
+                        if ui_h.button("No").clicked() {
+                            confirm_delete = None;
+                        }
+                        return;
+                    }
+                    if ui_h
+                        .add_enabled(!strat.steps.is_empty(), egui::Button::new("▶ Play"))
+                        .clicked()
+                    {
+                        action_queue.push(AppAction::PlayStrat(strat.id.clone()));
+                    }
+                    if ui_h.button("✏ Edit").clicked() {
+                        action_queue.push(AppAction::EditStrat(Some(strat.id.clone())));
+                    }
+                    if ui_h
+                        .button("🗑")
+                        .on_hover_text("Delete strat (lineups are kept)")
+                        .clicked()
+                    {
+                        confirm_delete = Some(strat.id.clone());
+                    }
+                });
+            });
+        }
+    });
+
+    ui.data_mut(|d| match confirm_delete {
+        Some(strat_id) => d.insert_temp(confirm_id, strat_id),
+        None => {
+            d.remove::<String>(confirm_id);
+        }
+    });
+}
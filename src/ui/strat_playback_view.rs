@@ -0,0 +1,205 @@
+use crate::app_actions::AppAction;
+use crate::app_state::AppState;
+use crate::object_store;
+use crate::persistence::{ImageMeta, STRAT_PLAYERS};
+use crate::services::thumbnail_service::ThumbnailServiceTrait;
+use crate::ui::strat_panel_view::player_color;
+use egui::{Align2, Context, RichText, Sense, Ui, Vec2, Window};
+
+/// Width of a lineup thumbnail in the playback window
+const PLAYBACK_THUMBNAIL_WIDTH: f32 = 220.0;
+
+/// Renders the strat playback window for `AppState::strat_playback`.
+///
+/// Steps through the strat's timeline one timing at a time, showing every player's
+/// utility for that timing, with an overview of each player's throws underneath.
+/// Clicking a lineup opens its detail modal.
+pub fn show_strat_playback(
+    app_state: &mut AppState,
+    ctx: &Context,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let Some((strat_id, step_index)) = app_state.strat_playback.clone() else {
+        return;
+    };
+    let Some(strat) = app_state.image_manifest.find_strat(&strat_id).cloned() else {
+        action_queue.push(AppAction::CloseStratPlayback);
+        return;
+    };
+    let timeline = strat.timeline();
+    let mut open = true;
+
+    Window::new(format!("Strat: {}", strat.name))
+        .id(egui::Id::new("strat_playback_window").with(&strat.id))
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            if timeline.is_empty() {
+                ui.label("This strat has no lineups yet. Edit it to add some.");
+                return;
+            }
+            let step_index = step_index.min(timeline.len() - 1);
+            let (order, steps) = &timeline[step_index];
+
+            ui.horizontal(|ui_h| {
+                if ui_h
+                    .add_enabled(step_index > 0, egui::Button::new("◀ Previous"))
+                    .clicked()
+                {
+                    action_queue.push(AppAction::StratPlaybackStep(step_index - 1));
+                }
+                ui_h.label(
+                    RichText::new(format!(
+                        "Step {} of {} (timing {})",
+                        step_index + 1,
+                        timeline.len(),
+                        order
+                    ))
+                    .strong(),
+                );
+                if ui_h
+                    .add_enabled(step_index + 1 < timeline.len(), egui::Button::new("Next ▶"))
+                    .clicked()
+                {
+                    action_queue.push(AppAction::StratPlaybackStep(step_index + 1));
+                }
+            });
+            if !strat.notes.is_empty() {
+                ui.weak(&strat.notes);
+            }
+            ui.separator();
+
+            // Every throw at this timing, side by side
+            ui.horizontal_top(|ui_h| {
+                for step in steps {
+                    ui_h.vertical(|ui_v| {
+                        ui_v.set_width(PLAYBACK_THUMBNAIL_WIDTH);
+                        ui_v.label(
+                            RichText::new(format!("Player {}", step.player))
+                                .color(player_color(step.player))
+                                .strong(),
+                        );
+                        match app_state.image_manifest.find_image(&step.image_id) {
+                            Some(meta) => {
+                                show_lineup_card(app_state, ui_v, meta, action_queue);
+                            }
+                            None => {
+                                ui_v.colored_label(egui::Color32::RED, "[Missing lineup]");
+                            }
+                        }
+                    });
+                }
+            });
+
+            ui.separator();
+            ui.label("Each player's utility:");
+            egui::Grid::new(("strat_playback_players", &strat.id))
+                .num_columns(2)
+                .spacing([10.0, 4.0])
+                .show(ui, |ui| {
+                    for player in 1..=STRAT_PLAYERS {
+                        let throws: Vec<(usize, &str)> = timeline
+                            .iter()
+                            .enumerate()
+                            .flat_map(|(index, (_, group))| {
+                                group
+                                    .iter()
+                                    .filter(|step| step.player == player)
+                                    .map(move |step| (index, step.image_id.as_str()))
+                            })
+                            .collect();
+                        if throws.is_empty() {
+                            continue;
+                        }
+                        ui.label(
+                            RichText::new(format!("Player {}", player)).color(player_color(player)),
+                        );
+                        ui.horizontal_wrapped(|ui_h| {
+                            for (index, image_id) in throws {
+                                let position = app_state
+                                    .image_manifest
+                                    .find_image(image_id)
+                                    .map_or("[Missing lineup]", |meta| meta.position.as_str());
+                                if ui_h
+                                    .selectable_label(index == step_index, position)
+                                    .clicked()
+                                {
+                                    action_queue.push(AppAction::StratPlaybackStep(index));
+                                }
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+        });
+
+    if !open {
+        action_queue.push(AppAction::CloseStratPlayback);
+    }
+}
+
+/// Thumbnail, position and notes of one lineup. Clicking the thumbnail opens the detail modal.
+fn show_lineup_card(
+    app_state: &AppState,
+    ui: &mut Ui,
+    meta: &ImageMeta,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let image_path = object_store::image_path(&app_state.data_dir, &meta.map, &meta.filename);
+    let cache_key = image_path.to_string_lossy().into_owned();
+    let texture_info = app_state
+        .thumbnail_service
+        .lock()
+        .ok()
+        .and_then(|mut service| {
+            if !service.has_texture(&cache_key) && image_path.exists() {
+                let _ = service.load_texture_from_file(&image_path, &cache_key, ui.ctx());
+            }
+            service.get_cached_texture_info(&cache_key)
+        });
+
+    let height = match texture_info {
+        Some((_, (width, height))) if width > 0 => {
+            PLAYBACK_THUMBNAIL_WIDTH * height as f32 / width as f32
+        }
+        _ => PLAYBACK_THUMBNAIL_WIDTH * 3.0 / 4.0,
+    };
+    let (rect, response) =
+        ui.allocate_exact_size(Vec2::new(PLAYBACK_THUMBNAIL_WIDTH, height), Sense::click());
+    match &texture_info {
+        Some((texture_handle, _)) => {
+            ui.painter().image(
+                texture_handle.id(),
+                rect,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE,
+            );
+        }
+        None => {
+            ui.painter().rect_filled(
+                rect,
+                egui::CornerRadius::default(),
+                egui::Color32::from_gray(30),
+            );
+        }
+    }
+    if response.on_hover_text("Open lineup").clicked() {
+        action_queue.push(AppAction::ImageGridImageClicked(meta.id.clone()));
+    }
+
+    ui.label(RichText::new(format!("{} ({:?})", meta.position, meta.nade_type)).strong());
+    if !meta.notes.is_empty() {
+        ui.label(&meta.notes);
+    }
+    if let Some(setpos) = meta.setpos() {
+        ui.horizontal(|ui_h| {
+            let command = setpos.command();
+            ui_h.label(RichText::new(&command).monospace().small());
+            if ui_h.small_button("Copy").clicked() {
+                ui_h.ctx().copy_text(command);
+            }
+        });
+    }
+}
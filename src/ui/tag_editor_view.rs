@@ -0,0 +1,63 @@
+use crate::persistence::normalize_tag;
+use egui::{Id, Ui};
+
+/// Renders an inline tag editor: the current tags with remove buttons, a text field
+/// for new tags (Enter or "Add" to confirm), and quick picks from tags already in use.
+///
+/// The pending text is kept in egui's temporary memory under `id_salt`, so callers
+/// only need to own the tag list itself.
+pub fn show_tag_editor(ui: &mut Ui, id_salt: &str, tags: &mut Vec<String>, known_tags: &[String]) {
+    let input_id = Id::new("tag_editor_input").with(id_salt);
+    let mut input: String = ui.data_mut(|d| d.get_temp(input_id).unwrap_or_default());
+
+    ui.vertical(|ui| {
+        let mut remove_index = None;
+        ui.horizontal_wrapped(|ui_tags| {
+            for (index, tag) in tags.iter().enumerate() {
+                if ui_tags
+                    .small_button(format!("{} ✖", tag))
+                    .on_hover_text("Remove tag")
+                    .clicked()
+                {
+                    remove_index = Some(index);
+                }
+            }
+        });
+        if let Some(index) = remove_index {
+            tags.remove(index);
+        }
+
+        ui.horizontal(|ui_h| {
+            let response = ui_h.add(
+                egui::TextEdit::singleline(&mut input)
+                    .hint_text("e.g., retake, one-way")
+                    .desired_width(140.0),
+            );
+            let submitted =
+                response.lost_focus() && ui_h.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui_h.button("Add").clicked() || submitted {
+                if let Some(tag) = normalize_tag(&input)
+                    && !tags.contains(&tag)
+                {
+                    tags.push(tag);
+                }
+                input.clear();
+            }
+
+            let suggestions: Vec<&String> =
+                known_tags.iter().filter(|t| !tags.contains(t)).collect();
+            if !suggestions.is_empty() {
+                ui_h.menu_button("Existing ▾", |ui_menu| {
+                    for tag in suggestions {
+                        if ui_menu.button(tag).clicked() {
+                            tags.push(tag.clone());
+                            ui_menu.close_menu();
+                        }
+                    }
+                });
+            }
+        });
+    });
+
+    ui.data_mut(|d| d.insert_temp(input_id, input));
+}
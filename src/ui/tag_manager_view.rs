@@ -0,0 +1,77 @@
+use crate::app_actions::AppAction;
+use crate::app_state::AppState;
+use eframe::egui;
+
+/// Renders the tag manager window, listing every tag in the library with its usage count.
+///
+/// Pushes `AppAction::RenameTag` when a tag is renamed. Renaming onto a tag that already
+/// exists merges the two. Closing the window pushes `AppAction::CloseTagManager`.
+pub fn show_tag_manager(
+    app_state: &mut AppState,
+    ctx: &egui::Context,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let mut open = app_state.show_tag_manager;
+    let tag_counts = app_state.image_manifest.tag_counts();
+
+    egui::Window::new("Manage Tags")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            if tag_counts.is_empty() {
+                ui.label("No tags yet. Add tags when uploading or editing a lineup.");
+                return;
+            }
+
+            ui.label("Rename a tag to an existing name to merge them.");
+            ui.add_space(5.0);
+
+            egui::ScrollArea::vertical()
+                .max_height(400.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("tag_manager_grid")
+                        .num_columns(3)
+                        .spacing([10.0, 6.0])
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (tag, count) in &tag_counts {
+                                // Pending rename text lives in egui memory, keyed by the tag
+                                let input_id = egui::Id::new("tag_manager_rename").with(tag);
+                                let mut new_name: String = ui.data_mut(|d| {
+                                    d.get_temp(input_id).unwrap_or_else(|| tag.clone())
+                                });
+
+                                ui.label(format!("{} ({})", tag, count));
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut new_name).desired_width(140.0),
+                                );
+                                let is_merge = new_name.trim() != tag
+                                    && tag_counts.contains_key(new_name.trim());
+                                let button_label = if is_merge { "Merge" } else { "Rename" };
+                                if ui
+                                    .add_enabled(
+                                        new_name.trim() != tag,
+                                        egui::Button::new(button_label),
+                                    )
+                                    .clicked()
+                                {
+                                    action_queue.push(AppAction::RenameTag {
+                                        from: tag.clone(),
+                                        to: new_name.clone(),
+                                    });
+                                    ui.data_mut(|d| d.remove::<String>(input_id));
+                                } else {
+                                    ui.data_mut(|d| d.insert_temp(input_id, new_name));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+
+    if !open {
+        action_queue.push(AppAction::CloseTagManager);
+    }
+}
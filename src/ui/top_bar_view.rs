@@ -1,7 +1,8 @@
 use crate::app_actions::AppAction;
 use crate::app_state::AppState;
-use crate::persistence::NadeType;
+use crate::persistence::{Movement, NadeType, Side, ThrowClick};
 use egui::Ui;
+use strum::IntoEnumIterator;
 
 const SIZE_LABELS: [&str; 3] = ["Large", "Medium", "Small"];
 const IMAGE_SIZES: [u32; 3] = [957, 637, 477]; // Direct size values for the grid
@@ -15,11 +16,15 @@ pub fn show_top_bar(
     ui.horizontal(|ui_content| {
         // Map selection icon
         ui_content.label("Map:");
-        let selected_map_text = app_state.current_map.clone();
+        let selected_map_text = app_state
+            .map_config
+            .label_for(&app_state.current_map)
+            .to_string();
         egui::ComboBox::new("map_selector_top_bar", "")
             .selected_text(&selected_map_text)
             .show_ui(ui_content, |ui_combo| {
-                for map_name_str in &app_state.maps {
+                for map_entry in app_state.map_config.visible_maps() {
+                    let map_name_str = &map_entry.name;
                     // For ComboBox, we need a mutable variable to bind to, even if we don't use its changed state directly for app_state.current_map.
                     // The actual change is driven by the action.
                     let mut current_selection_for_combo = app_state.current_map.clone();
@@ -27,7 +32,7 @@ pub fn show_top_bar(
                         .selectable_value(
                             &mut current_selection_for_combo,
                             map_name_str.to_string(),
-                            *map_name_str,
+                            map_entry.label(),
                         )
                         .changed()
                     {
@@ -37,6 +42,10 @@ pub fn show_top_bar(
                         }
                     }
                 }
+                ui_combo.separator();
+                if ui_combo.button("Manage Maps...").clicked() {
+                    action_queue.push(AppAction::ShowMapManager);
+                }
             });
 
         // Image size icon
@@ -82,6 +91,32 @@ pub fn show_top_bar(
             action_queue.push(AppAction::ShowUploadModal);
         }
 
+        // Undo/redo of lineup edits; also Ctrl+Z / Ctrl+Shift+Z
+        let undo_hint = app_state
+            .history
+            .next_undo()
+            .map(|description| format!("Undo {} (Ctrl+Z)", description));
+        if ui_content
+            .add_enabled(undo_hint.is_some(), egui::Button::new("Undo"))
+            .on_hover_text(undo_hint.unwrap_or_default())
+            .on_disabled_hover_text("Nothing to undo")
+            .clicked()
+        {
+            action_queue.push(AppAction::Undo);
+        }
+        let redo_hint = app_state
+            .history
+            .next_redo()
+            .map(|description| format!("Redo {} (Ctrl+Shift+Z)", description));
+        if ui_content
+            .add_enabled(redo_hint.is_some(), egui::Button::new("Redo"))
+            .on_hover_text(redo_hint.unwrap_or_default())
+            .on_disabled_hover_text("Nothing to redo")
+            .clicked()
+        {
+            action_queue.push(AppAction::Redo);
+        }
+
         // Nade Type Filter Buttons
         ui_content.add_space(10.0);
         let original_item_spacing = ui_content.style_mut().spacing.item_spacing.x;
@@ -120,6 +155,129 @@ pub fn show_top_bar(
                 action_queue.push(AppAction::SetNadeFilter(filter_option));
             }
         }
+
+        // Side Filter Buttons. Lineups marked "Either" show up under both sides.
+        ui_content.add_space(10.0);
+        ui_content.separator();
+        let side_options = [
+            (None, "Both Sides"),
+            (Some(Side::CT), "CT"),
+            (Some(Side::T), "T"),
+        ];
+
+        for (filter_option, label_str) in side_options {
+            let is_selected = app_state.selected_side == filter_option;
+
+            let button_text = egui::RichText::new(label_str).color(if is_selected {
+                text_color_selected
+            } else {
+                text_color_unselected
+            });
+
+            let mut button = egui::Button::new(button_text);
+
+            if is_selected {
+                button = button.fill(ui_content.style().visuals.selection.bg_fill);
+            } else {
+                button = button.fill(egui::Color32::TRANSPARENT);
+            }
+
+            if ui_content.add(button).clicked() && app_state.selected_side != filter_option {
+                action_queue.push(AppAction::SetSideFilter(filter_option));
+            }
+        }
+
+        // Throw technique filter. Edits a copy and only pushes an action when something changed.
+        let technique_label = if app_state.technique_filter.is_active() {
+            egui::RichText::new("Technique ●").color(text_color_selected)
+        } else {
+            egui::RichText::new("Technique").color(text_color_unselected)
+        };
+        ui_content.menu_button(technique_label, |ui_menu| {
+            let mut filter = app_state.technique_filter;
+
+            ui_menu.label("Movement:");
+            ui_menu.horizontal(|ui_row| {
+                ui_row.selectable_value(&mut filter.movement, None, "Any");
+                for movement in Movement::iter() {
+                    ui_row.selectable_value(
+                        &mut filter.movement,
+                        Some(movement),
+                        format!("{:?}", movement),
+                    );
+                }
+            });
+            ui_menu.label("Click:");
+            ui_menu.horizontal(|ui_row| {
+                ui_row.selectable_value(&mut filter.click, None, "Any");
+                for click in ThrowClick::iter() {
+                    ui_row.selectable_value(&mut filter.click, Some(click), format!("{:?}", click));
+                }
+            });
+            ui_menu.separator();
+            ui_menu.checkbox(
+                &mut filter.without_jump_throw_bind,
+                "Works without jump-throw bind",
+            );
+            if ui_menu
+                .add_enabled(filter.is_active(), egui::Button::new("Clear"))
+                .clicked()
+            {
+                filter = Default::default();
+            }
+
+            if filter != app_state.technique_filter {
+                action_queue.push(AppAction::SetTechniqueFilter(filter));
+            }
+        });
+
+        // Tag filter. Selecting several tags narrows the grid to lineups carrying all of them.
+        let tags_label = if app_state.selected_tags.is_empty() {
+            egui::RichText::new("Tags").color(text_color_unselected)
+        } else {
+            egui::RichText::new(format!("Tags ({})", app_state.selected_tags.len()))
+                .color(text_color_selected)
+        };
+        ui_content.menu_button(tags_label, |ui_menu| {
+            let tag_counts = app_state.image_manifest.tag_counts();
+            if tag_counts.is_empty() {
+                ui_menu.label("No tags yet");
+            }
+
+            let mut selected_tags = app_state.selected_tags.clone();
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui_menu, |ui_scroll| {
+                    for (tag, count) in &tag_counts {
+                        let mut checked = selected_tags.contains(tag);
+                        if ui_scroll
+                            .checkbox(&mut checked, format!("{} ({})", tag, count))
+                            .changed()
+                        {
+                            if checked {
+                                selected_tags.push(tag.clone());
+                            } else {
+                                selected_tags.retain(|t| t != tag);
+                            }
+                        }
+                    }
+                });
+            ui_menu.separator();
+            if ui_menu
+                .add_enabled(!selected_tags.is_empty(), egui::Button::new("Clear"))
+                .clicked()
+            {
+                selected_tags.clear();
+            }
+            if ui_menu.button("Manage Tags...").clicked() {
+                action_queue.push(AppAction::ShowTagManager);
+                ui_menu.close_menu();
+            }
+
+            if selected_tags != app_state.selected_tags {
+                action_queue.push(AppAction::SetTagFilter(selected_tags));
+            }
+        });
         ui_content.style_mut().spacing.item_spacing.x = original_item_spacing;
 
         // Reorder mode toggle button
@@ -129,18 +287,58 @@ pub fn show_top_bar(
         } else {
             egui::RichText::new("🔄 Reorder Mode").color(text_color_unselected)
         };
-        
+
         let mut reorder_button = egui::Button::new(reorder_button_text);
         if app_state.reorder_mode {
             reorder_button = reorder_button.fill(ui_content.style().visuals.selection.bg_fill);
         } else {
             reorder_button = reorder_button.fill(egui::Color32::TRANSPARENT);
         }
-        
+
         if ui_content.add(reorder_button).clicked() {
             action_queue.push(AppAction::ToggleReorderMode);
         }
 
+        // Radar panel toggle button
+        let radar_button_text = if app_state.show_radar_panel {
+            egui::RichText::new("🗺 Radar").color(text_color_selected)
+        } else {
+            egui::RichText::new("🗺 Radar").color(text_color_unselected)
+        };
+        let mut radar_button = egui::Button::new(radar_button_text);
+        if app_state.show_radar_panel {
+            radar_button = radar_button.fill(ui_content.style().visuals.selection.bg_fill);
+        } else {
+            radar_button = radar_button.fill(egui::Color32::TRANSPARENT);
+        }
+        if ui_content
+            .add(radar_button)
+            .on_hover_text("Show lineups on the map's radar")
+            .clicked()
+        {
+            action_queue.push(AppAction::ToggleRadarPanel);
+        }
+
+        // Strat panel toggle button
+        let strats_button_text = if app_state.show_strat_panel {
+            egui::RichText::new("📋 Strats").color(text_color_selected)
+        } else {
+            egui::RichText::new("📋 Strats").color(text_color_unselected)
+        };
+        let mut strats_button = egui::Button::new(strats_button_text);
+        if app_state.show_strat_panel {
+            strats_button = strats_button.fill(ui_content.style().visuals.selection.bg_fill);
+        } else {
+            strats_button = strats_button.fill(egui::Color32::TRANSPARENT);
+        }
+        if ui_content
+            .add(strats_button)
+            .on_hover_text("Team executes built from this map's lineups")
+            .clicked()
+        {
+            action_queue.push(AppAction::ToggleStratPanel);
+        }
+
         // Add flexible space to push the share button to the right
         ui_content.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
             // Share button logic (now on the right side)
@@ -151,6 +349,69 @@ pub fn show_top_bar(
             {
                 action_queue.push(AppAction::ShowSharingView);
             }
+
+            if ui
+                .button("Backups")
+                .on_hover_text("Restore the library as it was before a recent change")
+                .clicked()
+            {
+                action_queue.push(AppAction::ShowBackups);
+            }
+
+            let trash_count = app_state.trash.lineups.len();
+            let trash_label = if trash_count > 0 {
+                format!("Trash ({})", trash_count)
+            } else {
+                "Trash".to_string()
+            };
+            if ui
+                .button(trash_label)
+                .on_hover_text("Restore or purge deleted lineups")
+                .clicked()
+            {
+                action_queue.push(AppAction::ShowTrash);
+            }
+
+            if ui
+                .button("Check Library")
+                .on_hover_text("Find and fix files that no longer match the library")
+                .clicked()
+            {
+                action_queue.push(AppAction::ShowIntegrityCheck);
+            }
+
+            // Practice config, preselecting the visible lineups that can be teleported to
+            if ui
+                .button("Practice .cfg")
+                .on_hover_text("Save a CS2 practice config with teleports to this map's lineups")
+                .clicked()
+            {
+                let image_ids = app_state
+                    .visible_images()
+                    .into_iter()
+                    .filter(|meta| meta.map == app_state.current_map && meta.throw_pos.is_some())
+                    .map(|meta| meta.id.clone())
+                    .collect();
+                action_queue.push(AppAction::ShowPracticeConfig(image_ids));
+            }
+
+            // Search box. Searches every map, so the map selector doesn't narrow the results.
+            let mut query = app_state.search_query.clone();
+            if ui
+                .add_enabled(!query.is_empty(), egui::Button::new("✖").small())
+                .on_hover_text("Clear search")
+                .clicked()
+            {
+                query.clear();
+            }
+            ui.add(
+                egui::TextEdit::singleline(&mut query)
+                    .hint_text("Search all maps...")
+                    .desired_width(180.0),
+            );
+            if query != app_state.search_query {
+                action_queue.push(AppAction::SetSearchQuery(query));
+            }
         });
     });
 }
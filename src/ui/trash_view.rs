@@ -0,0 +1,150 @@
+use crate::app_actions::AppAction;
+use crate::app_state::AppState;
+use chrono::{DateTime, Local};
+use eframe::egui;
+
+/// Retention periods offered in the trash window, in days; 0 keeps lineups until emptied
+const RETENTION_CHOICES: [(u32, &str); 5] = [
+    (7, "1 week"),
+    (30, "30 days"),
+    (90, "90 days"),
+    (365, "1 year"),
+    (0, "Until emptied"),
+];
+
+/// Renders the trash window, listing `AppState::trash` with the map each lineup came from
+/// and when it was deleted, most recent first.
+///
+/// Restoring pushes `AppAction::RestoreFromTrash`. Deleting a lineup for good or emptying the
+/// trash asks for confirmation inline before pushing `AppAction::PurgeTrash`. Closing the
+/// window pushes `AppAction::CloseTrash`.
+pub fn show_trash(
+    app_state: &mut AppState,
+    ctx: &egui::Context,
+    action_queue: &mut Vec<AppAction>,
+) {
+    let mut open = app_state.show_trash;
+    // The purge waiting for confirmation, if any: `Some(None)` empties the trash
+    let confirm_id = egui::Id::new("trash_confirm_purge");
+    let mut confirm_purge: Option<Option<String>> = ctx.data_mut(|d| d.get_temp(confirm_id));
+    let trash = &app_state.trash;
+
+    egui::Window::new("Trash")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.horizontal(|ui_h| {
+                ui_h.label("Keep deleted lineups for");
+                let selected = RETENTION_CHOICES
+                    .iter()
+                    .find(|(days, _)| *days == trash.retention_days)
+                    .map_or_else(
+                        || format!("{} days", trash.retention_days),
+                        |(_, label)| label.to_string(),
+                    );
+                egui::ComboBox::new("trash_retention", "")
+                    .selected_text(selected)
+                    .show_ui(ui_h, |ui_combo| {
+                        for (days, label) in RETENTION_CHOICES {
+                            if ui_combo.selectable_label(trash.retention_days == days, label).clicked()
+                                && trash.retention_days != days
+                            {
+                                action_queue.push(AppAction::SetTrashRetention(days));
+                            }
+                        }
+                    });
+            });
+            ui.add_space(5.0);
+
+            if trash.lineups.is_empty() {
+                ui.label("The trash is empty. Deleted lineups are kept here until they're restored or purged.");
+                return;
+            }
+
+            egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                egui::Grid::new("trash_grid")
+                    .num_columns(5)
+                    .spacing([10.0, 6.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for entry in trash.lineups.iter().rev() {
+                            let image = &entry.image;
+                            let title = if image.position.is_empty() {
+                                "Untitled lineup"
+                            } else {
+                                &image.position
+                            };
+                            ui.label(title);
+                            ui.label(format!(
+                                "{} · {:?}",
+                                app_state.map_config.label_for(&image.map),
+                                image.nade_type
+                            ));
+                            ui.label(format!(
+                                "Deleted {}",
+                                DateTime::<Local>::from(entry.deleted_at).format("%Y-%m-%d %H:%M")
+                            ));
+                            match trash.expires_at(entry.deleted_at) {
+                                Some(expires_at) => ui.label(format!(
+                                    "Purged after {}",
+                                    DateTime::<Local>::from(expires_at).format("%Y-%m-%d")
+                                )),
+                                None => ui.label(""),
+                            };
+                            ui.horizontal(|ui_h| {
+                                if confirm_purge.as_ref() == Some(&Some(image.id.clone())) {
+                                    ui_h.label("Delete for good?");
+                                    if ui_h.button("Yes").clicked() {
+                                        action_queue.push(AppAction::PurgeTrash(Some(image.id.clone())));
+                                        confirm_purge = None;
+                                    }
+                                    if ui_h.button("No").clicked() {
+                                        confirm_purge = None;
+                                    }
+                                } else {
+                                    if ui_h
+                                        .button("Restore")
+                                        .on_hover_text("Put the lineup back at the end of its map")
+                                        .clicked()
+                                    {
+                                        action_queue.push(AppAction::RestoreFromTrash(image.id.clone()));
+                                    }
+                                    if ui_h.button("Delete").clicked() {
+                                        confirm_purge = Some(Some(image.id.clone()));
+                                    }
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+
+            ui.add_space(5.0);
+            ui.horizontal(|ui_h| {
+                if confirm_purge == Some(None) {
+                    ui_h.label(format!("Delete all {} lineups for good?", trash.lineups.len()));
+                    if ui_h.button("Yes").clicked() {
+                        action_queue.push(AppAction::PurgeTrash(None));
+                        confirm_purge = None;
+                    }
+                    if ui_h.button("No").clicked() {
+                        confirm_purge = None;
+                    }
+                } else if ui_h.button("Empty Trash").clicked() {
+                    confirm_purge = Some(None);
+                }
+            });
+        });
+
+    ctx.data_mut(|d| match confirm_purge {
+        Some(purge) if open => d.insert_temp(confirm_id, purge),
+        _ => {
+            d.remove::<Option<String>>(confirm_id);
+        }
+    });
+    if !open {
+        action_queue.push(AppAction::CloseTrash);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::persistence::NadeType;
+use crate::persistence::{ImageRole, Movement, NadeType, Setpos, Side, ThrowClick, ThrowTechnique};
 use eframe::egui;
 use rfd::FileDialog;
 use std::path::PathBuf;
@@ -7,8 +7,12 @@ use strum::IntoEnumIterator;
 // This struct will hold the local state for the upload modal.
 #[derive(Debug, Default)] // Added Default for easier initialization if needed elsewhere
 pub struct UploadModal {
-    pub file_path: Option<PathBuf>,
+    pub image_files: Vec<(PathBuf, ImageRole)>, // Lineup steps in order
     pub nade_type: NadeType,
+    pub side: Side,
+    pub technique: ThrowTechnique,
+    pub tags: Vec<String>,
+    pub setpos: Option<Setpos>, // Throw position and view angles from `getpos`
     pub position: String,
     pub notes: String,
     // Consider adding `pub error_message: Option<String>;` if modal needs to show specific errors
@@ -17,8 +21,12 @@ pub struct UploadModal {
 impl UploadModal {
     pub fn new() -> Self {
         Self {
-            file_path: None,
+            image_files: Vec::new(),
             nade_type: NadeType::Smoke, // Default nade type
+            side: Side::Either,
+            technique: ThrowTechnique::default(),
+            tags: Vec::new(),
+            setpos: None,
             position: String::new(),
             notes: String::new(),
         }
@@ -27,6 +35,8 @@ impl UploadModal {
 
 use crate::app_actions::AppAction; // Added
 use crate::app_state::AppState; // Added
+use crate::ui::getpos_input_view::show_getpos_input;
+use crate::ui::tag_editor_view::show_tag_editor;
 
 impl UploadModal {
     #[allow(clippy::too_many_lines)]
@@ -41,6 +51,7 @@ impl UploadModal {
         }
 
         let mut reset_and_close = false;
+        let known_tags: Vec<String> = app_state.image_manifest.tag_counts().into_keys().collect();
 
         egui::Window::new("Upload Image")
             .open(&mut app_state.show_upload_modal) // Directly use AppState's flag
@@ -49,22 +60,57 @@ impl UploadModal {
             .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
             .show(ctx, |ui| {
                 ui.vertical_centered_justified(|ui| {
-                    // File Picker
+                    // Lineup images, shown as steps in the detail view
+                    let mut remove_index = None;
+                    let mut move_up_index = None;
+                    for (index, (path, role)) in self.image_files.iter_mut().enumerate() {
+                        ui.horizontal(|ui_h| {
+                            ui_h.label(format!("{}.", index + 1));
+                            egui::ComboBox::from_id_salt(("upload_role_combo", index))
+                                .selected_text(format!("{:?}", role))
+                                .show_ui(ui_h, |ui_combo| {
+                                    for role_iter in ImageRole::iter() {
+                                        ui_combo.selectable_value(
+                                            role,
+                                            role_iter,
+                                            format!("{:?}", role_iter),
+                                        );
+                                    }
+                                });
+                            ui_h.label(
+                                path.file_name()
+                                    .and_then(|os| os.to_str())
+                                    .unwrap_or("Unnamed file"),
+                            );
+                            if index > 0
+                                && ui_h.small_button("⬆").on_hover_text("Move up").clicked()
+                            {
+                                move_up_index = Some(index);
+                            }
+                            if ui_h.small_button("✖").on_hover_text("Remove").clicked() {
+                                remove_index = Some(index);
+                            }
+                        });
+                    }
+                    if let Some(index) = move_up_index {
+                        self.image_files.swap(index - 1, index);
+                    }
+                    if let Some(index) = remove_index {
+                        self.image_files.remove(index);
+                    }
+
                     ui.horizontal(|ui_h| {
-                        ui_h.label("File:");
-                        let file_display_text = self
-                            .file_path
-                            .as_ref()
-                            .and_then(|p| p.file_name())
-                            .and_then(|os| os.to_str())
-                            .unwrap_or("No file selected");
-                        ui_h.label(file_display_text);
-                        if ui_h.button("Browse...").clicked() {
-                            if let Some(path) = FileDialog::new()
+                        if self.image_files.is_empty() {
+                            ui_h.label("No file selected");
+                        }
+                        if ui_h.button("Add Image...").clicked()
+                            && let Some(paths) = FileDialog::new()
                                 .add_filter("Image", &["png", "jpg", "jpeg", "gif"])
-                                .pick_file()
-                            {
-                                self.file_path = Some(path);
+                                .pick_files()
+                        {
+                            for path in paths {
+                                let role = ImageRole::for_step(self.image_files.len());
+                                self.image_files.push((path, role));
                             }
                         }
                     });
@@ -85,12 +131,82 @@ impl UploadModal {
                             });
                     });
 
+                    // Side
+                    ui.horizontal(|ui_h| {
+                        ui_h.label("Side:");
+                        egui::ComboBox::from_id_salt("upload_side_combo")
+                            .selected_text(format!("{:?}", self.side))
+                            .show_ui(ui_h, |ui_combo| {
+                                for side_iter in Side::iter() {
+                                    ui_combo.selectable_value(
+                                        &mut self.side,
+                                        side_iter,
+                                        format!("{:?}", side_iter),
+                                    );
+                                }
+                            });
+                    });
+
+                    // Throw technique
+                    ui.horizontal(|ui_h| {
+                        ui_h.label("Movement:");
+                        egui::ComboBox::from_id_salt("upload_movement_combo")
+                            .selected_text(format!("{:?}", self.technique.movement))
+                            .show_ui(ui_h, |ui_combo| {
+                                for movement_iter in Movement::iter() {
+                                    ui_combo.selectable_value(
+                                        &mut self.technique.movement,
+                                        movement_iter,
+                                        format!("{:?}", movement_iter),
+                                    );
+                                }
+                            });
+                        ui_h.label("Click:");
+                        egui::ComboBox::from_id_salt("upload_click_combo")
+                            .selected_text(format!("{:?}", self.technique.click))
+                            .show_ui(ui_h, |ui_combo| {
+                                for click_iter in ThrowClick::iter() {
+                                    ui_combo.selectable_value(
+                                        &mut self.technique.click,
+                                        click_iter,
+                                        format!("{:?}", click_iter),
+                                    );
+                                }
+                            });
+                    });
+                    ui.checkbox(
+                        &mut self.technique.jump_throw_bind,
+                        "Requires jump-throw bind",
+                    );
+
                     // Position
                     ui.horizontal(|ui_h| {
                         ui_h.label("Position:");
                         ui_h.text_edit_singleline(&mut self.position);
                     });
 
+                    // Throw position, pasted from the console `getpos` command
+                    ui.horizontal(|ui_h| {
+                        ui_h.label("getpos:");
+                        if let Some(setpos) = show_getpos_input(ui_h, "upload") {
+                            self.setpos = Some(setpos);
+                        }
+                    });
+                    if let Some(setpos) = self.setpos {
+                        ui.horizontal(|ui_h| {
+                            ui_h.weak(setpos.command());
+                            if ui_h.small_button("✖").on_hover_text("Clear").clicked() {
+                                self.setpos = None;
+                            }
+                        });
+                    }
+
+                    // Tags
+                    ui.horizontal(|ui_h| {
+                        ui_h.label("Tags:");
+                        show_tag_editor(ui_h, "upload", &mut self.tags, &known_tags);
+                    });
+
                     // Notes
                     ui.horizontal(|ui_h| {
                         ui_h.label("Notes:");
@@ -104,22 +220,25 @@ impl UploadModal {
                         if ui_h.button("Cancel").clicked() {
                             reset_and_close = true;
                         }
-                        // Enable confirm only if a file is selected
-                        let confirm_enabled = self.file_path.is_some();
+                        // Enable confirm only if at least one file is selected
+                        let confirm_enabled = !self.image_files.is_empty();
                         ui_h.add_enabled_ui(confirm_enabled, |ui_enabled_h| {
-                            if ui_enabled_h.button("Confirm Upload").clicked() {
-                                if let Some(file_path) = self.file_path.clone() {
-                                    // Shadow to ensure it's Some
-                                    action_queue.push(AppAction::SetProcessingUpload(true));
-                                    action_queue.push(AppAction::SubmitUpload {
-                                        file_path,
-                                        map_name: app_state.current_map.clone(), // Get map_name from AppState
-                                        nade_type: self.nade_type,               // NadeType is Copy
-                                        position: self.position.clone(),
-                                        notes: self.notes.clone(),
-                                    });
-                                    reset_and_close = true;
-                                }
+                            if ui_enabled_h.button("Confirm Upload").clicked()
+                                && !self.image_files.is_empty()
+                            {
+                                action_queue.push(AppAction::SetProcessingUpload(true));
+                                action_queue.push(AppAction::SubmitUpload {
+                                    image_files: self.image_files.clone(),
+                                    map_name: app_state.current_map.clone(), // Get map_name from AppState
+                                    nade_type: self.nade_type,               // NadeType is Copy
+                                    side: self.side,
+                                    technique: self.technique,
+                                    tags: self.tags.clone(),
+                                    setpos: self.setpos,
+                                    position: self.position.clone(),
+                                    notes: self.notes.clone(),
+                                });
+                                reset_and_close = true;
                             }
                         });
                     });